@@ -0,0 +1,405 @@
+//! TypeScript bindings for the IPC surface, generated from the Rust sources:
+//! every serde type in `models/mod.rs` becomes a TS type and every
+//! `#[tauri::command]` in `commands/mod.rs` a typed `invoke` wrapper.
+//!
+//! The output is checked in at `src/shared/bindings.ts`. `bun run bindings`
+//! regenerates it (the dev and build commands do so first), and
+//! `test_bindings_are_up_to_date` fails when the checked-in copy drifts.
+
+use std::collections::BTreeSet;
+use syn::parse::ParseStream;
+use syn::{Attribute, Fields, FnArg, GenericArgument, Item, LitStr, Pat, PathArguments, ReturnType, Token, Type};
+
+const MODELS_SOURCE: &str = include_str!("models/mod.rs");
+const COMMANDS_SOURCE: &str = include_str!("commands/mod.rs");
+const OUTPUT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../src/shared/bindings.ts");
+
+/// Parameters Tauri injects itself; they never cross the IPC boundary.
+const INJECTED_PARAMS: &[&str] = &["State", "AppHandle", "Window", "WebviewWindow"];
+
+#[derive(Default)]
+struct Output {
+    types: Vec<String>,
+    defined: BTreeSet<String>,
+    referenced: BTreeSet<String>,
+}
+
+pub fn generate() -> String {
+    let models = syn::parse_file(MODELS_SOURCE).expect("models/mod.rs should parse");
+    let commands = syn::parse_file(COMMANDS_SOURCE).expect("commands/mod.rs should parse");
+
+    let mut out = Output::default();
+    for item in &models.items {
+        match item {
+            Item::Struct(s) if is_serde(&s.attrs) => out.add_struct(s),
+            Item::Enum(e) if is_serde(&e.attrs) => out.add_enum(e),
+            Item::Macro(m) if m.mac.path.is_ident("string_enum") => out.add_string_enum(m),
+            _ => {}
+        }
+    }
+
+    let wrappers: Vec<String> = commands
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(f) if f.attrs.iter().any(|a| path_is(a.path(), "tauri::command")) => Some(out.command(f)),
+            _ => None,
+        })
+        .collect();
+
+    let missing: Vec<&String> = out.referenced.difference(&out.defined).collect();
+    assert!(missing.is_empty(), "types used over IPC but not defined in models/mod.rs: {:?}", missing);
+
+    format!(
+        "// Generated from src-tauri/src/models and src-tauri/src/commands.\n\
+         // Do not edit by hand: run `bun run bindings` to regenerate.\n\
+         \n\
+         import {{ invoke }} from '@tauri-apps/api/core'\n\
+         \n\
+         // ============================================================\n\
+         // Types\n\
+         // ============================================================\n\
+         \n\
+         {}\n\
+         // ============================================================\n\
+         // Commands\n\
+         // ============================================================\n\
+         \n\
+         export const commands = {{\n{}\n}}\n",
+        out.types.join("\n"),
+        wrappers.join(",\n"),
+    )
+}
+
+impl Output {
+    fn add_struct(&mut self, s: &syn::ItemStruct) {
+        let Fields::Named(ref fields) = s.fields else { return };
+        let rename_all = serde_value(&s.attrs, "rename_all");
+
+        let mut body = String::new();
+        for field in &fields.named {
+            if serde_flag(&field.attrs, "skip") {
+                continue;
+            }
+            let rust_name = field.ident.as_ref().unwrap().to_string();
+            let name = serde_value(&field.attrs, "rename").unwrap_or_else(|| rename(&rust_name, rename_all.as_deref()));
+            let (ty, optional) = match option_inner(&field.ty) {
+                Some(inner) => (self.nullable(inner), true),
+                None => (self.ts_type(&field.ty), serde_flag(&field.attrs, "default")),
+            };
+            body.push_str(&doc_comment(&field.attrs, "  "));
+            body.push_str(&format!("  {}{}: {}\n", name, if optional { "?" } else { "" }, ty));
+        }
+
+        let name = s.ident.to_string();
+        self.types.push(format!("{}export interface {} {{\n{}}}\n", doc_comment(&s.attrs, ""), name, body));
+        self.defined.insert(name);
+    }
+
+    fn add_enum(&mut self, e: &syn::ItemEnum) {
+        let rename_all = serde_value(&e.attrs, "rename_all");
+        let values: Vec<String> = e
+            .variants
+            .iter()
+            .map(|v| serde_value(&v.attrs, "rename").unwrap_or_else(|| rename(&v.ident.to_string(), rename_all.as_deref())))
+            .collect();
+        self.add_union(&e.ident.to_string(), &e.attrs, &values);
+    }
+
+    /// `string_enum! { Name { #[default] Variant => "value", ... } }`
+    fn add_string_enum(&mut self, m: &syn::ItemMacro) {
+        let (name, values) = m
+            .mac
+            .parse_body_with(|input: ParseStream| {
+                let name: syn::Ident = input.parse()?;
+                let content;
+                syn::braced!(content in input);
+                let mut values = Vec::new();
+                while !content.is_empty() {
+                    content.call(Attribute::parse_outer)?;
+                    content.parse::<syn::Ident>()?;
+                    content.parse::<Token![=>]>()?;
+                    values.push(content.parse::<LitStr>()?.value());
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+                Ok((name.to_string(), values))
+            })
+            .expect("string_enum! invocation should parse");
+        self.add_union(&name, &m.attrs, &values);
+    }
+
+    fn add_union(&mut self, name: &str, attrs: &[Attribute], values: &[String]) {
+        let variants: Vec<String> = values.iter().map(|v| format!("'{}'", v)).collect();
+        self.types.push(format!("{}export type {} = {}\n", doc_comment(attrs, ""), name, variants.join(" | ")));
+        self.defined.insert(name.to_string());
+    }
+
+    fn command(&mut self, f: &syn::ItemFn) -> String {
+        let mut params: Vec<(String, &Type)> = Vec::new();
+        for arg in &f.sig.inputs {
+            let FnArg::Typed(arg) = arg else { continue };
+            let Pat::Ident(ref pat) = *arg.pat else { continue };
+            if INJECTED_PARAMS.iter().any(|p| type_mentions(&arg.ty, p)) {
+                continue;
+            }
+            params.push((rename(&pat.ident.to_string(), Some("camelCase")), &arg.ty));
+        }
+
+        // `Option` params can be left out, as long as nothing required follows them
+        let required = params.iter().rposition(|(_, ty)| option_inner(ty).is_none()).map_or(0, |i| i + 1);
+        let signature: Vec<String> = params
+            .iter()
+            .enumerate()
+            .map(|(i, (name, ty))| match option_inner(ty) {
+                Some(inner) => format!("{}{}: {}", name, if i >= required { "?" } else { "" }, self.nullable(inner)),
+                None => format!("{}: {}", name, self.ts_type(ty)),
+            })
+            .collect();
+
+        let returns = match &f.sig.output {
+            ReturnType::Default => "void".to_string(),
+            ReturnType::Type(_, ty) => {
+                let ok = generic_args(ty, "Result").and_then(|args| args.first().copied()).unwrap_or(ty);
+                if is_unit(ok) { "void".to_string() } else { self.ts_type(ok) }
+            }
+        };
+
+        let command = f.sig.ident.to_string();
+        let args = if params.is_empty() {
+            String::new()
+        } else {
+            let names: Vec<&str> = params.iter().map(|(name, _)| name.as_str()).collect();
+            format!(", {{ {} }}", names.join(", "))
+        };
+        format!(
+            "  {}: ({}) =>\n    invoke<{}>('{}'{})",
+            rename(&command, Some("camelCase")),
+            signature.join(", "),
+            returns,
+            command,
+            args,
+        )
+    }
+
+    fn nullable(&mut self, ty: &Type) -> String {
+        match self.ts_type(ty) {
+            unknown if unknown == "unknown" => unknown,
+            ts => format!("{} | null", ts),
+        }
+    }
+
+    fn ts_type(&mut self, ty: &Type) -> String {
+        match ty {
+            Type::Reference(r) => self.ts_type(&r.elem),
+            Type::Tuple(t) if t.elems.is_empty() => "null".to_string(),
+            Type::Tuple(t) => {
+                let elems: Vec<String> = t.elems.iter().map(|e| self.ts_type(e)).collect();
+                format!("[{}]", elems.join(", "))
+            }
+            Type::Path(p) => {
+                let last = p.path.segments.last().expect("type path has a segment");
+                let args = generic_args(ty, &last.ident.to_string()).unwrap_or_default();
+                match (last.ident.to_string().as_str(), args.as_slice()) {
+                    ("String" | "str", _) => "string".to_string(),
+                    ("bool", _) => "boolean".to_string(),
+                    ("i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" | "f32" | "f64", _) => {
+                        "number".to_string()
+                    }
+                    ("Value", _) => "unknown".to_string(),
+                    ("Option", [inner]) => self.nullable(inner),
+                    ("Box", [inner]) => self.ts_type(inner),
+                    ("Vec", [inner]) => {
+                        let elem = self.ts_type(inner);
+                        if elem.contains(' ') { format!("({})[]", elem) } else { format!("{}[]", elem) }
+                    }
+                    ("HashMap" | "BTreeMap", [_, value]) => format!("Record<string, {}>", self.ts_type(value)),
+                    (name, []) => {
+                        self.referenced.insert(name.to_string());
+                        name.to_string()
+                    }
+                    (name, _) => panic!("no TypeScript mapping for generic type {}", name),
+                }
+            }
+            _ => panic!("no TypeScript mapping for this kind of type"),
+        }
+    }
+}
+
+// ============================================================
+// Syntax helpers
+// ============================================================
+
+fn is_serde(attrs: &[Attribute]) -> bool {
+    attrs.iter().filter(|a| a.path().is_ident("derive")).any(|a| {
+        let mut found = false;
+        let _ = a.parse_nested_meta(|meta| {
+            found |= meta.path.is_ident("Serialize") || meta.path.is_ident("Deserialize");
+            Ok(())
+        });
+        found
+    })
+}
+
+/// `#[serde(key = "value")]`
+fn serde_value(attrs: &[Attribute], key: &str) -> Option<String> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) {
+                value = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.input.peek(Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        });
+    }
+    value
+}
+
+/// `#[serde(key)]` or `#[serde(key = ...)]`
+fn serde_flag(attrs: &[Attribute], key: &str) -> bool {
+    let mut found = false;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            found |= meta.path.is_ident(key);
+            if meta.input.peek(Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        });
+    }
+    found
+}
+
+fn doc_comment(attrs: &[Attribute], indent: &str) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|a| match &a.meta {
+            syn::Meta::NameValue(nv) => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    match lines.as_slice() {
+        [] => String::new(),
+        [line] => format!("{}/** {} */\n", indent, line),
+        _ => {
+            let body: String = lines.iter().map(|l| format!("{} * {}\n", indent, l).replace(" * \n", " *\n")).collect();
+            format!("{}/**\n{}{} */\n", indent, body, indent)
+        }
+    }
+}
+
+fn rename(name: &str, rule: Option<&str>) -> String {
+    match rule {
+        Some("camelCase") => {
+            let mut out = String::new();
+            let mut upper = false;
+            for c in name.chars() {
+                if c == '_' {
+                    upper = true;
+                } else if upper {
+                    out.extend(c.to_uppercase());
+                    upper = false;
+                } else {
+                    out.push(c);
+                }
+            }
+            out
+        }
+        Some("lowercase") => name.to_lowercase(),
+        Some("SCREAMING_SNAKE_CASE") => {
+            let mut out = String::new();
+            for (i, c) in name.chars().enumerate() {
+                if c.is_uppercase() && i > 0 {
+                    out.push('_');
+                }
+                out.extend(c.to_uppercase());
+            }
+            out
+        }
+        None => name.to_string(),
+        Some(other) => panic!("unsupported serde rename_all rule {}", other),
+    }
+}
+
+fn path_is(path: &syn::Path, expected: &str) -> bool {
+    let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+    segments.join("::") == expected
+}
+
+/// Type arguments of `ty` when its last path segment is `name`, e.g. `T` in `Option<T>`.
+fn generic_args<'a>(ty: &'a Type, name: &str) -> Option<Vec<&'a Type>> {
+    let Type::Path(p) = ty else { return None };
+    let last = p.path.segments.last()?;
+    if last.ident != name {
+        return None;
+    }
+    match &last.arguments {
+        PathArguments::AngleBracketed(args) => Some(
+            args.args
+                .iter()
+                .filter_map(|a| match a {
+                    GenericArgument::Type(t) => Some(t),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => Some(Vec::new()),
+    }
+}
+
+/// `T` for `Option<T>`, looking through nested options: `Option<Option<T>>` is
+/// "missing = unchanged, null = cleared", which TS spells `field?: T | null`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let inner = *generic_args(ty, "Option")?.first()?;
+    Some(option_inner(inner).unwrap_or(inner))
+}
+
+fn is_unit(ty: &Type) -> bool {
+    matches!(ty, Type::Tuple(t) if t.elems.is_empty())
+}
+
+fn type_mentions(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Reference(r) => type_mentions(&r.elem, name),
+        Type::Path(p) => p.path.segments.iter().any(|s| s.ident == name),
+        _ => false,
+    }
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bindings_are_up_to_date() {
+        let generated = generate();
+        if std::env::var_os("UPDATE_BINDINGS").is_some() {
+            std::fs::write(OUTPUT_PATH, &generated).expect("write src/shared/bindings.ts");
+            return;
+        }
+        let checked_in = std::fs::read_to_string(OUTPUT_PATH).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "src/shared/bindings.ts is out of date with the Rust models/commands; run `bun run bindings`"
+        );
+    }
+
+    #[test]
+    fn test_nested_options_become_optional_nullable_fields() {
+        let generated = generate();
+        assert!(generated.contains("export interface UpdateTaskInput {\n  title?: string | null\n  description?: string | null\n"));
+        assert!(generated.contains("export type Priority = 'none' | 'low' | 'medium' | 'high'\n"));
+        assert!(generated.contains("  updateTask: (id: string, data: UpdateTaskInput) =>\n    invoke<Task>('update_task', { id, data })"));
+    }
+}
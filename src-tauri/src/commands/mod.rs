@@ -1,56 +1,72 @@
 use tauri::State;
 use crate::db::Database;
 use crate::models::*;
-use crate::services::{task, category, tag, search, statistics, data, notification, note};
+use crate::services::{task, bulk, attachment, dependency, view, project, template, time_entry, focus, pomodoro, category, tag, search, statistics, data, notification, note, backup, settings, integrity, trash, logbook, change_log, undo, workspace};
 use crate::services::notification::NotificationState;
+use crate::services::pomodoro::PomodoroState;
+use crate::services::undo::UndoState;
+use crate::services::workspace::WorkspaceState;
 
-// Commands return Result<T, String> where the String is a JSON-serialized AppError.
-// This allows the frontend to parse error details from the rejected promise.
+// Commands return Result<T, AppError>. Tauri serializes the error as the rejection
+// value, so the frontend receives `{ code, message, details? }` as an object.
 
 // ============================================================
 // Task Commands
 // ============================================================
 
 #[tauri::command]
-pub fn create_task(db: State<Database>, data: CreateTaskInput) -> Result<Task, String> {
-    let conn = db.conn.lock().unwrap();
-    task::create_task(&conn, data).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn create_task(db: State<Database>, undo_state: State<UndoState>, data: CreateTaskInput) -> Result<Task, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "createTask", || task::create_task(&conn, data))
 }
 
 #[tauri::command]
-pub fn update_task(db: State<Database>, id: String, data: UpdateTaskInput) -> Result<Task, String> {
-    let conn = db.conn.lock().unwrap();
-    task::update_task(&conn, &id, data).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn update_task(db: State<Database>, undo_state: State<UndoState>, id: String, data: UpdateTaskInput) -> Result<Task, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "updateTask", || task::update_task(&conn, &id, data))
 }
 
 #[tauri::command]
-pub fn delete_task(db: State<Database>, id: String) -> Result<(), String> {
-    let conn = db.conn.lock().unwrap();
-    task::delete_task(&conn, &id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn delete_task(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    id: String,
+) -> Result<(), AppError> {
+    let conn = db.write();
+    notification::cancel(&notification_state, &id);
+    undo::capture(&undo_state, &conn, "deleteTask", || task::delete_task(&conn, &id))
+}
+
+#[tauri::command]
+pub fn get_tasks(db: State<Database>, filter: Option<TaskFilter>) -> Result<Vec<Task>, AppError> {
+    let conn = db.read();
+    task::get_all_tasks(&conn, filter)
 }
 
 #[tauri::command]
-pub fn get_tasks(db: State<Database>, filter: Option<TaskFilter>) -> Result<Vec<Task>, String> {
-    let conn = db.conn.lock().unwrap();
-    task::get_all_tasks(&conn, filter).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_task_by_id(db: State<Database>, id: String) -> Result<Option<Task>, AppError> {
+    let conn = db.read();
+    task::get_task_by_id(&conn, &id)
 }
 
 #[tauri::command]
-pub fn get_task_by_id(db: State<Database>, id: String) -> Result<Option<Task>, String> {
-    let conn = db.conn.lock().unwrap();
-    task::get_task_by_id(&conn, &id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_view(db: State<Database>, kind: String) -> Result<Vec<Task>, AppError> {
+    let conn = db.read();
+    view::get_view(&conn, &kind)
 }
 
 #[tauri::command]
 pub fn complete_task(
     db: State<Database>,
     notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
     app: tauri::AppHandle,
     id: String,
-) -> Result<CompleteTaskResult, String> {
-    let conn = db.conn.lock().unwrap();
+) -> Result<CompleteTaskResult, AppError> {
+    let conn = db.write();
     notification::cancel(&notification_state, &id);
-    let result = task::complete_task(&conn, &id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))?;
+    let result = undo::capture(&undo_state, &conn, "completeTask", || task::complete_task(&conn, &id))?;
 
     // Schedule notification for next recurring task if applicable
     if let Some(ref next) = result.next_task {
@@ -63,15 +79,54 @@ pub fn complete_task(
 }
 
 #[tauri::command]
-pub fn uncomplete_task(db: State<Database>, id: String) -> Result<Task, String> {
-    let conn = db.conn.lock().unwrap();
-    task::uncomplete_task(&conn, &id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn uncomplete_task(db: State<Database>, undo_state: State<UndoState>, id: String) -> Result<Task, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "uncompleteTask", || task::uncomplete_task(&conn, &id))
+}
+
+#[tauri::command]
+pub fn reorder_tasks(db: State<Database>, undo_state: State<UndoState>, items: Vec<ReorderTaskItem>) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "reorderTasks", || task::reorder_tasks(&conn, items))
 }
 
 #[tauri::command]
-pub fn reorder_tasks(db: State<Database>, items: Vec<ReorderTaskItem>) -> Result<(), String> {
-    let conn = db.conn.lock().unwrap();
-    task::reorder_tasks(&conn, items).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn duplicate_task(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    app: tauri::AppHandle,
+    id: String,
+    options: Option<DuplicateTaskOptions>,
+) -> Result<Task, AppError> {
+    let conn = db.write();
+    let copy = undo::capture(&undo_state, &conn, "duplicateTask", || {
+        task::duplicate_task(&conn, &id, options.unwrap_or_default())
+    })?;
+    sync_reminder(&conn, &notification_state, &app, &copy.id);
+    Ok(copy)
+}
+
+#[tauri::command]
+pub fn bulk_update_tasks(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    app: tauri::AppHandle,
+    data: BulkUpdateTasksInput,
+) -> Result<BulkUpdateResult, AppError> {
+    let conn = db.write();
+    let result = undo::capture(&undo_state, &conn, "bulkUpdateTasks", || bulk::bulk_update_tasks(&conn, data))?;
+
+    if result.applied {
+        for item in &result.results {
+            sync_reminder(&conn, &notification_state, &app, &item.id);
+            if let Some(ref next) = item.next_task {
+                sync_reminder(&conn, &notification_state, &app, &next.id);
+            }
+        }
+    }
+    Ok(result)
 }
 
 // ============================================================
@@ -81,25 +136,63 @@ pub fn reorder_tasks(db: State<Database>, items: Vec<ReorderTaskItem>) -> Result
 #[tauri::command]
 pub fn create_sub_task(
     db: State<Database>,
+    undo_state: State<UndoState>,
     task_id: String,
     data: CreateSubTaskInput,
     parent_id: Option<String>,
-) -> Result<SubTask, String> {
-    let conn = db.conn.lock().unwrap();
-    task::create_sub_task(&conn, &task_id, data, parent_id)
-        .map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+) -> Result<SubTask, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "createSubTask", || task::create_sub_task(&conn, &task_id, data, parent_id))
 }
 
 #[tauri::command]
-pub fn update_sub_task(db: State<Database>, id: String, data: UpdateSubTaskInput) -> Result<SubTask, String> {
-    let conn = db.conn.lock().unwrap();
-    task::update_sub_task(&conn, &id, data).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn update_sub_task(db: State<Database>, undo_state: State<UndoState>, id: String, data: UpdateSubTaskInput) -> Result<SubTask, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "updateSubTask", || task::update_sub_task(&conn, &id, data))
 }
 
 #[tauri::command]
-pub fn delete_sub_task(db: State<Database>, id: String) -> Result<(), String> {
-    let conn = db.conn.lock().unwrap();
-    task::delete_sub_task(&conn, &id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn delete_sub_task(db: State<Database>, undo_state: State<UndoState>, id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "deleteSubTask", || task::delete_sub_task(&conn, &id))
+}
+
+#[tauri::command]
+pub fn move_sub_task(
+    db: State<Database>,
+    undo_state: State<UndoState>,
+    id: String,
+    new_task_id: String,
+    new_parent_id: Option<String>,
+    position: Option<i64>,
+) -> Result<SubTask, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "moveSubTask", || {
+        task::move_sub_task(&conn, &id, &new_task_id, new_parent_id, position)
+    })
+}
+
+#[tauri::command]
+pub fn promote_sub_task(db: State<Database>, undo_state: State<UndoState>, id: String) -> Result<Task, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "promoteSubTask", || task::promote_sub_task(&conn, &id))
+}
+
+#[tauri::command]
+pub fn demote_task(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    task_id: String,
+    target_task_id: String,
+    parent_id: Option<String>,
+) -> Result<SubTask, AppError> {
+    let conn = db.write();
+    let sub_task = undo::capture(&undo_state, &conn, "demoteTask", || {
+        task::demote_task(&conn, &task_id, &target_task_id, parent_id)
+    })?;
+    notification::cancel(&notification_state, &task_id);
+    Ok(sub_task)
 }
 
 // ============================================================
@@ -107,27 +200,27 @@ pub fn delete_sub_task(db: State<Database>, id: String) -> Result<(), String> {
 // ============================================================
 
 #[tauri::command]
-pub fn create_category(db: State<Database>, data: CreateCategoryInput) -> Result<Category, String> {
-    let conn = db.conn.lock().unwrap();
-    category::create_category(&conn, data).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn create_category(db: State<Database>, data: CreateCategoryInput) -> Result<Category, AppError> {
+    let conn = db.write();
+    category::create_category(&conn, data)
 }
 
 #[tauri::command]
-pub fn update_category(db: State<Database>, id: String, data: UpdateCategoryInput) -> Result<Category, String> {
-    let conn = db.conn.lock().unwrap();
-    category::update_category(&conn, &id, data).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn update_category(db: State<Database>, id: String, data: UpdateCategoryInput) -> Result<Category, AppError> {
+    let conn = db.write();
+    category::update_category(&conn, &id, data)
 }
 
 #[tauri::command]
-pub fn delete_category(db: State<Database>, id: String) -> Result<(), String> {
-    let conn = db.conn.lock().unwrap();
-    category::delete_category(&conn, &id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn delete_category(db: State<Database>, id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    category::delete_category(&conn, &id)
 }
 
 #[tauri::command]
-pub fn get_categories(db: State<Database>) -> Result<Vec<Category>, String> {
-    let conn = db.conn.lock().unwrap();
-    category::get_all_categories(&conn).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_categories(db: State<Database>) -> Result<Vec<Category>, AppError> {
+    let conn = db.read();
+    category::get_all_categories(&conn)
 }
 
 // ============================================================
@@ -135,39 +228,274 @@ pub fn get_categories(db: State<Database>) -> Result<Vec<Category>, String> {
 // ============================================================
 
 #[tauri::command]
-pub fn create_tag(db: State<Database>, name: String, color: Option<String>) -> Result<Tag, String> {
-    let conn = db.conn.lock().unwrap();
-    tag::create_tag(&conn, &name, color).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn create_tag(db: State<Database>, name: String, color: Option<String>) -> Result<Tag, AppError> {
+    let conn = db.write();
+    tag::create_tag(&conn, &name, color)
+}
+
+#[tauri::command]
+pub fn update_tag(db: State<Database>, id: String, name: String, color: Option<String>) -> Result<Tag, AppError> {
+    let conn = db.write();
+    tag::update_tag(&conn, &id, &name, color)
+}
+
+#[tauri::command]
+pub fn delete_tag(db: State<Database>, id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    tag::delete_tag(&conn, &id)
+}
+
+#[tauri::command]
+pub fn get_tags(db: State<Database>) -> Result<Vec<Tag>, AppError> {
+    let conn = db.read();
+    tag::get_all_tags(&conn)
+}
+
+#[tauri::command]
+pub fn add_tag_to_task(db: State<Database>, undo_state: State<UndoState>, task_id: String, tag_id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "addTagToTask", || tag::add_tag_to_task(&conn, &task_id, &tag_id))
+}
+
+#[tauri::command]
+pub fn remove_tag_from_task(db: State<Database>, undo_state: State<UndoState>, task_id: String, tag_id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "removeTagFromTask", || tag::remove_tag_from_task(&conn, &task_id, &tag_id))
+}
+
+// ============================================================
+// Dependency Commands
+// ============================================================
+
+#[tauri::command]
+pub fn add_task_dependency(db: State<Database>, undo_state: State<UndoState>, task_id: String, depends_on_id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "addTaskDependency", || dependency::add_dependency(&conn, &task_id, &depends_on_id))
+}
+
+#[tauri::command]
+pub fn remove_task_dependency(db: State<Database>, undo_state: State<UndoState>, task_id: String, depends_on_id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "removeTaskDependency", || dependency::remove_dependency(&conn, &task_id, &depends_on_id))
+}
+
+// ============================================================
+// Project Commands
+// ============================================================
+
+#[tauri::command]
+pub fn create_project(db: State<Database>, undo_state: State<UndoState>, data: CreateProjectInput) -> Result<Project, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "createProject", || project::create_project(&conn, data))
+}
+
+#[tauri::command]
+pub fn update_project(db: State<Database>, undo_state: State<UndoState>, id: String, data: UpdateProjectInput) -> Result<Project, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "updateProject", || project::update_project(&conn, &id, data))
+}
+
+#[tauri::command]
+pub fn delete_project(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    id: String,
+) -> Result<(), AppError> {
+    let conn = db.write();
+    for task_id in project::live_task_ids(&conn, &id)? {
+        notification::cancel(&notification_state, &task_id);
+    }
+    undo::capture(&undo_state, &conn, "deleteProject", || project::delete_project(&conn, &id))
+}
+
+#[tauri::command]
+pub fn get_projects(db: State<Database>) -> Result<Vec<Project>, AppError> {
+    let conn = db.read();
+    project::get_projects(&conn)
+}
+
+#[tauri::command]
+pub fn get_project_by_id(db: State<Database>, id: String) -> Result<Option<Project>, AppError> {
+    let conn = db.read();
+    project::get_project_by_id(&conn, &id)
+}
+
+/// `remaining_tasks` is "complete" or "cancel"; leave it out to keep open tasks open.
+#[tauri::command]
+pub fn complete_project(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    app: tauri::AppHandle,
+    id: String,
+    remaining_tasks: Option<String>,
+) -> Result<Project, AppError> {
+    let conn = db.write();
+    let result = undo::capture(&undo_state, &conn, "completeProject", || {
+        project::complete_project(&conn, &id, remaining_tasks.as_deref())
+    })?;
+    for task_id in project::live_task_ids(&conn, &id)? {
+        sync_reminder(&conn, &notification_state, &app, &task_id);
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn reopen_project(db: State<Database>, undo_state: State<UndoState>, id: String) -> Result<Project, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "reopenProject", || project::reopen_project(&conn, &id))
+}
+
+#[tauri::command]
+pub fn create_heading(db: State<Database>, undo_state: State<UndoState>, project_id: String, title: String) -> Result<Heading, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "createHeading", || project::create_heading(&conn, &project_id, &title))
+}
+
+#[tauri::command]
+pub fn update_heading(db: State<Database>, undo_state: State<UndoState>, id: String, data: UpdateHeadingInput) -> Result<Heading, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "updateHeading", || project::update_heading(&conn, &id, data))
+}
+
+#[tauri::command]
+pub fn delete_heading(db: State<Database>, undo_state: State<UndoState>, id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "deleteHeading", || project::delete_heading(&conn, &id))
+}
+
+// ============================================================
+// Template Commands
+// ============================================================
+
+#[tauri::command]
+pub fn get_templates(db: State<Database>) -> Result<Vec<TaskTemplate>, AppError> {
+    let conn = db.read();
+    template::get_templates(&conn)
+}
+
+#[tauri::command]
+pub fn create_template(db: State<Database>, undo_state: State<UndoState>, data: CreateTemplateInput) -> Result<TaskTemplate, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "createTemplate", || template::create_template(&conn, data))
+}
+
+#[tauri::command]
+pub fn create_template_from_task(
+    db: State<Database>,
+    undo_state: State<UndoState>,
+    task_id: String,
+    name: String,
+    base_date: Option<String>,
+) -> Result<TaskTemplate, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "createTemplate", || {
+        template::create_template_from_task(&conn, &task_id, &name, base_date)
+    })
+}
+
+#[tauri::command]
+pub fn update_template(db: State<Database>, undo_state: State<UndoState>, id: String, data: UpdateTemplateInput) -> Result<TaskTemplate, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "updateTemplate", || template::update_template(&conn, &id, data))
+}
+
+#[tauri::command]
+pub fn delete_template(db: State<Database>, undo_state: State<UndoState>, id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "deleteTemplate", || template::delete_template(&conn, &id))
+}
+
+#[tauri::command]
+pub fn instantiate_template(
+    db: State<Database>,
+    undo_state: State<UndoState>,
+    id: String,
+    data: Option<InstantiateTemplateInput>,
+) -> Result<Task, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "instantiateTemplate", || {
+        template::instantiate_template(&conn, &id, data.unwrap_or_default())
+    })
+}
+
+// ============================================================
+// Time Tracking Commands
+// ============================================================
+
+#[tauri::command]
+pub fn start_timer(db: State<Database>, undo_state: State<UndoState>, task_id: String, note: Option<String>) -> Result<TimeEntry, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "startTimer", || time_entry::start_timer(&conn, &task_id, note))
+}
+
+#[tauri::command]
+pub fn stop_timer(db: State<Database>, undo_state: State<UndoState>) -> Result<Option<TimeEntry>, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "stopTimer", || time_entry::stop_timer(&conn))
+}
+
+#[tauri::command]
+pub fn get_running_timer(db: State<Database>) -> Result<Option<TimeEntry>, AppError> {
+    let conn = db.read();
+    time_entry::get_running_timer(&conn)
+}
+
+#[tauri::command]
+pub fn get_time_entries(db: State<Database>, task_id: String) -> Result<Vec<TimeEntry>, AppError> {
+    let conn = db.read();
+    time_entry::get_time_entries(&conn, &task_id)
+}
+
+#[tauri::command]
+pub fn create_time_entry(db: State<Database>, undo_state: State<UndoState>, data: CreateTimeEntryInput) -> Result<TimeEntry, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "createTimeEntry", || time_entry::create_time_entry(&conn, data))
 }
 
 #[tauri::command]
-pub fn update_tag(db: State<Database>, id: String, name: String, color: Option<String>) -> Result<Tag, String> {
-    let conn = db.conn.lock().unwrap();
-    tag::update_tag(&conn, &id, &name, color).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn update_time_entry(db: State<Database>, undo_state: State<UndoState>, id: String, data: UpdateTimeEntryInput) -> Result<TimeEntry, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "updateTimeEntry", || time_entry::update_time_entry(&conn, &id, data))
 }
 
 #[tauri::command]
-pub fn delete_tag(db: State<Database>, id: String) -> Result<(), String> {
-    let conn = db.conn.lock().unwrap();
-    tag::delete_tag(&conn, &id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn delete_time_entry(db: State<Database>, undo_state: State<UndoState>, id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "deleteTimeEntry", || time_entry::delete_time_entry(&conn, &id))
+}
+
+// ============================================================
+// Pomodoro Commands
+// ============================================================
+
+#[tauri::command]
+pub fn start_pomodoro(
+    db: State<Database>,
+    pomodoro_state: State<PomodoroState>,
+    app: tauri::AppHandle,
+    task_id: Option<String>,
+) -> Result<PomodoroStatus, AppError> {
+    let conn = db.write();
+    pomodoro::start(&pomodoro_state, &app, &conn, task_id)
 }
 
 #[tauri::command]
-pub fn get_tags(db: State<Database>) -> Result<Vec<Tag>, String> {
-    let conn = db.conn.lock().unwrap();
-    tag::get_all_tags(&conn).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn stop_pomodoro(db: State<Database>, pomodoro_state: State<PomodoroState>) -> Result<Option<FocusSession>, AppError> {
+    let conn = db.write();
+    pomodoro::stop(&pomodoro_state, &conn)
 }
 
 #[tauri::command]
-pub fn add_tag_to_task(db: State<Database>, task_id: String, tag_id: String) -> Result<(), String> {
-    let conn = db.conn.lock().unwrap();
-    tag::add_tag_to_task(&conn, &task_id, &tag_id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_pomodoro_status(pomodoro_state: State<PomodoroState>) -> Option<PomodoroStatus> {
+    pomodoro::status(&pomodoro_state)
 }
 
 #[tauri::command]
-pub fn remove_tag_from_task(db: State<Database>, task_id: String, tag_id: String) -> Result<(), String> {
-    let conn = db.conn.lock().unwrap();
-    tag::remove_tag_from_task(&conn, &task_id, &tag_id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_focus_sessions(db: State<Database>, task_id: Option<String>) -> Result<Vec<FocusSession>, AppError> {
+    let conn = db.read();
+    focus::get_focus_sessions(&conn, task_id.as_deref())
 }
 
 // ============================================================
@@ -175,9 +503,9 @@ pub fn remove_tag_from_task(db: State<Database>, task_id: String, tag_id: String
 // ============================================================
 
 #[tauri::command]
-pub fn search_tasks(db: State<Database>, query: Option<String>, filters: Option<TaskFilter>) -> Result<Vec<Task>, String> {
-    let conn = db.conn.lock().unwrap();
-    search::search_tasks(&conn, query, filters).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn search_tasks(db: State<Database>, query: Option<String>, filters: Option<TaskFilter>) -> Result<Vec<Task>, AppError> {
+    let conn = db.read();
+    search::search_tasks(&conn, query, filters)
 }
 
 // ============================================================
@@ -191,13 +519,13 @@ pub fn schedule_notification(
     task_id: String,
     title: String,
     reminder_time: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     notification::schedule(&notification_state, &app, &task_id, &title, &reminder_time);
     Ok(())
 }
 
 #[tauri::command]
-pub fn cancel_notification(notification_state: State<NotificationState>, task_id: String) -> Result<(), String> {
+pub fn cancel_notification(notification_state: State<NotificationState>, task_id: String) -> Result<(), AppError> {
     notification::cancel(&notification_state, &task_id);
     Ok(())
 }
@@ -207,15 +535,33 @@ pub fn cancel_notification(notification_state: State<NotificationState>, task_id
 // ============================================================
 
 #[tauri::command]
-pub fn get_stats(db: State<Database>, period: String) -> Result<StatsSummary, String> {
-    let conn = db.conn.lock().unwrap();
-    statistics::get_stats(&conn, &period).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_stats(db: State<Database>, period: String) -> Result<StatsSummary, AppError> {
+    let conn = db.read();
+    statistics::get_stats(&conn, &period)
 }
 
 #[tauri::command]
-pub fn get_daily_trend(db: State<Database>, days: i64) -> Result<Vec<DailyTrend>, String> {
-    let conn = db.conn.lock().unwrap();
-    statistics::get_daily_trend(&conn, days).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_daily_trend(db: State<Database>, days: i64) -> Result<Vec<DailyTrend>, AppError> {
+    let conn = db.read();
+    statistics::get_daily_trend(&conn, days)
+}
+
+#[tauri::command]
+pub fn get_focus_trend(db: State<Database>, days: i64) -> Result<Vec<FocusTrend>, AppError> {
+    let conn = db.read();
+    statistics::get_focus_trend(&conn, days)
+}
+
+#[tauri::command]
+pub fn get_focus_by_category(db: State<Database>, days: i64) -> Result<Vec<FocusByCategory>, AppError> {
+    let conn = db.read();
+    statistics::get_focus_by_category(&conn, days)
+}
+
+#[tauri::command]
+pub fn get_time_stats(db: State<Database>, from: String, to: String) -> Result<TimeStats, AppError> {
+    let conn = db.read();
+    statistics::get_time_stats(&conn, &from, &to)
 }
 
 // ============================================================
@@ -223,15 +569,261 @@ pub fn get_daily_trend(db: State<Database>, days: i64) -> Result<Vec<DailyTrend>
 // ============================================================
 
 #[tauri::command]
-pub fn export_data(db: State<Database>) -> Result<String, String> {
-    let conn = db.conn.lock().unwrap();
-    data::export_data(&conn).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn export_data(
+    db: State<Database>,
+    workspaces: State<WorkspaceState>,
+    include_attachments: Option<bool>,
+) -> Result<String, AppError> {
+    let conn = db.read();
+    let attachment_dir = workspaces.attachment_dir(&db.path());
+    data::export_data(&conn, include_attachments.unwrap_or(false).then_some(attachment_dir.as_path()))
+}
+
+#[tauri::command]
+pub fn import_data(
+    db: State<Database>,
+    workspaces: State<WorkspaceState>,
+    undo_state: State<UndoState>,
+    json_str: String,
+) -> Result<(), AppError> {
+    let conn = db.write();
+    data::import_data(&conn, &json_str, &workspaces.attachment_dir(&db.path()))?;
+    undo::clear(&undo_state);
+    Ok(())
+}
+
+// ============================================================
+// Backup Commands
+// ============================================================
+
+#[tauri::command]
+pub fn list_backups(db: State<Database>) -> Result<Vec<BackupInfo>, AppError> {
+    backup::list_backups(&db.path(), db.backup_dir())
 }
 
 #[tauri::command]
-pub fn import_data(db: State<Database>, json_str: String) -> Result<(), String> {
-    let conn = db.conn.lock().unwrap();
-    data::import_data(&conn, &json_str).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn create_backup(db: State<Database>) -> Result<BackupInfo, AppError> {
+    backup::create_manual_backup(&db)
+}
+
+#[tauri::command]
+pub fn restore_backup(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    app: tauri::AppHandle,
+    file_name: String,
+) -> Result<(), AppError> {
+    backup::restore_backup(&db, &file_name)?;
+    undo::clear(&undo_state);
+
+    // Scheduled reminders belong to the replaced data
+    notification::clear_all(&notification_state);
+    let conn = db.read();
+    notification::schedule_all_future_reminders(&conn, &notification_state, &app);
+    Ok(())
+}
+
+// ============================================================
+// Trash Commands
+// ============================================================
+
+#[tauri::command]
+pub fn get_trash(db: State<Database>) -> Result<Vec<TrashItem>, AppError> {
+    let conn = db.read();
+    trash::get_trash(&conn)
+}
+
+#[tauri::command]
+pub fn restore_from_trash(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    app: tauri::AppHandle,
+    entity_type: String,
+    id: String,
+) -> Result<(), AppError> {
+    let conn = db.write();
+    trash::restore_from_trash(&conn, &entity_type, &id)?;
+
+    // A restored task gets its pending reminder back
+    if entity_type == change_log::TASK {
+        sync_reminder(&conn, &notification_state, &app, &id);
+    } else if entity_type == change_log::PROJECT {
+        for task_id in project::live_task_ids(&conn, &id)? {
+            sync_reminder(&conn, &notification_state, &app, &task_id);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn empty_trash(db: State<Database>, workspaces: State<WorkspaceState>) -> Result<usize, AppError> {
+    let conn = db.write();
+    let removed = trash::purge_trash(&conn, None)?;
+    // Files of purged attachments; the maintenance loop retries on failure
+    if let Err(e) = attachment::remove_unreferenced(&conn, &workspaces.attachment_dir(&db.path())) {
+        log::error!("Removing attachment files failed: {}", e);
+    }
+    Ok(removed)
+}
+
+// ============================================================
+// Logbook Commands
+// ============================================================
+
+#[tauri::command]
+pub fn get_logbook(db: State<Database>, page: Option<i64>, page_size: Option<i64>) -> Result<LogbookPage, AppError> {
+    let conn = db.read();
+    logbook::get_logbook(&conn, page, page_size)
+}
+
+#[tauri::command]
+pub fn unarchive_task(db: State<Database>, undo_state: State<UndoState>, id: String) -> Result<Task, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "unarchiveTask", || logbook::unarchive_task(&conn, &id))
+}
+
+// ============================================================
+// History Commands
+// ============================================================
+
+#[tauri::command]
+pub fn undo(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    app: tauri::AppHandle,
+) -> Result<Option<UndoResult>, AppError> {
+    let conn = db.write();
+    let result = undo::undo(&undo_state, &conn)?;
+    sync_changed_reminders(&conn, &notification_state, &app, result.as_ref());
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn redo(
+    db: State<Database>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    app: tauri::AppHandle,
+) -> Result<Option<UndoResult>, AppError> {
+    let conn = db.write();
+    let result = undo::redo(&undo_state, &conn)?;
+    sync_changed_reminders(&conn, &notification_state, &app, result.as_ref());
+    Ok(result)
+}
+
+fn sync_changed_reminders(
+    conn: &rusqlite::Connection,
+    notification_state: &NotificationState,
+    app: &tauri::AppHandle,
+    result: Option<&UndoResult>,
+) {
+    for change in result.map(|r| r.changes.as_slice()).unwrap_or_default() {
+        if change.entity_type == change_log::TASK {
+            sync_reminder(conn, notification_state, app, &change.entity_id);
+        }
+    }
+}
+
+/// Cancel a task's reminder, then schedule it again if the task is live, open and
+/// the reminder is still ahead.
+fn sync_reminder(conn: &rusqlite::Connection, notification_state: &NotificationState, app: &tauri::AppHandle, id: &str) {
+    notification::cancel(notification_state, id);
+    let Ok(Some(task)) = task::get_task_by_id(conn, id) else { return };
+    let Some(ref reminder) = task.reminder_time else { return };
+    let in_future = chrono::DateTime::parse_from_rfc3339(reminder)
+        .map(|dt| dt.with_timezone(&chrono::Utc) > chrono::Utc::now())
+        .unwrap_or(false);
+    if task.status == TaskStatus::Todo && in_future {
+        notification::schedule(notification_state, app, &task.id, &task.title, reminder);
+    }
+}
+
+#[tauri::command]
+pub fn get_entity_history(db: State<Database>, entity: String, id: String) -> Result<Vec<ChangeLogEntry>, AppError> {
+    let conn = db.read();
+    change_log::get_entity_history(&conn, &entity, &id)
+}
+
+// ============================================================
+// Workspace Commands
+// ============================================================
+
+#[tauri::command]
+pub fn list_workspaces(workspaces: State<WorkspaceState>) -> Result<Vec<Workspace>, AppError> {
+    Ok(workspace::list_workspaces(&workspaces))
+}
+
+#[tauri::command]
+pub fn create_workspace(workspaces: State<WorkspaceState>, name: String) -> Result<Workspace, AppError> {
+    workspace::create_workspace(&workspaces, &name)
+}
+
+#[tauri::command]
+pub fn rename_workspace(workspaces: State<WorkspaceState>, id: String, name: String) -> Result<Workspace, AppError> {
+    workspace::rename_workspace(&workspaces, &id, &name)
+}
+
+#[tauri::command]
+pub fn delete_workspace(workspaces: State<WorkspaceState>, id: String) -> Result<(), AppError> {
+    workspace::delete_workspace(&workspaces, &id)
+}
+
+#[tauri::command]
+pub fn switch_workspace(
+    db: State<Database>,
+    workspaces: State<WorkspaceState>,
+    notification_state: State<NotificationState>,
+    undo_state: State<UndoState>,
+    pomodoro_state: State<PomodoroState>,
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<Workspace, AppError> {
+    // A running focus session is recorded in the workspace it was started in, so
+    // it's stopped there, once the switch is sure to go ahead
+    let switched = workspace::switch_workspace(&workspaces, &db, &id, |conn| {
+        pomodoro::stop(&pomodoro_state, conn).map(|_| ())
+    })?;
+
+    // Reminders and undo history belong to the workspace we just left
+    notification::clear_all(&notification_state);
+    undo::clear(&undo_state);
+    let conn = db.read();
+    notification::schedule_all_future_reminders(&conn, &notification_state, &app);
+    Ok(switched)
+}
+
+// ============================================================
+// Maintenance Commands
+// ============================================================
+
+#[tauri::command]
+pub fn check_database(db: State<Database>) -> Result<DatabaseReport, AppError> {
+    let conn = db.read();
+    integrity::check_database(&conn)
+}
+
+#[tauri::command]
+pub fn repair_database(db: State<Database>) -> Result<RepairReport, AppError> {
+    let conn = db.write();
+    integrity::repair_database(&conn)
+}
+
+// ============================================================
+// Settings Commands
+// ============================================================
+
+#[tauri::command]
+pub fn get_settings(db: State<Database>) -> Result<AppSettings, AppError> {
+    let conn = db.read();
+    settings::get_settings(&conn)
+}
+
+#[tauri::command]
+pub fn update_settings(db: State<Database>, data: UpdateSettingsInput) -> Result<AppSettings, AppError> {
+    let conn = db.write();
+    settings::update_settings(&conn, data)
 }
 
 // ============================================================
@@ -239,39 +831,67 @@ pub fn import_data(db: State<Database>, json_str: String) -> Result<(), String>
 // ============================================================
 
 #[tauri::command]
-pub fn create_note(db: State<Database>, data: CreateNoteInput) -> Result<Note, String> {
-    let conn = db.conn.lock().unwrap();
-    note::create_note(&conn, data).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn create_note(db: State<Database>, undo_state: State<UndoState>, data: CreateNoteInput) -> Result<Note, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "createNote", || note::create_note(&conn, data))
+}
+
+#[tauri::command]
+pub fn update_note(db: State<Database>, undo_state: State<UndoState>, id: String, data: UpdateNoteInput) -> Result<Note, AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "updateNote", || note::update_note(&conn, &id, data))
+}
+
+#[tauri::command]
+pub fn delete_note(db: State<Database>, undo_state: State<UndoState>, id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    undo::capture(&undo_state, &conn, "deleteNote", || note::delete_note(&conn, &id))
 }
 
 #[tauri::command]
-pub fn update_note(db: State<Database>, id: String, data: UpdateNoteInput) -> Result<Note, String> {
-    let conn = db.conn.lock().unwrap();
-    note::update_note(&conn, &id, data).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_notes(db: State<Database>) -> Result<Vec<Note>, AppError> {
+    let conn = db.read();
+    note::get_all_notes(&conn)
 }
 
 #[tauri::command]
-pub fn delete_note(db: State<Database>, id: String) -> Result<(), String> {
-    let conn = db.conn.lock().unwrap();
-    note::delete_note(&conn, &id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_note_by_id(db: State<Database>, id: String) -> Result<Option<Note>, AppError> {
+    let conn = db.read();
+    note::get_note_by_id(&conn, &id)
+}
+
+#[tauri::command]
+pub fn search_notes(db: State<Database>, query: Option<String>) -> Result<Vec<Note>, AppError> {
+    let conn = db.read();
+    note::search_notes(&conn, query)
+}
+
+// ============================================================
+// Attachment Commands
+// ============================================================
+
+#[tauri::command]
+pub fn get_attachments(db: State<Database>, task_id: Option<String>, note_id: Option<String>) -> Result<Vec<Attachment>, AppError> {
+    let conn = db.read();
+    attachment::get_attachments(&conn, task_id.as_deref(), note_id.as_deref())
 }
 
 #[tauri::command]
-pub fn get_notes(db: State<Database>) -> Result<Vec<Note>, String> {
-    let conn = db.conn.lock().unwrap();
-    note::get_all_notes(&conn).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn add_attachment(db: State<Database>, workspaces: State<WorkspaceState>, data: AddAttachmentInput) -> Result<Attachment, AppError> {
+    let conn = db.write();
+    attachment::add_attachment(&conn, &workspaces.attachment_dir(&db.path()), data)
 }
 
 #[tauri::command]
-pub fn get_note_by_id(db: State<Database>, id: String) -> Result<Option<Note>, String> {
-    let conn = db.conn.lock().unwrap();
-    note::get_note_by_id(&conn, &id).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn delete_attachment(db: State<Database>, workspaces: State<WorkspaceState>, id: String) -> Result<(), AppError> {
+    let conn = db.write();
+    attachment::delete_attachment(&conn, &workspaces.attachment_dir(&db.path()), &id)
 }
 
 #[tauri::command]
-pub fn search_notes(db: State<Database>, query: Option<String>) -> Result<Vec<Note>, String> {
-    let conn = db.conn.lock().unwrap();
-    note::search_notes(&conn, query).map_err(|e| serde_json::to_string(&e).unwrap_or(e.message))
+pub fn get_attachment_path(db: State<Database>, workspaces: State<WorkspaceState>, id: String) -> Result<String, AppError> {
+    let conn = db.read();
+    attachment::get_attachment_path(&conn, &workspaces.attachment_dir(&db.path()), &id)
 }
 
 // ============================================================
@@ -295,12 +915,12 @@ pub fn set_compact_mode(
     app: tauri::AppHandle,
     saved_geometry: State<SavedWindowGeometry>,
     compact: bool,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     use tauri::Manager;
 
     let window = app
         .get_webview_window("main")
-        .ok_or_else(|| "Main window not found".to_string())?;
+        .ok_or_else(|| AppError::not_found("Main window not found"))?;
 
     if compact {
         // Save current window position and size before shrinking,
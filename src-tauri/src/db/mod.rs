@@ -1,29 +1,137 @@
-use rusqlite::{Connection, params};
-use std::path::Path;
-use std::sync::Mutex;
+mod pool;
 
-/// Global database state managed by Tauri
+use rusqlite::{Connection, DatabaseName, params};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, RwLock};
+
+use crate::services::backup;
+
+pub use pool::PooledConnection;
+use pool::ReadPool;
+
+/// Number of read-only connections kept open next to the writer.
+const READ_POOL_SIZE: usize = 4;
+
+/// Global database state managed by Tauri.
+///
+/// All writes go through a single connection; reads are served from a small pool of
+/// read-only connections so they don't queue behind writers. The file behind them
+/// can be swapped at runtime with [`Database::switch_to`].
 pub struct Database {
-    pub conn: Mutex<Connection>,
+    path: RwLock<PathBuf>,
+    backup_dir: PathBuf,
+    writer: Mutex<Connection>,
+    readers: ReadPool,
 }
 
 impl Database {
-    /// Open (or create) a SQLite database at the given path.
-    pub fn new(path: &Path) -> Result<Self, rusqlite::Error> {
-        let conn = Connection::open(path)?;
+    /// Open (or create) a SQLite database at the given path. Snapshots of it are kept
+    /// in `backup_dir`.
+    pub fn new(path: &Path, backup_dir: &Path) -> Result<Self, rusqlite::Error> {
+        let conn = open_writer(path, backup_dir)?;
 
-        // Enable WAL mode for better concurrent read performance
-        conn.execute_batch("PRAGMA journal_mode = WAL")?;
-        // Enable foreign keys
-        conn.execute_batch("PRAGMA foreign_keys = ON")?;
-
-        create_tables(&conn)?;
-        run_migrations(&conn)?;
+        // Readers are opened after migrations so they see the final schema
+        let readers = ReadPool::open(path, READ_POOL_SIZE)?;
 
         Ok(Database {
-            conn: Mutex::new(conn),
+            path: RwLock::new(path.to_path_buf()),
+            backup_dir: backup_dir.to_path_buf(),
+            writer: Mutex::new(conn),
+            readers,
+        })
+    }
+
+    /// The file currently behind the connections. Stable for as long as a connection
+    /// from `write()` or `read()` is held.
+    pub fn path(&self) -> PathBuf {
+        self.path.read().unwrap().clone()
+    }
+
+    pub fn backup_dir(&self) -> &Path {
+        &self.backup_dir
+    }
+
+    /// The writer connection. Use for any command that modifies data.
+    pub fn write(&self) -> MutexGuard<'_, Connection> {
+        self.writer.lock().unwrap()
+    }
+
+    /// A read-only connection from the pool. Use for queries only.
+    pub fn read(&self) -> PooledConnection<'_> {
+        self.readers.get()
+    }
+
+    /// Replace the database contents with the snapshot at `src` and reopen every
+    /// connection. The restored schema is migrated forward if it is older.
+    pub fn restore_from(&self, src: &Path) -> Result<(), rusqlite::Error> {
+        let mut writer = self.write();
+        self.readers.reopen(&self.path(), || {
+            writer.restore(DatabaseName::Main, src, None::<fn(rusqlite::backup::Progress)>)?;
+            create_tables(&writer)?;
+            run_migrations(&writer)
         })
     }
+
+    /// Point every connection at the database file at `path`, creating and migrating
+    /// it as `new` would. Waits for in-flight reads and writes to finish first.
+    ///
+    /// `before_swap` runs on the old writer once every connection to `path` is open,
+    /// when nothing but it can stop the switch any more. If it fails, every
+    /// connection stays on the old file.
+    pub fn switch_to<E: From<rusqlite::Error>>(
+        &self,
+        path: &Path,
+        before_swap: impl FnOnce(&Connection) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let conn = open_writer(path, &self.backup_dir)?;
+        let readers = self.readers.open_all(path)?;
+        let mut writer = self.write();
+        before_swap(&writer)?;
+        self.readers.replace(readers);
+        *writer = conn;
+        *self.path.write().unwrap() = path.to_path_buf();
+        Ok(())
+    }
+}
+
+/// Open the writer connection for `path`, bringing its schema up to date.
+fn open_writer(path: &Path, backup_dir: &Path) -> Result<Connection, rusqlite::Error> {
+    let existed = path.exists();
+    let conn = Connection::open(path)?;
+
+    // Enable WAL mode so the read pool can run concurrently with the writer
+    conn.execute_batch("PRAGMA journal_mode = WAL")?;
+    // Enable foreign keys
+    conn.execute_batch("PRAGMA foreign_keys = ON")?;
+
+    // Snapshot an existing database before its schema is touched
+    if existed && get_current_version(&conn) < latest_version() {
+        let keep = backup::backup_keep_setting(&conn);
+        if let Err(e) = backup::snapshot(&conn, path, backup_dir, backup::KIND_PRE_MIGRATION, keep) {
+            log::error!("Pre-migration backup failed: {}", e);
+        }
+    }
+
+    create_tables(&conn)?;
+    run_migrations(&conn)?;
+    Ok(conn)
+}
+
+/// A fully migrated in-memory connection for service tests.
+#[cfg(test)]
+pub fn open_test_connection() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+    create_tables(&conn).unwrap();
+    run_migrations(&conn).unwrap();
+    conn
+}
+
+/// A task with only a title, for tests that need one to hang things off.
+#[cfg(test)]
+pub fn create_test_task(conn: &Connection, title: &str) -> crate::models::Task {
+    let input = crate::models::CreateTaskInput { title: title.to_string(), ..Default::default() };
+    crate::services::task::create_task(conn, input).unwrap()
 }
 
 // ============================================================
@@ -114,88 +222,964 @@ fn get_current_version(conn: &Connection) -> i64 {
     .unwrap_or(0)
 }
 
-fn run_migrations(conn: &Connection) -> Result<(), rusqlite::Error> {
-    let current_version = get_current_version(conn);
+/// Tables whose rows are soft-deleted into the trash via `deleted_at`.
+const TRASHABLE_TABLES: [&str; 5] = ["tasks", "sub_tasks", "notes", "categories", "tags"];
+
+/// Columns and indexes of `tasks` as of version 12, carried over by the version 13 rebuild.
+const TASK_COLUMNS_V12: &str = "id, title, description, status, priority, category_id, due_date, reminder_time, \
+    recurrence_rule, completed_at, sort_order, created_at, updated_at, deleted_at, start_date, someday";
+const TASK_INDEXES_V12: &str = "
+    CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status);
+    CREATE INDEX IF NOT EXISTS idx_tasks_category_id ON tasks(category_id);
+    CREATE INDEX IF NOT EXISTS idx_tasks_due_date ON tasks(due_date);
+    CREATE INDEX IF NOT EXISTS idx_tasks_priority ON tasks(priority);
+    CREATE INDEX IF NOT EXISTS idx_tasks_completed_at ON tasks(completed_at);
+    CREATE INDEX IF NOT EXISTS idx_tasks_sort_order ON tasks(sort_order);
+    CREATE INDEX IF NOT EXISTS idx_tasks_deleted_at ON tasks(deleted_at);
+    CREATE INDEX IF NOT EXISTS idx_tasks_start_date ON tasks(start_date);";
 
-    let migrations: Vec<(i64, &str, Box<dyn Fn(&Connection) -> Result<(), rusqlite::Error>>)> = vec![
-        (1, "Add description, priority, due_date columns to sub_tasks", Box::new(|conn| {
-            let cols = get_column_names(conn, "sub_tasks")?;
-            if !cols.contains(&"description".to_string()) {
-                conn.execute_batch("ALTER TABLE sub_tasks ADD COLUMN description TEXT")?;
-            }
-            if !cols.contains(&"priority".to_string()) {
-                conn.execute_batch("ALTER TABLE sub_tasks ADD COLUMN priority TEXT NOT NULL DEFAULT 'none'")?;
-            }
-            if !cols.contains(&"due_date".to_string()) {
-                conn.execute_batch("ALTER TABLE sub_tasks ADD COLUMN due_date TEXT")?;
-            }
-            Ok(())
-        })),
-        (2, "Add index on tasks(completed_at)", Box::new(|conn| {
-            conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_tasks_completed_at ON tasks(completed_at)")?;
-            Ok(())
-        })),
-        (3, "Add sort_order column to tasks", Box::new(|conn| {
-            let cols = get_column_names(conn, "tasks")?;
-            if !cols.contains(&"sort_order".to_string()) {
-                conn.execute_batch("ALTER TABLE tasks ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0")?;
+type MigrationFn = Box<dyn Fn(&Connection) -> Result<(), rusqlite::Error>>;
+
+/// A single schema step. `up` moves the schema from `version - 1` to `version`;
+/// `down`, when present, reverses it.
+struct Migration {
+    version: i64,
+    description: &'static str,
+    /// Run with foreign key enforcement off, for rebuilds of a table other tables
+    /// reference: dropping it would otherwise cascade into them. Any violation left
+    /// behind fails the step.
+    disable_foreign_keys: bool,
+    up: MigrationFn,
+    down: Option<MigrationFn>,
+}
+
+fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            description: "Add description, priority, due_date columns to sub_tasks",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                let cols = get_column_names(conn, "sub_tasks")?;
+                if !cols.contains(&"description".to_string()) {
+                    conn.execute_batch("ALTER TABLE sub_tasks ADD COLUMN description TEXT")?;
+                }
+                if !cols.contains(&"priority".to_string()) {
+                    conn.execute_batch("ALTER TABLE sub_tasks ADD COLUMN priority TEXT NOT NULL DEFAULT 'none'")?;
+                }
+                if !cols.contains(&"due_date".to_string()) {
+                    conn.execute_batch("ALTER TABLE sub_tasks ADD COLUMN due_date TEXT")?;
+                }
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                let cols = get_column_names(conn, "sub_tasks")?;
+                for col in ["description", "priority", "due_date"] {
+                    if cols.contains(&col.to_string()) {
+                        conn.execute_batch(&format!("ALTER TABLE sub_tasks DROP COLUMN {}", col))?;
+                    }
+                }
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 2,
+            description: "Add index on tasks(completed_at)",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_tasks_completed_at ON tasks(completed_at)")?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP INDEX IF EXISTS idx_tasks_completed_at")?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 3,
+            description: "Add sort_order column to tasks",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                let cols = get_column_names(conn, "tasks")?;
+                if !cols.contains(&"sort_order".to_string()) {
+                    conn.execute_batch("ALTER TABLE tasks ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0")?;
+                    conn.execute_batch(
+                        "UPDATE tasks SET sort_order = (
+                            SELECT COUNT(*) FROM tasks AS t2 WHERE t2.created_at > tasks.created_at
+                        )"
+                    )?;
+                }
+                conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_tasks_sort_order ON tasks(sort_order)")?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP INDEX IF EXISTS idx_tasks_sort_order")?;
+                if get_column_names(conn, "tasks")?.contains(&"sort_order".to_string()) {
+                    conn.execute_batch("ALTER TABLE tasks DROP COLUMN sort_order")?;
+                }
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 4,
+            description: "Add parent_id column to sub_tasks",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                let cols = get_column_names(conn, "sub_tasks")?;
+                if !cols.contains(&"parent_id".to_string()) {
+                    conn.execute_batch("ALTER TABLE sub_tasks ADD COLUMN parent_id TEXT")?;
+                }
+                conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_sub_tasks_parent_id ON sub_tasks(parent_id)")?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP INDEX IF EXISTS idx_sub_tasks_parent_id")?;
+                if get_column_names(conn, "sub_tasks")?.contains(&"parent_id".to_string()) {
+                    conn.execute_batch("ALTER TABLE sub_tasks DROP COLUMN parent_id")?;
+                }
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 5,
+            description: "Create notes table",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS notes (
+                        id TEXT PRIMARY KEY,
+                        title TEXT NOT NULL DEFAULT '',
+                        content TEXT NOT NULL DEFAULT '',
+                        is_pinned INTEGER NOT NULL DEFAULT 0,
+                        sort_order INTEGER NOT NULL DEFAULT 0,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_notes_is_pinned ON notes(is_pinned);
+                    CREATE INDEX IF NOT EXISTS idx_notes_updated_at ON notes(updated_at);"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP TABLE IF EXISTS notes")?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 6,
+            description: "Create settings table",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS settings (
+                        key TEXT PRIMARY KEY,
+                        value TEXT NOT NULL
+                    );"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP TABLE IF EXISTS settings")?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 7,
+            description: "Add deleted_at column for the trash",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                for table in TRASHABLE_TABLES {
+                    if !get_column_names(conn, table)?.contains(&"deleted_at".to_string()) {
+                        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN deleted_at TEXT", table))?;
+                    }
+                    conn.execute_batch(&format!(
+                        "CREATE INDEX IF NOT EXISTS idx_{table}_deleted_at ON {table}(deleted_at)"
+                    ))?;
+                }
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                for table in TRASHABLE_TABLES {
+                    conn.execute_batch(&format!("DROP INDEX IF EXISTS idx_{}_deleted_at", table))?;
+                    // Trashed rows would reappear as live data, so drop them with the column
+                    if get_column_names(conn, table)?.contains(&"deleted_at".to_string()) {
+                        conn.execute_batch(&format!(
+                            "DELETE FROM {table} WHERE deleted_at IS NOT NULL;
+                             ALTER TABLE {table} DROP COLUMN deleted_at;"
+                        ))?;
+                    }
+                }
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 8,
+            description: "Create append-only change_log table",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS change_log (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        entity_type TEXT NOT NULL,
+                        entity_id TEXT NOT NULL,
+                        operation TEXT NOT NULL,
+                        before_json TEXT,
+                        after_json TEXT,
+                        created_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_change_log_entity ON change_log(entity_type, entity_id);
+                    CREATE TRIGGER IF NOT EXISTS change_log_no_update BEFORE UPDATE ON change_log
+                    BEGIN SELECT RAISE(ABORT, 'change_log is append-only'); END;
+                    CREATE TRIGGER IF NOT EXISTS change_log_no_delete BEFORE DELETE ON change_log
+                    BEGIN SELECT RAISE(ABORT, 'change_log is append-only'); END;"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP TABLE IF EXISTS change_log")?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 9,
+            description: "Rebuild sub_tasks with a cascading parent_id foreign key",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                // Renaming first keeps the new self-reference pointing at the final
+                // table; deferring lets children be copied before their parents.
+                // Parents that are missing or belong to another task are cleared.
+                conn.execute_batch(
+                    "PRAGMA defer_foreign_keys = ON;
+                    ALTER TABLE sub_tasks RENAME TO sub_tasks_old;
+                    CREATE TABLE sub_tasks (
+                        id TEXT PRIMARY KEY,
+                        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                        parent_id TEXT REFERENCES sub_tasks(id) ON DELETE CASCADE CHECK(parent_id != id),
+                        title TEXT NOT NULL,
+                        description TEXT,
+                        priority TEXT NOT NULL DEFAULT 'none',
+                        due_date TEXT,
+                        completed INTEGER NOT NULL DEFAULT 0,
+                        sort_order INTEGER NOT NULL DEFAULT 0,
+                        created_at TEXT NOT NULL,
+                        deleted_at TEXT
+                    );
+                    INSERT INTO sub_tasks (id, task_id, parent_id, title, description, priority, due_date, completed, sort_order, created_at, deleted_at)
+                        SELECT s.id, s.task_id, CASE WHEN p.task_id = s.task_id AND s.parent_id != s.id THEN s.parent_id END,
+                               s.title, s.description, s.priority, s.due_date, s.completed, s.sort_order, s.created_at, s.deleted_at
+                        FROM sub_tasks_old s
+                        LEFT JOIN sub_tasks_old p ON p.id = s.parent_id
+                        WHERE s.task_id IN (SELECT id FROM tasks);
+                    DROP TABLE sub_tasks_old;
+                    CREATE INDEX IF NOT EXISTS idx_sub_tasks_task_id ON sub_tasks(task_id);
+                    CREATE INDEX IF NOT EXISTS idx_sub_tasks_parent_id ON sub_tasks(parent_id);
+                    CREATE INDEX IF NOT EXISTS idx_sub_tasks_deleted_at ON sub_tasks(deleted_at);
+                    CREATE TRIGGER IF NOT EXISTS sub_tasks_parent_same_task_insert
+                    BEFORE INSERT ON sub_tasks
+                    WHEN NEW.parent_id IS NOT NULL
+                     AND (SELECT task_id FROM sub_tasks WHERE id = NEW.parent_id) != NEW.task_id
+                    BEGIN SELECT RAISE(ABORT, 'parent sub-task must belong to the same task'); END;
+                    CREATE TRIGGER IF NOT EXISTS sub_tasks_parent_same_task_update
+                    BEFORE UPDATE OF parent_id, task_id ON sub_tasks
+                    WHEN NEW.parent_id IS NOT NULL
+                     AND (SELECT task_id FROM sub_tasks WHERE id = NEW.parent_id) != NEW.task_id
+                    BEGIN SELECT RAISE(ABORT, 'parent sub-task must belong to the same task'); END;"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch(
+                    "PRAGMA defer_foreign_keys = ON;
+                    ALTER TABLE sub_tasks RENAME TO sub_tasks_old;
+                    CREATE TABLE sub_tasks (
+                        id TEXT PRIMARY KEY,
+                        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                        parent_id TEXT,
+                        title TEXT NOT NULL,
+                        description TEXT,
+                        priority TEXT NOT NULL DEFAULT 'none',
+                        due_date TEXT,
+                        completed INTEGER NOT NULL DEFAULT 0,
+                        sort_order INTEGER NOT NULL DEFAULT 0,
+                        created_at TEXT NOT NULL,
+                        deleted_at TEXT
+                    );
+                    INSERT INTO sub_tasks SELECT id, task_id, parent_id, title, description, priority, due_date, completed, sort_order, created_at, deleted_at FROM sub_tasks_old;
+                    DROP TABLE sub_tasks_old;
+                    CREATE INDEX IF NOT EXISTS idx_sub_tasks_task_id ON sub_tasks(task_id);
+                    CREATE INDEX IF NOT EXISTS idx_sub_tasks_parent_id ON sub_tasks(parent_id);
+                    CREATE INDEX IF NOT EXISTS idx_sub_tasks_deleted_at ON sub_tasks(deleted_at);"
+                )?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 10,
+            description: "Restrict sub_tasks.priority to known values",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                // tasks.priority has a CHECK constraint; sub_tasks got the column later
+                // via ALTER TABLE, so guard it with triggers instead of another rebuild.
+                conn.execute_batch(
+                    "UPDATE sub_tasks SET priority = 'none' WHERE priority NOT IN ('none', 'low', 'medium', 'high');
+                    CREATE TRIGGER IF NOT EXISTS sub_tasks_priority_insert
+                    BEFORE INSERT ON sub_tasks
+                    WHEN NEW.priority NOT IN ('none', 'low', 'medium', 'high')
+                    BEGIN SELECT RAISE(ABORT, 'invalid sub-task priority'); END;
+                    CREATE TRIGGER IF NOT EXISTS sub_tasks_priority_update
+                    BEFORE UPDATE OF priority ON sub_tasks
+                    WHEN NEW.priority NOT IN ('none', 'low', 'medium', 'high')
+                    BEGIN SELECT RAISE(ABORT, 'invalid sub-task priority'); END;"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch(
+                    "DROP TRIGGER IF EXISTS sub_tasks_priority_insert;
+                    DROP TRIGGER IF EXISTS sub_tasks_priority_update;"
+                )?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 11,
+            description: "Create task_dependencies table",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
                 conn.execute_batch(
-                    "UPDATE tasks SET sort_order = (
-                        SELECT COUNT(*) FROM tasks AS t2 WHERE t2.created_at > tasks.created_at
-                    )"
+                    "CREATE TABLE IF NOT EXISTS task_dependencies (
+                        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                        depends_on_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                        created_at TEXT NOT NULL,
+                        PRIMARY KEY (task_id, depends_on_id),
+                        CHECK (task_id != depends_on_id)
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_task_dependencies_depends_on_id ON task_dependencies(depends_on_id);"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP TABLE IF EXISTS task_dependencies")?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 12,
+            description: "Add start_date and someday columns to tasks",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                let cols = get_column_names(conn, "tasks")?;
+                if !cols.contains(&"start_date".to_string()) {
+                    conn.execute_batch("ALTER TABLE tasks ADD COLUMN start_date TEXT")?;
+                }
+                if !cols.contains(&"someday".to_string()) {
+                    conn.execute_batch("ALTER TABLE tasks ADD COLUMN someday INTEGER NOT NULL DEFAULT 0")?;
+                }
+                conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_tasks_start_date ON tasks(start_date)")?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP INDEX IF EXISTS idx_tasks_start_date")?;
+                let cols = get_column_names(conn, "tasks")?;
+                for column in ["start_date", "someday"] {
+                    if cols.contains(&column.to_string()) {
+                        conn.execute_batch(&format!("ALTER TABLE tasks DROP COLUMN {}", column))?;
+                    }
+                }
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 13,
+            description: "Create projects and headings, and rebuild tasks to reference them",
+            disable_foreign_keys: true,
+            up: Box::new(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS projects (
+                        id TEXT PRIMARY KEY,
+                        category_id TEXT REFERENCES categories(id) ON DELETE SET NULL,
+                        title TEXT NOT NULL,
+                        notes TEXT,
+                        deadline TEXT,
+                        status TEXT NOT NULL DEFAULT 'active' CHECK(status IN ('active', 'completed')),
+                        completed_at TEXT,
+                        sort_order INTEGER NOT NULL DEFAULT 0,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        deleted_at TEXT
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_projects_category_id ON projects(category_id);
+                    CREATE INDEX IF NOT EXISTS idx_projects_deleted_at ON projects(deleted_at);
+                    CREATE TABLE IF NOT EXISTS headings (
+                        id TEXT PRIMARY KEY,
+                        project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+                        title TEXT NOT NULL,
+                        sort_order INTEGER NOT NULL DEFAULT 0,
+                        created_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_headings_project_id ON headings(project_id);"
+                )?;
+                // The status CHECK gains 'canceled', which needs a rebuild. Building the
+                // new table under a temporary name and renaming it afterwards keeps the
+                // foreign keys of sub_tasks, task_tags and task_dependencies on `tasks`.
+                conn.execute_batch(&format!(
+                    "CREATE TABLE tasks_new (
+                        id TEXT PRIMARY KEY,
+                        title TEXT NOT NULL,
+                        description TEXT,
+                        status TEXT NOT NULL DEFAULT 'todo' CHECK(status IN ('todo', 'completed', 'canceled')),
+                        priority TEXT NOT NULL DEFAULT 'none' CHECK(priority IN ('none', 'low', 'medium', 'high')),
+                        category_id TEXT REFERENCES categories(id) ON DELETE SET NULL,
+                        project_id TEXT REFERENCES projects(id) ON DELETE SET NULL,
+                        heading_id TEXT REFERENCES headings(id) ON DELETE SET NULL,
+                        start_date TEXT,
+                        due_date TEXT,
+                        reminder_time TEXT,
+                        recurrence_rule TEXT,
+                        someday INTEGER NOT NULL DEFAULT 0,
+                        completed_at TEXT,
+                        sort_order INTEGER NOT NULL DEFAULT 0,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        deleted_at TEXT
+                    );
+                    INSERT INTO tasks_new ({columns}) SELECT {columns} FROM tasks;
+                    DROP TABLE tasks;
+                    ALTER TABLE tasks_new RENAME TO tasks;
+                    {indexes}
+                    CREATE INDEX IF NOT EXISTS idx_tasks_project_id ON tasks(project_id);
+                    CREATE INDEX IF NOT EXISTS idx_tasks_heading_id ON tasks(heading_id);",
+                    columns = TASK_COLUMNS_V12,
+                    indexes = TASK_INDEXES_V12,
+                ))?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                // Canceled tasks were closed without being done; completed is the
+                // closest state the old schema has.
+                conn.execute_batch(&format!(
+                    "CREATE TABLE tasks_new (
+                        id TEXT PRIMARY KEY,
+                        title TEXT NOT NULL,
+                        description TEXT,
+                        status TEXT NOT NULL DEFAULT 'todo' CHECK(status IN ('todo', 'completed')),
+                        priority TEXT NOT NULL DEFAULT 'none' CHECK(priority IN ('none', 'low', 'medium', 'high')),
+                        category_id TEXT REFERENCES categories(id) ON DELETE SET NULL,
+                        due_date TEXT,
+                        reminder_time TEXT,
+                        recurrence_rule TEXT,
+                        completed_at TEXT,
+                        sort_order INTEGER NOT NULL DEFAULT 0,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        deleted_at TEXT,
+                        start_date TEXT,
+                        someday INTEGER NOT NULL DEFAULT 0
+                    );
+                    UPDATE tasks SET status = 'completed' WHERE status = 'canceled';
+                    INSERT INTO tasks_new ({columns}) SELECT {columns} FROM tasks;
+                    DROP TABLE tasks;
+                    ALTER TABLE tasks_new RENAME TO tasks;
+                    {indexes}
+                    DROP TABLE IF EXISTS headings;
+                    DROP TABLE IF EXISTS projects;",
+                    columns = TASK_COLUMNS_V12,
+                    indexes = TASK_INDEXES_V12,
+                ))?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 14,
+            description: "Create time_entries table",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                // The partial unique index lets at most one entry be running at a time
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS time_entries (
+                        id TEXT PRIMARY KEY,
+                        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                        started_at TEXT NOT NULL,
+                        ended_at TEXT,
+                        note TEXT,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_time_entries_task_id ON time_entries(task_id);
+                    CREATE INDEX IF NOT EXISTS idx_time_entries_started_at ON time_entries(started_at);
+                    CREATE UNIQUE INDEX IF NOT EXISTS idx_time_entries_running
+                        ON time_entries((ended_at IS NULL)) WHERE ended_at IS NULL;"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP TABLE IF EXISTS time_entries")?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 15,
+            description: "Create focus_sessions table",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS focus_sessions (
+                        id TEXT PRIMARY KEY,
+                        task_id TEXT REFERENCES tasks(id) ON DELETE SET NULL,
+                        started_at TEXT NOT NULL,
+                        ended_at TEXT NOT NULL,
+                        duration_seconds INTEGER NOT NULL,
+                        completed INTEGER NOT NULL DEFAULT 0,
+                        created_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_focus_sessions_task_id ON focus_sessions(task_id);
+                    CREATE INDEX IF NOT EXISTS idx_focus_sessions_started_at ON focus_sessions(started_at);"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP TABLE IF EXISTS focus_sessions")?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 16,
+            description: "Create task_templates table",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                // The sub-task tree and tag ids are stored as JSON, like recurrence rules
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS task_templates (
+                        id TEXT PRIMARY KEY,
+                        name TEXT NOT NULL UNIQUE,
+                        title TEXT NOT NULL,
+                        description TEXT,
+                        priority TEXT NOT NULL DEFAULT 'none' CHECK(priority IN ('none', 'low', 'medium', 'high')),
+                        category_id TEXT REFERENCES categories(id) ON DELETE SET NULL,
+                        start_offset_days INTEGER,
+                        due_offset_days INTEGER,
+                        tag_ids TEXT NOT NULL DEFAULT '[]',
+                        sub_tasks TEXT NOT NULL DEFAULT '[]',
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_task_templates_category_id ON task_templates(category_id);"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP TABLE IF EXISTS task_templates")?;
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 17,
+            description: "Add archived_at column to tasks for the logbook",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                if !get_column_names(conn, "tasks")?.contains(&"archived_at".to_string()) {
+                    conn.execute_batch("ALTER TABLE tasks ADD COLUMN archived_at TEXT")?;
+                }
+                conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_tasks_archived_at ON tasks(archived_at)")?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP INDEX IF EXISTS idx_tasks_archived_at")?;
+                if get_column_names(conn, "tasks")?.contains(&"archived_at".to_string()) {
+                    conn.execute_batch("ALTER TABLE tasks DROP COLUMN archived_at")?;
+                }
+                Ok(())
+            })),
+        },
+        Migration {
+            version: 18,
+            description: "Create attachments table",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| {
+                // Each attachment belongs to exactly one task or note and goes with it
+                // when it's purged; the file itself is kept by its SHA-256
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS attachments (
+                        id TEXT PRIMARY KEY,
+                        task_id TEXT REFERENCES tasks(id) ON DELETE CASCADE,
+                        note_id TEXT REFERENCES notes(id) ON DELETE CASCADE,
+                        file_name TEXT NOT NULL,
+                        size_bytes INTEGER NOT NULL,
+                        sha256 TEXT NOT NULL,
+                        created_at TEXT NOT NULL,
+                        CHECK((task_id IS NULL) != (note_id IS NULL))
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_attachments_task_id ON attachments(task_id);
+                    CREATE INDEX IF NOT EXISTS idx_attachments_note_id ON attachments(note_id);
+                    CREATE INDEX IF NOT EXISTS idx_attachments_sha256 ON attachments(sha256);"
+                )?;
+                Ok(())
+            }),
+            down: Some(Box::new(|conn| {
+                conn.execute_batch("DROP TABLE IF EXISTS attachments")?;
+                Ok(())
+            })),
+        },
+    ]
+}
+
+/// The schema version a fully migrated database ends up at.
+pub fn latest_version() -> i64 {
+    migrations().iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+/// Apply every pending migration. Each step runs in its own transaction together
+/// with its `schema_version` bookkeeping, so a failing step leaves the database at
+/// the last version that completed.
+pub fn run_migrations(conn: &Connection) -> Result<(), rusqlite::Error> {
+    apply_migrations(conn, &migrations(), latest_version())
+}
+
+/// Move the schema to exactly `target`, applying pending `up` steps in order or
+/// `down` steps in reverse. Only tests step down for now.
+#[cfg(test)]
+pub fn migrate_to(conn: &Connection, target: i64) -> Result<(), rusqlite::Error> {
+    apply_migrations(conn, &migrations(), target)
+}
+
+/// Run one migration step in its own transaction. Steps that ask for it run with
+/// foreign key enforcement switched off, and fail if they leave more violations
+/// behind than there were before. The pragma is ignored inside a transaction, so
+/// that case is refused rather than letting a rebuild cascade.
+fn run_step(conn: &Connection, m: &Migration, step: impl FnOnce(&Connection) -> Result<(), rusqlite::Error>) -> Result<(), rusqlite::Error> {
+    let enforced: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+    if !m.disable_foreign_keys || !enforced {
+        let tx = conn.unchecked_transaction()?;
+        step(&tx)?;
+        return tx.commit();
+    }
+
+    conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+    let result = (|| {
+        if conn.query_row("PRAGMA foreign_keys", [], |row| row.get::<_, bool>(0))? {
+            return Err(migration_error(format!(
+                "Migration {} ({}) can't run inside a transaction",
+                m.version, m.description
+            )));
+        }
+        let tx = conn.unchecked_transaction()?;
+        let before = foreign_key_violations(&tx)?;
+        step(&tx)?;
+        let after = foreign_key_violations(&tx)?;
+        if after > before {
+            return Err(migration_error(format!(
+                "Migration {} ({}) left {} new foreign key violations",
+                m.version, m.description, after - before
+            )));
+        }
+        tx.commit()
+    })();
+    conn.execute_batch("PRAGMA foreign_keys = ON")?;
+    result
+}
+
+fn foreign_key_violations(conn: &Connection) -> Result<i64, rusqlite::Error> {
+    conn.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))
+}
+
+fn apply_migrations(conn: &Connection, migrations: &[Migration], target: i64) -> Result<(), rusqlite::Error> {
+    let latest = migrations.iter().map(|m| m.version).max().unwrap_or(0);
+    if target < 0 || target > latest {
+        return Err(migration_error(format!(
+            "Cannot migrate to version {}: known versions are 0..={}",
+            target, latest
+        )));
+    }
+
+    let current_version = get_current_version(conn);
+
+    if target >= current_version {
+        for m in migrations.iter().filter(|m| m.version > current_version && m.version <= target) {
+            run_step(conn, m, |tx| {
+                (m.up)(tx)?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO schema_version (version, applied_at) VALUES (?1, ?2)",
+                    params![m.version, chrono::Utc::now().to_rfc3339()],
                 )?;
-            }
-            conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_tasks_sort_order ON tasks(sort_order)")?;
-            Ok(())
-        })),
-        (4, "Add parent_id column to sub_tasks", Box::new(|conn| {
-            let cols = get_column_names(conn, "sub_tasks")?;
-            if !cols.contains(&"parent_id".to_string()) {
-                conn.execute_batch("ALTER TABLE sub_tasks ADD COLUMN parent_id TEXT")?;
-            }
-            conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_sub_tasks_parent_id ON sub_tasks(parent_id)")?;
-            Ok(())
-        })),
-        (5, "Create notes table", Box::new(|conn| {
-            conn.execute_batch(
-                "CREATE TABLE IF NOT EXISTS notes (
-                    id TEXT PRIMARY KEY,
-                    title TEXT NOT NULL DEFAULT '',
-                    content TEXT NOT NULL DEFAULT '',
-                    is_pinned INTEGER NOT NULL DEFAULT 0,
-                    sort_order INTEGER NOT NULL DEFAULT 0,
-                    created_at TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                );
-                CREATE INDEX IF NOT EXISTS idx_notes_is_pinned ON notes(is_pinned);
-                CREATE INDEX IF NOT EXISTS idx_notes_updated_at ON notes(updated_at);"
-            )?;
-            Ok(())
-        })),
-    ];
-
-    let pending: Vec<_> = migrations.into_iter().filter(|(v, _, _)| *v > current_version).collect();
-    if pending.is_empty() {
-        return Ok(());
-    }
-
-    let tx = conn;
-    for (version, _desc, up) in &pending {
-        up(tx)?;
-        tx.execute(
-            "INSERT OR IGNORE INTO schema_version (version, applied_at) VALUES (?1, ?2)",
-            params![version, chrono::Utc::now().to_rfc3339()],
-        )?;
+                Ok(())
+            })?;
+            log::info!("Applied migration {}: {}", m.version, m.description);
+        }
+    } else {
+        for m in migrations.iter().rev().filter(|m| m.version <= current_version && m.version > target) {
+            let down = m.down.as_ref().ok_or_else(|| {
+                migration_error(format!("Migration {} ({}) cannot be reverted", m.version, m.description))
+            })?;
+            run_step(conn, m, |tx| {
+                down(tx)?;
+                tx.execute("DELETE FROM schema_version WHERE version = ?1", params![m.version])?;
+                Ok(())
+            })?;
+            log::info!("Reverted migration {}: {}", m.version, m.description);
+        }
     }
 
     Ok(())
 }
 
+fn migration_error(message: String) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR), Some(message))
+}
+
 fn get_column_names(conn: &Connection, table: &str) -> Result<Vec<String>, rusqlite::Error> {
     let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
     let names = stmt
         .query_map([], |row| row.get::<_, String>(1))?
-        .filter_map(|r| r.ok())
-        .collect();
+        .collect::<Result<_, _>>()?;
     Ok(names)
 }
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db_path() -> PathBuf {
+        std::env::temp_dir().join(format!("watermelon-test-{}.db", uuid::Uuid::new_v4()))
+    }
+
+    fn remove_db_files(path: &Path) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn table_exists(conn: &Connection, table: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![table],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    #[test]
+    fn test_run_migrations_reaches_latest() {
+        let conn = setup_db();
+        run_migrations(&conn).unwrap();
+        assert_eq!(get_current_version(&conn), latest_version());
+        assert!(table_exists(&conn, "notes"));
+    }
+
+    #[test]
+    fn test_migrate_down_and_up_again() {
+        let conn = setup_db();
+        run_migrations(&conn).unwrap();
+
+        migrate_to(&conn, 3).unwrap();
+        assert_eq!(get_current_version(&conn), 3);
+        assert!(!table_exists(&conn, "notes"));
+        assert!(!get_column_names(&conn, "sub_tasks").unwrap().contains(&"parent_id".to_string()));
+        assert!(get_column_names(&conn, "tasks").unwrap().contains(&"sort_order".to_string()));
+
+        migrate_to(&conn, latest_version()).unwrap();
+        assert_eq!(get_current_version(&conn), latest_version());
+        assert!(table_exists(&conn, "notes"));
+        assert!(get_column_names(&conn, "sub_tasks").unwrap().contains(&"parent_id".to_string()));
+    }
+
+    #[test]
+    fn test_sub_task_rebuild_cleans_parents_and_cascades() {
+        let conn = setup_db();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        migrate_to(&conn, 8).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, created_at, updated_at) VALUES ('t1', 'A', '', ''), ('t2', 'B', '', '');
+             INSERT INTO sub_tasks (id, task_id, parent_id, title, created_at) VALUES
+                ('root', 't1', NULL, 'root', ''),
+                ('child', 't1', 'root', 'child', ''),
+                ('stray', 't2', 'root', 'other task', ''),
+                ('orphan', 't1', 'gone', 'missing parent', '');",
+        )
+        .unwrap();
+
+        migrate_to(&conn, 9).unwrap();
+        let parent_of = |id: &str| -> Option<String> {
+            conn.query_row("SELECT parent_id FROM sub_tasks WHERE id = ?1", params![id], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(parent_of("child").as_deref(), Some("root"));
+        assert_eq!(parent_of("stray"), None);
+        assert_eq!(parent_of("orphan"), None);
+
+        // Cross-task parents are rejected from now on
+        assert!(conn.execute("UPDATE sub_tasks SET parent_id = 'root' WHERE id = 'stray'", []).is_err());
+        assert!(conn
+            .execute("INSERT INTO sub_tasks (id, task_id, parent_id, title, created_at) VALUES ('x', 't2', 'root', 'x', '')", [])
+            .is_err());
+
+        conn.execute("DELETE FROM sub_tasks WHERE id = 'root'", []).unwrap();
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM sub_tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 2);
+    }
+
+    #[test]
+    fn test_sub_task_priority_is_normalized_and_guarded() {
+        let conn = setup_db();
+        migrate_to(&conn, 9).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, created_at, updated_at) VALUES ('t1', 'A', '', '');
+             INSERT INTO sub_tasks (id, task_id, title, priority, created_at) VALUES ('s1', 't1', 's', 'urgent', '');",
+        )
+        .unwrap();
+
+        migrate_to(&conn, 10).unwrap();
+        let priority: String = conn.query_row("SELECT priority FROM sub_tasks WHERE id = 's1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(priority, "none");
+        assert!(conn.execute("UPDATE sub_tasks SET priority = 'urgent' WHERE id = 's1'", []).is_err());
+        assert!(conn
+            .execute("INSERT INTO sub_tasks (id, task_id, title, priority, created_at) VALUES ('s2', 't1', 's', 'HIGH', '')", [])
+            .is_err());
+    }
+
+    #[test]
+    fn test_task_rebuild_keeps_children_and_references() {
+        let conn = setup_db();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        migrate_to(&conn, 12).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, status, created_at, updated_at) VALUES ('t1', 'A', 'completed', '', ''), ('t2', 'B', 'todo', '', '');
+             INSERT INTO sub_tasks (id, task_id, title, created_at) VALUES ('s1', 't1', 'step', '');
+             INSERT INTO task_dependencies (task_id, depends_on_id, created_at) VALUES ('t2', 't1', '');",
+        )
+        .unwrap();
+
+        migrate_to(&conn, 13).unwrap();
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM sub_tasks"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM task_dependencies"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM pragma_foreign_key_check"), 0);
+        let schema: String = conn
+            .query_row("SELECT sql FROM sqlite_master WHERE name = 'sub_tasks'", [], |row| row.get(0))
+            .unwrap();
+        assert!(schema.contains("REFERENCES tasks(id)"));
+        conn.execute("UPDATE tasks SET status = 'canceled' WHERE id = 't2'", []).unwrap();
+
+        // Children still cascade from the rebuilt table
+        conn.execute("DELETE FROM tasks WHERE id = 't1'", []).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM sub_tasks"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM task_dependencies"), 0);
+
+        migrate_to(&conn, 12).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM tasks WHERE status = 'completed'"), 1);
+        assert!(!table_exists(&conn, "projects"));
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let conn = setup_db();
+        let steps = vec![
+            Migration {
+                version: 1,
+                description: "create a",
+                disable_foreign_keys: false,
+                up: Box::new(|conn| conn.execute_batch("CREATE TABLE a (id INTEGER)")),
+                down: None,
+            },
+            Migration {
+                version: 2,
+                description: "alter a, then fail",
+                disable_foreign_keys: false,
+                up: Box::new(|conn| {
+                    conn.execute_batch("ALTER TABLE a ADD COLUMN b TEXT")?;
+                    conn.execute_batch("INSERT INTO missing_table VALUES (1)")
+                }),
+                down: None,
+            },
+        ];
+
+        assert!(apply_migrations(&conn, &steps, 2).is_err());
+        assert_eq!(get_current_version(&conn), 1);
+        assert!(table_exists(&conn, "a"));
+        assert!(!get_column_names(&conn, "a").unwrap().contains(&"b".to_string()));
+    }
+
+    #[test]
+    fn test_migrate_rejects_unknown_or_irreversible_targets() {
+        let conn = setup_db();
+        assert!(migrate_to(&conn, latest_version() + 1).is_err());
+
+        let steps = vec![Migration {
+            version: 1,
+            description: "create a",
+            disable_foreign_keys: false,
+            up: Box::new(|conn| conn.execute_batch("CREATE TABLE a (id INTEGER)")),
+            down: None,
+        }];
+        apply_migrations(&conn, &steps, 1).unwrap();
+        assert!(apply_migrations(&conn, &steps, 0).is_err());
+        assert_eq!(get_current_version(&conn), 1);
+    }
+
+    #[test]
+    fn test_read_pool_sees_writes_and_rejects_writes() {
+        let path = temp_db_path();
+        {
+            let db = Database::new(&path, &std::env::temp_dir()).unwrap();
+            db.write()
+                .execute(
+                    "INSERT INTO tags (id, name, color, created_at) VALUES ('t1', 'work', NULL, '2024-01-01T00:00:00Z')",
+                    [],
+                )
+                .unwrap();
+
+            // Hold one reader while borrowing another to exercise the pool
+            let first = db.read();
+            let second = db.read();
+            let count: i64 = second.query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0)).unwrap();
+            assert_eq!(count, 1);
+            assert!(first.execute("DELETE FROM tags", []).is_err());
+        }
+        remove_db_files(&path);
+    }
+
+    #[test]
+    fn test_failed_reopen_keeps_the_old_readers() {
+        let path = temp_db_path();
+        {
+            let db = Database::new(&path, &std::env::temp_dir()).unwrap();
+            let missing = std::env::temp_dir().join("watermelon-missing-dir").join("nope.db");
+            assert!(db.readers.reopen(&missing, || Ok(())).is_err());
+
+            // Every reader is still there, on the old file
+            let readers: Vec<_> = (0..READ_POOL_SIZE).map(|_| db.read()).collect();
+            let count: i64 = readers[0].query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0)).unwrap();
+            assert_eq!(count, 0);
+        }
+        remove_db_files(&path);
+    }
+
+    #[test]
+    fn test_switch_stays_put_when_before_swap_fails() {
+        let (a, b) = (temp_db_path(), temp_db_path());
+        {
+            let db = Database::new(&a, &std::env::temp_dir()).unwrap();
+            db.write()
+                .execute("INSERT INTO tags (id, name, created_at) VALUES ('t1', 'work', '')", [])
+                .unwrap();
+            let tags = |db: &Database| -> i64 { db.read().query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0)).unwrap() };
+
+            assert!(db.switch_to(&b, |_| Err(rusqlite::Error::InvalidQuery)).is_err());
+            assert_eq!((db.path(), tags(&db)), (a.clone(), 1));
+
+            db.switch_to(&b, |_| Ok::<_, rusqlite::Error>(())).unwrap();
+            assert_eq!((db.path(), tags(&db)), (b.clone(), 0));
+        }
+        remove_db_files(&a);
+        remove_db_files(&b);
+    }
+}
@@ -0,0 +1,108 @@
+use rusqlite::{Connection, OpenFlags};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+/// A fixed-size pool of read-only connections.
+///
+/// WAL mode lets these readers run alongside the single writer connection, so
+/// long-running reads (export, statistics) no longer block task edits.
+pub struct ReadPool {
+    size: usize,
+    conns: Mutex<Vec<Connection>>,
+    available: Condvar,
+}
+
+impl ReadPool {
+    pub fn open(path: &Path, size: usize) -> Result<Self, rusqlite::Error> {
+        let mut conns = Vec::with_capacity(size);
+        for _ in 0..size {
+            conns.push(open_reader(path)?);
+        }
+        Ok(ReadPool {
+            size,
+            conns: Mutex::new(conns),
+            available: Condvar::new(),
+        })
+    }
+
+    /// Borrow a connection, blocking until one is returned if all are in use.
+    pub fn get(&self) -> PooledConnection<'_> {
+        let mut conns = self.conns.lock().unwrap();
+        loop {
+            if let Some(conn) = conns.pop() {
+                return PooledConnection { pool: self, conn: Some(conn) };
+            }
+            conns = self.available.wait(conns).unwrap();
+        }
+    }
+
+    /// Take every connection out of use, run `f`, then reopen the pool against `path`.
+    ///
+    /// Waits for borrowed connections to come back first, so no read is in flight
+    /// while `f` replaces the database file contents. The pool is reopened even when
+    /// `f` fails. If the new readers can't be opened, the old ones are put back and
+    /// the error is returned, so the pool is never left short.
+    pub fn reopen<T>(
+        &self,
+        path: &Path,
+        f: impl FnOnce() -> Result<T, rusqlite::Error>,
+    ) -> Result<T, rusqlite::Error> {
+        let mut conns = self.wait_for_all();
+        let result = f();
+        *conns = self.open_all(path)?;
+        self.available.notify_all();
+        result
+    }
+
+    /// Open a full set of readers on `path` for [`ReadPool::replace`], leaving the
+    /// pool as it is.
+    pub fn open_all(&self, path: &Path) -> Result<Vec<Connection>, rusqlite::Error> {
+        (0..self.size).map(|_| open_reader(path)).collect()
+    }
+
+    /// Swap in `readers` once every borrowed connection has come back.
+    pub fn replace(&self, readers: Vec<Connection>) {
+        let mut conns = self.wait_for_all();
+        *conns = readers;
+        self.available.notify_all();
+    }
+
+    fn wait_for_all(&self) -> MutexGuard<'_, Vec<Connection>> {
+        let mut conns = self.conns.lock().unwrap();
+        while conns.len() < self.size {
+            conns = self.available.wait(conns).unwrap();
+        }
+        conns
+    }
+}
+
+fn open_reader(path: &Path) -> Result<Connection, rusqlite::Error> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+}
+
+/// A read-only connection checked out of a [`ReadPool`]; returned on drop.
+pub struct PooledConnection<'a> {
+    pool: &'a ReadPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.conns.lock().unwrap().push(conn);
+            self.pool.available.notify_all();
+        }
+    }
+}
@@ -3,12 +3,23 @@ mod models;
 mod services;
 mod commands;
 mod utils;
+#[cfg(test)]
+mod bindings;
 
 use db::Database;
 use services::notification::NotificationState;
+use services::pomodoro::PomodoroState;
+use services::undo::UndoState;
+use services::workspace::WorkspaceState;
 use commands::SavedWindowGeometry;
 use std::fs;
 use std::sync::Mutex;
+use std::time::Duration;
+
+/// How often the background thread runs maintenance: it checks for a due backup,
+/// purges expired trash, archives old closed tasks and removes unreferenced
+/// attachment files.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -25,19 +36,21 @@ pub fn run() {
             if !app_data_dir.exists() {
                 fs::create_dir_all(&app_data_dir).expect("Failed to create app data dir");
             }
-            let db_path = app_data_dir.join("watermelon.db");
+            let workspaces = WorkspaceState::load(&app_data_dir).expect("Failed to load workspaces");
+            let db_path = workspaces.active_database_path();
+            let backup_dir = app_data_dir.join("backups");
 
             log::info!("Database path: {:?}", db_path);
 
             // Initialize database
-            let database = Database::new(&db_path).expect("Failed to initialize database");
+            let database = Database::new(&db_path, &backup_dir).expect("Failed to initialize database");
 
             // Initialize notification state
             let notification_state = NotificationState::new();
 
             // Check missed reminders and schedule future ones
             {
-                let conn = database.conn.lock().unwrap();
+                let conn = database.write();
                 services::notification::check_missed_reminders(&conn, app.handle());
                 services::notification::schedule_all_future_reminders(&conn, &notification_state, app.handle());
             }
@@ -45,8 +58,34 @@ pub fn run() {
             // Register state
             app.manage(database);
             app.manage(notification_state);
+            app.manage(UndoState::new());
+            app.manage(PomodoroState::new());
+            app.manage(workspaces);
             app.manage(SavedWindowGeometry { inner: Mutex::new(None) });
 
+            // Periodic automatic backups, trash purging, logbook archiving and attachment cleanup
+            let handle = app.handle().clone();
+            std::thread::spawn(move || loop {
+                let db = handle.state::<Database>();
+                if let Err(e) = services::backup::run_scheduled_backup(&db) {
+                    log::error!("Scheduled backup failed: {}", e);
+                }
+                if let Err(e) = services::trash::purge_expired(&db.write()) {
+                    log::error!("Trash purge failed: {}", e);
+                }
+                if let Err(e) = services::logbook::archive_expired(&db.write()) {
+                    log::error!("Logbook archiving failed: {}", e);
+                }
+                {
+                    let conn = db.write();
+                    let attachment_dir = handle.state::<WorkspaceState>().attachment_dir(&db.path());
+                    if let Err(e) = services::attachment::remove_unreferenced(&conn, &attachment_dir) {
+                        log::error!("Removing attachment files failed: {}", e);
+                    }
+                }
+                std::thread::sleep(MAINTENANCE_INTERVAL);
+            });
+
             log::info!("小西瓜 app started!");
 
             Ok(())
@@ -57,12 +96,18 @@ pub fn run() {
             commands::delete_task,
             commands::get_tasks,
             commands::get_task_by_id,
+            commands::get_view,
             commands::complete_task,
             commands::uncomplete_task,
             commands::reorder_tasks,
+            commands::duplicate_task,
+            commands::bulk_update_tasks,
             commands::create_sub_task,
             commands::update_sub_task,
             commands::delete_sub_task,
+            commands::move_sub_task,
+            commands::promote_sub_task,
+            commands::demote_task,
             commands::create_category,
             commands::update_category,
             commands::delete_category,
@@ -73,11 +118,43 @@ pub fn run() {
             commands::get_tags,
             commands::add_tag_to_task,
             commands::remove_tag_from_task,
+            commands::add_task_dependency,
+            commands::remove_task_dependency,
+            commands::create_project,
+            commands::update_project,
+            commands::delete_project,
+            commands::get_projects,
+            commands::get_project_by_id,
+            commands::complete_project,
+            commands::reopen_project,
+            commands::create_heading,
+            commands::update_heading,
+            commands::delete_heading,
+            commands::get_templates,
+            commands::create_template,
+            commands::create_template_from_task,
+            commands::update_template,
+            commands::delete_template,
+            commands::instantiate_template,
+            commands::start_timer,
+            commands::stop_timer,
+            commands::get_running_timer,
+            commands::get_time_entries,
+            commands::create_time_entry,
+            commands::update_time_entry,
+            commands::delete_time_entry,
+            commands::start_pomodoro,
+            commands::stop_pomodoro,
+            commands::get_pomodoro_status,
+            commands::get_focus_sessions,
             commands::search_tasks,
             commands::schedule_notification,
             commands::cancel_notification,
             commands::get_stats,
             commands::get_daily_trend,
+            commands::get_focus_trend,
+            commands::get_focus_by_category,
+            commands::get_time_stats,
             commands::export_data,
             commands::import_data,
             commands::set_compact_mode,
@@ -87,6 +164,30 @@ pub fn run() {
             commands::get_notes,
             commands::get_note_by_id,
             commands::search_notes,
+            commands::get_attachments,
+            commands::add_attachment,
+            commands::delete_attachment,
+            commands::get_attachment_path,
+            commands::list_backups,
+            commands::create_backup,
+            commands::restore_backup,
+            commands::check_database,
+            commands::repair_database,
+            commands::get_settings,
+            commands::update_settings,
+            commands::get_trash,
+            commands::restore_from_trash,
+            commands::empty_trash,
+            commands::get_logbook,
+            commands::unarchive_task,
+            commands::get_entity_history,
+            commands::undo,
+            commands::redo,
+            commands::list_workspaces,
+            commands::create_workspace,
+            commands::rename_workspace,
+            commands::delete_workspace,
+            commands::switch_workspace,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
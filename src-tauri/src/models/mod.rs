@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 // ============================================================
-// Core Data Types — mirrors src/shared/types.ts
+// Core Data Types — exported to src/shared/bindings.ts (see bindings.rs)
 // ============================================================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,13 +10,22 @@ pub struct Task {
     pub id: String,
     pub title: String,
     pub description: Option<String>,
-    pub status: String,       // "todo" | "completed"
-    pub priority: String,     // "none" | "low" | "medium" | "high"
+    pub status: TaskStatus,
+    pub priority: Priority,
     pub category_id: Option<String>,
+    pub project_id: Option<String>,
+    /// Always a heading of `project_id`
+    pub heading_id: Option<String>,
+    /// Hidden from Today and Anytime until this date
+    pub start_date: Option<String>,
     pub due_date: Option<String>,
     pub reminder_time: Option<String>,
     pub recurrence_rule: Option<RecurrenceRule>,
+    /// Parked in Someday, out of every other view
+    pub someday: bool,
     pub completed_at: Option<String>,
+    /// Moved to the logbook, out of the task list and every view
+    pub archived_at: Option<String>,
     pub sort_order: i64,
     pub created_at: String,
     pub updated_at: String,
@@ -26,6 +35,18 @@ pub struct Task {
     pub tags: Option<Vec<Tag>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category: Option<Category>,
+    /// Ids of the tasks this one waits on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocked_by: Option<Vec<String>>,
+    /// Ids of the tasks waiting on this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocking: Option<Vec<String>>,
+    /// Whether any task this one waits on is still open
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_blocked: Option<bool>,
+    /// Seconds logged against this task, a running timer included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_spent_seconds: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,7 +57,7 @@ pub struct SubTask {
     pub parent_id: Option<String>,
     pub title: String,
     pub description: Option<String>,
-    pub priority: String,
+    pub priority: Priority,
     pub due_date: Option<String>,
     pub completed: bool,
     pub sort_order: i64,
@@ -55,6 +76,43 @@ pub struct Category {
     pub created_at: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: String,
+    pub category_id: Option<String>,
+    pub title: String,
+    pub notes: Option<String>,
+    pub deadline: Option<String>,
+    pub status: ProjectStatus,
+    pub completed_at: Option<String>,
+    pub sort_order: i64,
+    pub created_at: String,
+    pub updated_at: String,
+    pub headings: Vec<Heading>,
+    pub progress: ProjectProgress,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Heading {
+    pub id: String,
+    pub project_id: String,
+    pub title: String,
+    pub sort_order: i64,
+    pub created_at: String,
+}
+
+/// Task counts of a project; canceled tasks count toward neither.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectProgress {
+    pub total_tasks: i64,
+    pub completed_tasks: i64,
+    /// `completed_tasks / total_tasks`, 0 for an empty project
+    pub ratio: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Tag {
@@ -64,6 +122,71 @@ pub struct Tag {
     pub created_at: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntry {
+    pub id: String,
+    pub task_id: String,
+    pub started_at: String,
+    /// `None` while the timer is running
+    pub ended_at: Option<String>,
+    pub note: Option<String>,
+    /// Up to now for a running timer
+    #[serde(default)]
+    pub duration_seconds: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A saved task to create again and again. Dates are stored as offsets in days
+/// from the base date given when the template is used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTemplate {
+    pub id: String,
+    pub name: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: Priority,
+    pub category_id: Option<String>,
+    pub start_offset_days: Option<i64>,
+    pub due_offset_days: Option<i64>,
+    pub tag_ids: Vec<String>,
+    pub sub_tasks: Vec<TemplateSubTask>,
+    /// `{{placeholder}}` names used in titles and descriptions, in order of appearance
+    #[serde(default)]
+    pub placeholders: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateSubTask {
+    pub title: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    pub due_offset_days: Option<i64>,
+    #[serde(default)]
+    pub children: Vec<TemplateSubTask>,
+}
+
+/// A file attached to a task or a note. The file is stored once per workspace
+/// under its SHA-256, however many attachments share it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: String,
+    /// Exactly one of `task_id` and `note_id` is set
+    pub task_id: Option<String>,
+    pub note_id: Option<String>,
+    pub file_name: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub created_at: String,
+}
+
 // ============================================================
 // Recurrence Rule
 // ============================================================
@@ -82,51 +205,195 @@ pub struct RecurrenceRule {
     pub end_date: Option<String>,
 }
 
+// ============================================================
+// Status & Priority
+// ============================================================
+
+/// Defines a string-backed enum: serialized, stored and compared as its lowercase
+/// name, and parsed from user input with a `VALIDATION_ERROR` naming the field.
+macro_rules! string_enum {
+    ($name:ident { $($(#[$meta:meta])* $variant:ident => $value:literal),+ $(,)? }) => {
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        pub enum $name {
+            $($(#[$meta])* #[serde(rename = $value)] $variant),+
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $($name::$variant => $value),+
+                }
+            }
+
+            /// Parse `value` given for `field`, rejecting anything that isn't a known variant.
+            pub fn parse(field: &str, value: &str) -> Result<Self, AppError> {
+                Self::ALL.iter().copied().find(|v| v.as_str() == value).ok_or_else(|| {
+                    let expected: Vec<&str> = Self::ALL.iter().map(|v| v.as_str()).collect();
+                    AppError::validation(format!("Invalid {}: \"{}\" (expected one of {})", field, value, expected.join(", ")))
+                        .with_detail("field", field)
+                        .with_detail("value", value)
+                })
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl rusqlite::types::ToSql for $name {
+            fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+                Ok(self.as_str().into())
+            }
+        }
+
+        impl rusqlite::types::FromSql for $name {
+            fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+                let text = value.as_str()?;
+                Self::ALL
+                    .iter()
+                    .copied()
+                    .find(|v| v.as_str() == text)
+                    .ok_or_else(|| rusqlite::types::FromSqlError::Other(format!("invalid {}: {}", stringify!($name), text).into()))
+            }
+        }
+    };
+}
+
+string_enum! {
+    TaskStatus {
+        #[default]
+        Todo => "todo",
+        Completed => "completed",
+        Canceled => "canceled",
+    }
+}
+
+string_enum! {
+    ProjectStatus {
+        #[default]
+        Active => "active",
+        Completed => "completed",
+    }
+}
+
+// What `complete_project` does with the tasks still open in the project
+string_enum! {
+    RemainingTasks {
+        #[default]
+        Complete => "complete",
+        Cancel => "cancel",
+    }
+}
+
+// What `bulk_update_tasks` does to each task once its fields and tags are changed
+string_enum! {
+    BulkTaskAction {
+        #[default]
+        Complete => "complete",
+        Uncomplete => "uncomplete",
+        Delete => "delete",
+    }
+}
+
+string_enum! {
+    Priority {
+        #[default]
+        None => "none",
+        Low => "low",
+        Medium => "medium",
+        High => "high",
+    }
+}
+
+impl Priority {
+    /// Position when sorting by priority, most urgent first.
+    pub fn rank(self) -> i64 {
+        match self {
+            Priority::High => 0,
+            Priority::Medium => 1,
+            Priority::Low => 2,
+            Priority::None => 3,
+        }
+    }
+}
+
 // ============================================================
 // Input Types
 // ============================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// For `Option<Option<T>>` update fields: a missing field stays `None` (leave the
+/// value alone) while an explicit `null` becomes `Some(None)` (clear it).
+fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateTaskInput {
     pub title: String,
     pub description: Option<String>,
-    pub priority: Option<String>,
+    pub priority: Option<String>, // parsed into Priority by the service
     pub category_id: Option<String>,
+    pub project_id: Option<String>,
+    pub heading_id: Option<String>, // implies its project
+    pub start_date: Option<String>,
     pub due_date: Option<String>,
     pub reminder_time: Option<String>,
     pub recurrence_rule: Option<RecurrenceRule>,
+    pub someday: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateTaskInput {
     pub title: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
     pub description: Option<Option<String>>,
-    pub priority: Option<String>,
+    pub priority: Option<String>, // parsed into Priority by the service
+    #[serde(default, deserialize_with = "double_option")]
     pub category_id: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub project_id: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub heading_id: Option<Option<String>>, // implies its project
+    #[serde(default, deserialize_with = "double_option")]
+    pub start_date: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
     pub due_date: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
     pub reminder_time: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
     pub recurrence_rule: Option<Option<RecurrenceRule>>,
+    pub someday: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateSubTaskInput {
     pub title: String,
     pub description: Option<String>,
-    pub priority: Option<String>,
+    pub priority: Option<String>, // parsed into Priority by the service
     pub due_date: Option<String>,
     pub sort_order: Option<i64>,
     pub parent_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateSubTaskInput {
     pub title: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
     pub description: Option<Option<String>>,
-    pub priority: Option<String>,
+    pub priority: Option<String>, // parsed into Priority by the service
+    #[serde(default, deserialize_with = "double_option")]
     pub due_date: Option<Option<String>>,
     pub completed: Option<bool>,
     pub sort_order: Option<i64>,
@@ -147,6 +414,178 @@ pub struct UpdateCategoryInput {
     pub sort_order: Option<i64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateProjectInput {
+    pub title: String,
+    pub notes: Option<String>,
+    pub category_id: Option<String>,
+    pub deadline: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProjectInput {
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub notes: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub category_id: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub deadline: Option<Option<String>>,
+    pub sort_order: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateHeadingInput {
+    pub title: Option<String>,
+    pub sort_order: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTimeEntryInput {
+    pub task_id: String,
+    pub started_at: String,
+    pub ended_at: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTimeEntryInput {
+    pub started_at: Option<String>,
+    /// Can't be cleared; use `start_timer` to run a timer
+    pub ended_at: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub note: Option<Option<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateTaskOptions {
+    /// Start the copy over as open; defaults to true
+    pub reset_completion: Option<bool>,
+    /// Days to move every date of the copy by, e.g. 7 for the week after
+    pub date_offset_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUpdateTasksInput {
+    pub ids: Vec<String>,
+    /// Field changes applied to every task, as in `update_task`
+    pub changes: Option<UpdateTaskInput>,
+    pub add_tag_ids: Option<Vec<String>>,
+    pub remove_tag_ids: Option<Vec<String>>,
+    pub action: Option<String>, // parsed into BulkTaskAction by the service
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTemplateInput {
+    pub name: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: Option<String>, // parsed into Priority by the service
+    pub category_id: Option<String>,
+    pub start_offset_days: Option<i64>,
+    pub due_offset_days: Option<i64>,
+    pub tag_ids: Option<Vec<String>>,
+    pub sub_tasks: Option<Vec<TemplateSubTaskInput>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTemplateInput {
+    pub name: Option<String>,
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub description: Option<Option<String>>,
+    pub priority: Option<String>, // parsed into Priority by the service
+    #[serde(default, deserialize_with = "double_option")]
+    pub category_id: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub start_offset_days: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub due_offset_days: Option<Option<i64>>,
+    pub tag_ids: Option<Vec<String>>,
+    /// Replaces the whole tree
+    pub sub_tasks: Option<Vec<TemplateSubTaskInput>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateSubTaskInput {
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: Option<String>, // parsed into Priority by the service
+    pub due_offset_days: Option<i64>,
+    pub children: Option<Vec<TemplateSubTaskInput>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstantiateTemplateInput {
+    /// Date the offsets count from; defaults to today
+    pub base_date: Option<String>,
+    /// Values for the template's placeholders; `date` defaults to the base date
+    pub values: Option<std::collections::HashMap<String, String>>,
+    pub project_id: Option<String>,
+    pub heading_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddAttachmentInput {
+    /// Exactly one of `task_id` and `note_id`
+    pub task_id: Option<String>,
+    pub note_id: Option<String>,
+    /// File to copy into the attachment store
+    pub source_path: String,
+}
+
+// ============================================================
+// Pomodoro Types
+// ============================================================
+
+// Phases of the Pomodoro cycle: work, then a short break, or a long one every few rounds
+string_enum! {
+    PomodoroPhase {
+        #[default]
+        Work => "work",
+        ShortBreak => "shortBreak",
+        LongBreak => "longBreak",
+    }
+}
+
+/// The Pomodoro phase in progress.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PomodoroStatus {
+    pub phase: PomodoroPhase,
+    pub task_id: Option<String>,
+    pub started_at: String,
+    pub ends_at: String,
+    /// Work sessions finished since the last long break
+    pub completed_in_cycle: i64,
+}
+
+/// A Pomodoro work session, finished or stopped early.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusSession {
+    pub id: String,
+    pub task_id: Option<String>,
+    pub started_at: String,
+    pub ended_at: String,
+    pub duration_seconds: i64,
+    /// Whether it ran its full length
+    pub completed: bool,
+    pub created_at: String,
+}
+
 // ============================================================
 // Filter & Search Types
 // ============================================================
@@ -154,16 +593,29 @@ pub struct UpdateCategoryInput {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskFilter {
+    #[serde(default, deserialize_with = "double_option")]
     pub category_id: Option<Option<String>>,
     pub tag_ids: Option<Vec<String>>,
-    pub priority: Option<String>,
-    pub status: Option<String>,
+    pub priority: Option<String>, // parsed into Priority by the service
+    pub status: Option<String>,   // parsed into TaskStatus by the service
     pub due_date_from: Option<String>,
     pub due_date_to: Option<String>,
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
 }
 
+// The built-in lists served by `get_view`
+string_enum! {
+    ViewKind {
+        #[default]
+        Today => "today",
+        Upcoming => "upcoming",
+        Anytime => "anytime",
+        Someday => "someday",
+        Logbook => "logbook",
+    }
+}
+
 // ============================================================
 // Statistics Types
 // ============================================================
@@ -186,6 +638,57 @@ pub struct DailyTrend {
     pub created: i64,
 }
 
+/// Time logged in entries started between `from` and `to` (inclusive dates).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeStats {
+    pub from: String,
+    pub to: String,
+    pub total_seconds: i64,
+    /// Every date in the range, oldest first
+    pub by_day: Vec<TimeByDay>,
+    pub by_category: Vec<TimeByGroup>,
+    /// A task's time counts toward each of its tags, so these can add up to more
+    /// than `total_seconds`
+    pub by_tag: Vec<TimeByGroup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeByDay {
+    pub date: String,
+    pub seconds: i64,
+}
+
+/// Finished Pomodoro work sessions per day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusTrend {
+    pub date: String,
+    pub pomodoros: i64,
+    pub focus_seconds: i64,
+}
+
+/// Finished Pomodoro work sessions per category; `id` and `name` are `None` for
+/// sessions without a categorized task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusByCategory {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub pomodoros: i64,
+    pub focus_seconds: i64,
+}
+
+/// Time for one category or tag; `id` and `name` are `None` for tasks without one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeByGroup {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub seconds: i64,
+}
+
 // ============================================================
 // Reorder Types
 // ============================================================
@@ -201,21 +704,86 @@ pub struct ReorderTaskItem {
 // Error Types
 // ============================================================
 
+/// Machine-readable error category, serialized as e.g. `"NOT_FOUND"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    DbError,
+    NotFound,
+    ValidationError,
+    SerializeError,
+    IoError,
+    Conflict,
+}
+
+/// Error returned by services and commands. Tauri serializes it as the rejection
+/// value of `invoke`, so the frontend receives `{ code, message, details? }`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppError {
-    pub code: String,
+    pub code: ErrorCode,
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<std::collections::HashMap<String, String>>,
 }
 
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        AppError { code, message: message.into(), details: None }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ValidationError, message)
+    }
+
+    /// Attach a detail entry, e.g. the name of the field that failed validation.
+    pub fn with_detail(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.details.get_or_insert_with(Default::default).insert(key.to_string(), value.into());
+        self
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ErrorCode::DbError => "DB_ERROR",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::ValidationError => "VALIDATION_ERROR",
+            ErrorCode::SerializeError => "SERIALIZE_ERROR",
+            ErrorCode::IoError => "IO_ERROR",
+            ErrorCode::Conflict => "CONFLICT",
+        })
+    }
+}
+
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[{}] {}", self.code, self.message)
     }
 }
 
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::new(ErrorCode::DbError, e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::new(ErrorCode::SerializeError, e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::new(ErrorCode::IoError, e.to_string())
+    }
+}
+
 impl std::error::Error for AppError {}
 
 // ============================================================
@@ -227,6 +795,32 @@ impl std::error::Error for AppError {}
 pub struct CompleteTaskResult {
     pub completed_task: Task,
     pub next_task: Option<Task>,
+    /// Tasks that were waiting only on the completed task
+    pub unblocked_tasks: Vec<Task>,
+}
+
+// ============================================================
+// Bulk Update Result
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUpdateResult {
+    /// False when any task failed, in which case nothing was written
+    pub applied: bool,
+    /// One per distinct id, in the order given
+    pub results: Vec<BulkTaskResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkTaskResult {
+    pub id: String,
+    /// The task as it now is; absent when it was deleted or nothing was applied
+    pub task: Option<Task>,
+    /// The next occurrence spawned by completing a recurring task
+    pub next_task: Option<Task>,
+    pub error: Option<AppError>,
 }
 
 // ============================================================
@@ -276,6 +870,30 @@ pub struct ExportData {
     pub task_tags: Vec<TaskTagRow>,
     #[serde(default)]
     pub notes: Vec<Note>,
+    #[serde(default)]
+    pub task_dependencies: Vec<TaskDependencyRow>,
+    #[serde(default)]
+    pub projects: Vec<ExportProjectRow>,
+    #[serde(default)]
+    pub headings: Vec<Heading>,
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
+    #[serde(default)]
+    pub focus_sessions: Vec<FocusSession>,
+    #[serde(default)]
+    pub templates: Vec<TaskTemplate>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// File contents, present only in a bundle exported with attachments
+    #[serde(default)]
+    pub attachment_files: Vec<AttachmentFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentFile {
+    pub sha256: String,
+    pub data: String, // base64
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -284,13 +902,23 @@ pub struct ExportTaskRow {
     pub id: String,
     pub title: String,
     pub description: Option<String>,
-    pub status: String,
-    pub priority: String,
+    pub status: TaskStatus,
+    pub priority: Priority,
     pub category_id: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub heading_id: Option<String>,
+    #[serde(default)]
+    pub start_date: Option<String>,
     pub due_date: Option<String>,
     pub reminder_time: Option<String>,
     pub recurrence_rule: Option<String>, // JSON string (raw)
+    #[serde(default)]
+    pub someday: bool,
     pub completed_at: Option<String>,
+    #[serde(default)]
+    pub archived_at: Option<String>,
     pub sort_order: i64,
     pub created_at: String,
     pub updated_at: String,
@@ -304,7 +932,7 @@ pub struct ExportSubTaskRow {
     pub parent_id: Option<String>,
     pub title: String,
     pub description: Option<String>,
-    pub priority: String,
+    pub priority: Priority,
     pub due_date: Option<String>,
     pub completed: bool,
     pub sort_order: i64,
@@ -317,3 +945,233 @@ pub struct TaskTagRow {
     pub task_id: String,
     pub tag_id: String,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProjectRow {
+    pub id: String,
+    pub category_id: Option<String>,
+    pub title: String,
+    pub notes: Option<String>,
+    pub deadline: Option<String>,
+    pub status: ProjectStatus,
+    pub completed_at: Option<String>,
+    pub sort_order: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskDependencyRow {
+    pub task_id: String,
+    pub depends_on_id: String,
+    pub created_at: String,
+}
+
+// ============================================================
+// Backup Types
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub kind: String, // "auto" | "manual" | "pre-migration" | "pre-restore"
+    pub size_bytes: i64,
+    pub created_at: String,
+}
+
+// ============================================================
+// Settings Types
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSettings {
+    /// Snapshots kept of each kind (automatic, manual, pre-migration, pre-restore)
+    pub backup_keep: i64,
+    pub backup_interval_hours: i64,
+    pub trash_retention_days: i64,
+    /// Closed tasks move to the logbook this many days after closing
+    pub logbook_archive_days: i64,
+    pub pomodoro_work_minutes: i64,
+    pub pomodoro_short_break_minutes: i64,
+    pub pomodoro_long_break_minutes: i64,
+    /// Every this many finished work sessions, the break is a long one
+    pub pomodoro_long_break_every: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSettingsInput {
+    pub backup_keep: Option<i64>,
+    pub backup_interval_hours: Option<i64>,
+    pub trash_retention_days: Option<i64>,
+    pub logbook_archive_days: Option<i64>,
+    pub pomodoro_work_minutes: Option<i64>,
+    pub pomodoro_short_break_minutes: Option<i64>,
+    pub pomodoro_long_break_minutes: Option<i64>,
+    pub pomodoro_long_break_every: Option<i64>,
+}
+
+// ============================================================
+// Database Maintenance Types
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseReport {
+    pub ok: bool,
+    pub integrity_errors: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    pub orphaned_sub_tasks: Vec<String>,
+    pub invalid_recurrence_rules: Vec<String>,
+    pub invalid_dates: Vec<InvalidDateField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub row_id: i64,
+    pub parent: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidDateField {
+    pub table: String,
+    pub id: String,
+    pub field: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairReport {
+    pub removed_rows: i64,
+    pub cleared_references: i64,
+    pub reattached_sub_tasks: i64,
+    pub cleared_recurrence_rules: i64,
+    pub cleared_dates: i64,
+    pub remaining: DatabaseReport,
+}
+
+// ============================================================
+// Trash Types
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub entity_type: String, // "task" | "subTask" | "note" | "category" | "tag" | "project"
+    pub id: String,
+    pub title: String,
+    pub task_id: Option<String>, // owning task, for sub-tasks
+    pub deleted_at: String,
+}
+
+// ============================================================
+// Logbook Types
+// ============================================================
+
+/// One page of closed tasks, archived or not, grouped by the local date they were
+/// closed on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogbookPage {
+    pub groups: Vec<LogbookGroup>,
+    pub page: i64,
+    pub page_size: i64,
+    /// Closed tasks across all pages
+    pub total: i64,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogbookGroup {
+    pub date: String, // %Y-%m-%d
+    pub tasks: Vec<Task>,
+}
+
+// ============================================================
+// Change Log Types
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeLogEntry {
+    pub id: i64,
+    pub entity_type: String, // "task" | "subTask" | "note" | "category" | "tag" | "project" | "taskTag"
+    pub entity_id: String,
+    pub operation: String, // "create" | "update" | "delete" | "restore" | "purge" | "complete" | "uncomplete" | "reorder" | "archive"
+    pub before: Option<serde_json::Value>, // raw row before the change
+    pub after: Option<serde_json::Value>,  // raw row after the change
+    pub created_at: String,
+}
+
+// ============================================================
+// Undo / Redo Types
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityChange {
+    pub entity_type: String, // "task" | "subTask" | "note" | ...
+    pub entity_id: String,
+    pub change: String, // "created" | "updated" | "deleted" — as seen after the undo/redo
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoResult {
+    pub action: String, // command that was undone/redone, e.g. "completeTask"
+    pub changes: Vec<EntityChange>,
+    pub can_undo: bool,
+    pub can_redo: bool,
+}
+
+// ============================================================
+// Workspace Types
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+    pub active: bool,
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_error_serializes_for_the_frontend() {
+        let err = AppError::validation("Invalid priority").with_detail("field", "priority");
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({ "code": "VALIDATION_ERROR", "message": "Invalid priority", "details": { "field": "priority" } })
+        );
+
+        let err: AppError = rusqlite::Error::QueryReturnedNoRows.into();
+        assert_eq!(err.code, ErrorCode::DbError);
+        assert_eq!(err.to_string(), format!("[DB_ERROR] {}", rusqlite::Error::QueryReturnedNoRows));
+    }
+
+    #[test]
+    fn test_explicit_null_clears_while_missing_leaves_alone() {
+        let input: UpdateTaskInput = serde_json::from_str(r#"{ "dueDate": null, "title": "Renamed" }"#).unwrap();
+        assert_eq!(input.due_date, Some(None));
+        assert_eq!(input.reminder_time, None);
+        assert_eq!(input.title.as_deref(), Some("Renamed"));
+    }
+}
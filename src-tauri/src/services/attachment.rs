@@ -0,0 +1,425 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rusqlite::{Connection, params, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use crate::models::*;
+use crate::services::backup;
+
+// Files are stored in `<app data>/attachments/<database stem>/` as
+// `<sha256>.<extension>`, once however many attachments share them. The extension
+// stays so the OS knows what to open a file with. Every workspace has its own
+// directory, so a file can go as soon as its own database stops referring to it.
+//
+// Attachments aren't tracked in `change_log`: undo can't bring back a file that
+// has been removed, so adding and deleting them is final. Undo refuses to remove a
+// task or note that has attachments (see `undo::ensure_no_dependents`). They go
+// with their task or note when it's purged from the trash, not when it's moved there.
+
+pub const DIR_NAME: &str = "attachments";
+
+/// Longest extension kept in a stored file's name; longer ones are dropped.
+const MAX_EXTENSION_LEN: usize = 16;
+
+/// The directory holding the files of the database at `db_path`.
+pub fn store_dir(data_dir: &Path, db_path: &Path) -> PathBuf {
+    data_dir.join(DIR_NAME).join(backup::db_stem(db_path))
+}
+
+/// Name of the stored file for content `sha256` attached as `file_name`.
+fn blob_name(sha256: &str, file_name: &str) -> String {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| e.len() <= MAX_EXTENSION_LEN && e.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|e| e.to_ascii_lowercase());
+    match extension {
+        Some(extension) => format!("{}.{}", sha256, extension),
+        None => sha256.to_string(),
+    }
+}
+
+fn is_sha256(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+}
+
+pub(crate) fn row_to_attachment(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: row.get("id")?,
+        task_id: row.get("task_id")?,
+        note_id: row.get("note_id")?,
+        file_name: row.get("file_name")?,
+        size_bytes: row.get("size_bytes")?,
+        sha256: row.get("sha256")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+fn get_attachment(conn: &Connection, id: &str) -> Result<Attachment, AppError> {
+    conn.query_row("SELECT * FROM attachments WHERE id = ?1", params![id], row_to_attachment)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Attachment not found").with_detail("id", id))
+}
+
+/// Resolve `task_id`/`note_id` to the one parent they name, as `(column, table, id)`.
+fn parent<'a>(task_id: Option<&'a str>, note_id: Option<&'a str>) -> Result<(&'static str, &'static str, &'a str), AppError> {
+    match (task_id, note_id) {
+        (Some(id), None) => Ok(("task_id", "tasks", id)),
+        (None, Some(id)) => Ok(("note_id", "notes", id)),
+        _ => Err(AppError::validation("Exactly one of taskId and noteId is required").with_detail("field", "taskId")),
+    }
+}
+
+// ============================================================
+// Attachments
+// ============================================================
+
+/// A task's or a note's attachments, oldest first.
+pub fn get_attachments(conn: &Connection, task_id: Option<&str>, note_id: Option<&str>) -> Result<Vec<Attachment>, AppError> {
+    let (column, _, id) = parent(task_id, note_id)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT * FROM attachments WHERE {} = ?1 ORDER BY created_at, file_name",
+        column
+    ))?;
+    let attachments: Vec<Attachment> = stmt
+        .query_map(params![id], row_to_attachment)?
+        .collect::<Result<_, _>>()?;
+    Ok(attachments)
+}
+
+/// Copy the file at `input.source_path` into the store at `dir` and attach it.
+pub fn add_attachment(conn: &Connection, dir: &Path, input: AddAttachmentInput) -> Result<Attachment, AppError> {
+    let (column, table, parent_id) = parent(input.task_id.as_deref(), input.note_id.as_deref())?;
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM {} WHERE id = ?1 AND deleted_at IS NULL", table),
+        params![parent_id],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        let message = if table == "tasks" { "Task not found" } else { "Note not found" };
+        return Err(AppError::not_found(message).with_detail("id", parent_id));
+    }
+
+    let source = Path::new(&input.source_path);
+    let file_name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .filter(|_| source.is_file())
+        .ok_or_else(|| {
+            AppError::validation("sourcePath must be an existing file")
+                .with_detail("field", "sourcePath")
+                .with_detail("value", input.source_path.as_str())
+        })?;
+
+    // Hash while copying, so the stored file is exactly what was hashed even if
+    // the source changes in the meantime
+    fs::create_dir_all(dir)?;
+    let partial = dir.join(format!("{}.partial", Uuid::new_v4()));
+    let mut writer = HashingWriter { file: fs::File::create(&partial)?, hasher: Sha256::new() };
+    let copied = io::copy(&mut fs::File::open(source)?, &mut writer).and_then(|size| writer.file.sync_all().map(|_| size));
+    let size_bytes = match copied {
+        Ok(size) => size as i64,
+        Err(e) => {
+            remove_file_if_exists(&partial)?;
+            return Err(e.into());
+        }
+    };
+    let sha256 = format!("{:x}", writer.hasher.finalize());
+    drop(writer.file);
+    let target = dir.join(blob_name(&sha256, &file_name));
+    if target.exists() {
+        remove_file_if_exists(&partial)?;
+    } else {
+        fs::rename(&partial, &target)?;
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        &format!(
+            "INSERT INTO attachments (id, {}, file_name, size_bytes, sha256, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            column
+        ),
+        params![id, parent_id, file_name, size_bytes, sha256, now],
+    )?;
+    get_attachment(conn, &id)
+}
+
+/// Writes to `file` and feeds the same bytes to `hasher`.
+struct HashingWriter {
+    file: fs::File,
+    hasher: Sha256,
+}
+
+impl io::Write for HashingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Delete an attachment, and its file once no other attachment shares it.
+pub fn delete_attachment(conn: &Connection, dir: &Path, id: &str) -> Result<(), AppError> {
+    let attachment = get_attachment(conn, id)?;
+    conn.execute("DELETE FROM attachments WHERE id = ?1", params![id])?;
+
+    let name = blob_name(&attachment.sha256, &attachment.file_name);
+    if !referenced_blobs(conn)?.contains(&name) {
+        remove_file_if_exists(&dir.join(name))?;
+    }
+    Ok(())
+}
+
+/// Where the attachment's file is on disk, to open it with.
+pub fn get_attachment_path(conn: &Connection, dir: &Path, id: &str) -> Result<String, AppError> {
+    let attachment = get_attachment(conn, id)?;
+    let path = dir.join(blob_name(&attachment.sha256, &attachment.file_name));
+    if !path.is_file() {
+        // e.g. an attachment brought back by restoring a backup
+        return Err(AppError::not_found("Attachment file is missing")
+            .with_detail("id", id)
+            .with_detail("path", path.to_string_lossy()));
+    }
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Remove stored files that no attachment refers to any more, e.g. after their
+/// task or note was purged. Returns the number of files removed.
+pub fn remove_unreferenced(conn: &Connection, dir: &Path) -> Result<usize, AppError> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let referenced = referenced_blobs(conn)?;
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_file() && !referenced.contains(&name) {
+            remove_file_if_exists(&entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn referenced_blobs(conn: &Connection) -> Result<HashSet<String>, AppError> {
+    let mut stmt = conn.prepare("SELECT DISTINCT sha256, file_name FROM attachments")?;
+    let names: HashSet<String> = stmt
+        .query_map([], |row| Ok(blob_name(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?)))?
+        .collect::<Result<_, _>>()?;
+    Ok(names)
+}
+
+fn remove_file_if_exists(path: &Path) -> Result<(), AppError> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+// ============================================================
+// Export / Import
+// ============================================================
+
+/// The contents of the given attachments' files, once per distinct SHA-256.
+/// Files missing from the store are left out.
+pub fn bundle_files(dir: &Path, attachments: &[Attachment]) -> Result<Vec<AttachmentFile>, AppError> {
+    let mut files: Vec<AttachmentFile> = Vec::new();
+    for attachment in attachments {
+        if files.iter().any(|f| f.sha256 == attachment.sha256) {
+            continue;
+        }
+        let path = dir.join(blob_name(&attachment.sha256, &attachment.file_name));
+        match fs::read(&path) {
+            Ok(bytes) => files.push(AttachmentFile { sha256: attachment.sha256.clone(), data: BASE64.encode(bytes) }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(files)
+}
+
+/// Decode a bundle's files, checking each against its SHA-256.
+pub fn decode_bundle_files(files: &[AttachmentFile]) -> Result<HashMap<String, Vec<u8>>, AppError> {
+    let mut decoded = HashMap::new();
+    for (i, file) in files.iter().enumerate() {
+        let bytes = BASE64
+            .decode(&file.data)
+            .ok()
+            .filter(|bytes| format!("{:x}", Sha256::digest(bytes)) == file.sha256)
+            .ok_or_else(|| {
+                let field = format!("attachmentFiles[{}].data", i);
+                AppError::validation(format!("{} doesn't match its SHA-256", field)).with_detail("field", field)
+            })?;
+        decoded.insert(file.sha256.clone(), bytes);
+    }
+    Ok(decoded)
+}
+
+/// Check imported attachment rows before anything is written.
+pub fn validate_rows(attachments: &[Attachment]) -> Result<(), AppError> {
+    for (i, attachment) in attachments.iter().enumerate() {
+        if !is_sha256(&attachment.sha256) {
+            let field = format!("attachments[{}].sha256", i);
+            return Err(AppError::validation(format!("{} must be a lowercase hex SHA-256", field))
+                .with_detail("field", field)
+                .with_detail("value", attachment.sha256.as_str()));
+        }
+    }
+    Ok(())
+}
+
+/// Import attachment rows, writing their files from `files` into the store at
+/// `dir` as needed. Rows whose file is neither stored nor in `files` are skipped.
+pub fn import_attachments(
+    conn: &Connection,
+    dir: &Path,
+    attachments: &[Attachment],
+    files: &HashMap<String, Vec<u8>>,
+) -> Result<(), AppError> {
+    for attachment in attachments {
+        let target = dir.join(blob_name(&attachment.sha256, &attachment.file_name));
+        let bytes = if target.exists() {
+            None
+        } else {
+            let Some(bytes) = files.get(&attachment.sha256) else { continue };
+            Some(bytes)
+        };
+        // The row goes in first, so a row that fails (e.g. its task isn't there)
+        // leaves no file behind
+        conn.execute(
+            "INSERT OR IGNORE INTO attachments (id, task_id, note_id, file_name, size_bytes, sha256, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                attachment.id, attachment.task_id, attachment.note_id, attachment.file_name,
+                attachment.size_bytes, attachment.sha256, attachment.created_at,
+            ],
+        )?;
+        if let Some(bytes) = bytes {
+            fs::create_dir_all(dir)?;
+            let partial = dir.join(format!("{}.partial", Uuid::new_v4()));
+            fs::write(&partial, bytes)?;
+            fs::rename(&partial, &target)?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_test_task, open_test_connection};
+    use crate::services::{task, trash};
+
+    struct TestDir(PathBuf);
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn setup() -> (TestDir, Connection, Task) {
+        let dir = std::env::temp_dir().join(format!("watermelon-attachment-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let conn = open_test_connection();
+        let t = create_test_task(&conn, "Design review");
+        (TestDir(dir), conn, t)
+    }
+
+    fn attach(conn: &Connection, dir: &Path, task_id: &str, source: &Path) -> Attachment {
+        add_attachment(conn, &dir.join("store"), AddAttachmentInput {
+            task_id: Some(task_id.to_string()),
+            note_id: None,
+            source_path: source.to_string_lossy().into_owned(),
+        })
+        .unwrap()
+    }
+
+    fn stored(dir: &Path) -> usize {
+        fs::read_dir(dir.join("store")).map_or(0, |entries| entries.count())
+    }
+
+    #[test]
+    fn test_identical_files_are_stored_once() {
+        let (dir, conn, t) = setup();
+        let (a, b) = (dir.0.join("mock.png"), dir.0.join("copy.PNG"));
+        fs::write(&a, b"pixels").unwrap();
+        fs::write(&b, b"pixels").unwrap();
+
+        let first = attach(&conn, &dir.0, &t.id, &a);
+        let second = attach(&conn, &dir.0, &t.id, &b);
+        assert_eq!(first.sha256, second.sha256);
+        assert_eq!((first.file_name.as_str(), first.size_bytes), ("mock.png", 6));
+        assert_eq!(stored(&dir.0), 1);
+        assert_eq!(get_attachments(&conn, Some(&t.id), None).unwrap().len(), 2);
+        let path = get_attachment_path(&conn, &dir.0.join("store"), &first.id).unwrap();
+        assert!(path.ends_with(&format!("{}.png", first.sha256)));
+
+        // The file stays while another attachment uses it
+        delete_attachment(&conn, &dir.0.join("store"), &first.id).unwrap();
+        assert_eq!(stored(&dir.0), 1);
+        delete_attachment(&conn, &dir.0.join("store"), &second.id).unwrap();
+        assert_eq!(stored(&dir.0), 0);
+
+        let err = add_attachment(&conn, &dir.0.join("store"), AddAttachmentInput {
+            task_id: Some(t.id.clone()),
+            note_id: None,
+            source_path: dir.0.join("missing.pdf").to_string_lossy().into_owned(),
+        })
+        .unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "sourcePath");
+    }
+
+    #[test]
+    fn test_purging_the_task_removes_its_files() {
+        let (dir, conn, t) = setup();
+        let source = dir.0.join("spec.pdf");
+        fs::write(&source, b"%PDF").unwrap();
+        attach(&conn, &dir.0, &t.id, &source);
+
+        task::delete_task(&conn, &t.id).unwrap();
+        assert_eq!(remove_unreferenced(&conn, &dir.0.join("store")).unwrap(), 0);
+        trash::purge_trash(&conn, None).unwrap();
+        assert!(get_attachments(&conn, Some(&t.id), None).unwrap().is_empty());
+        assert_eq!(remove_unreferenced(&conn, &dir.0.join("store")).unwrap(), 1);
+        assert_eq!(stored(&dir.0), 0);
+    }
+
+    #[test]
+    fn test_bundles_round_trip_and_are_checked() {
+        let (dir, conn, t) = setup();
+        let source = dir.0.join("notes.txt");
+        fs::write(&source, b"hello").unwrap();
+        let attachment = attach(&conn, &dir.0, &t.id, &source);
+
+        let files = bundle_files(&dir.0.join("store"), std::slice::from_ref(&attachment)).unwrap();
+        let decoded = decode_bundle_files(&files).unwrap();
+        let other = dir.0.join("other");
+        conn.execute("DELETE FROM attachments", []).unwrap();
+        import_attachments(&conn, &other, std::slice::from_ref(&attachment), &decoded).unwrap();
+        assert_eq!(fs::read(get_attachment_path(&conn, &other, &attachment.id).unwrap()).unwrap(), b"hello");
+
+        let dangling = Attachment { id: "a2".into(), task_id: Some("missing".into()), ..attachment.clone() };
+        let err = import_attachments(&conn, &dir.0.join("third"), &[dangling], &decoded).unwrap_err();
+        assert_eq!(err.code, ErrorCode::DbError);
+        assert!(!dir.0.join("third").exists());
+
+        let tampered = vec![AttachmentFile { sha256: attachment.sha256.clone(), data: BASE64.encode(b"bye") }];
+        let err = decode_bundle_files(&tampered).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "attachmentFiles[0].data");
+    }
+}
@@ -0,0 +1,259 @@
+use rusqlite::{Connection, DatabaseName};
+use chrono::NaiveDateTime;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::db::Database;
+use crate::models::*;
+use crate::services::settings;
+
+pub const KIND_AUTO: &str = "auto";
+pub const KIND_MANUAL: &str = "manual";
+pub const KIND_PRE_MIGRATION: &str = "pre-migration";
+pub const KIND_PRE_RESTORE: &str = "pre-restore";
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+
+/// Snapshots are named `<db stem>.<timestamp>.<kind>.db`, so several databases can
+/// share one backup directory.
+pub(crate) fn db_stem(db_path: &Path) -> String {
+    db_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "watermelon".to_string())
+}
+
+fn parse_backup_file_name(db_path: &Path, file_name: &str) -> Option<(NaiveDateTime, String)> {
+    let rest = file_name
+        .strip_prefix(&format!("{}.", db_stem(db_path)))?
+        .strip_suffix(".db")?;
+    let (timestamp, kind) = rest.split_once('.')?;
+    let created = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+    Some((created, kind.to_string()))
+}
+
+/// Number of snapshots to keep, falling back to the default when the settings table
+/// does not exist yet (e.g. before migrations have run).
+pub fn backup_keep_setting(conn: &Connection) -> usize {
+    let keep = settings::get_settings(conn).unwrap_or_default().backup_keep;
+    keep.max(1) as usize
+}
+
+/// Copy the live database into a new snapshot using SQLite's online backup API.
+/// Works from a read-only connection, so it does not block writers.
+pub fn create_backup(conn: &Connection, db_path: &Path, backup_dir: &Path, kind: &str) -> Result<BackupInfo, AppError> {
+    fs::create_dir_all(backup_dir)?;
+
+    let now = chrono::Utc::now().naive_utc();
+    let file_name = format!("{}.{}.{}.db", db_stem(db_path), now.format(TIMESTAMP_FORMAT), kind);
+    let target = backup_dir.join(&file_name);
+    let partial = backup_dir.join(format!("{}.partial", file_name));
+
+    if let Err(e) = conn.backup(DatabaseName::Main, &partial, None) {
+        let _ = fs::remove_file(&partial);
+        return Err(e.into());
+    }
+    fs::rename(&partial, &target)?;
+
+    let size_bytes = fs::metadata(&target)?.len() as i64;
+    log::info!("Created {} backup {:?}", kind, target);
+
+    Ok(BackupInfo {
+        file_name,
+        kind: kind.to_string(),
+        size_bytes,
+        created_at: now.and_utc().to_rfc3339(),
+    })
+}
+
+/// Create a snapshot and prune old ones of the same kind down to `keep`.
+pub fn snapshot(conn: &Connection, db_path: &Path, backup_dir: &Path, kind: &str, keep: usize) -> Result<BackupInfo, AppError> {
+    let info = create_backup(conn, db_path, backup_dir, kind)?;
+    prune_backups(db_path, backup_dir, kind, keep, None)?;
+    Ok(info)
+}
+
+/// List snapshots of the given database, newest first.
+pub fn list_backups(db_path: &Path, backup_dir: &Path) -> Result<Vec<BackupInfo>, AppError> {
+    if !backup_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut backups: Vec<(NaiveDateTime, BackupInfo)> = Vec::new();
+    for entry in fs::read_dir(backup_dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some((created, kind)) = parse_backup_file_name(db_path, &file_name) else { continue };
+        let size_bytes = entry.metadata()?.len() as i64;
+        backups.push((created, BackupInfo {
+            file_name,
+            kind,
+            size_bytes,
+            created_at: created.and_utc().to_rfc3339(),
+        }));
+    }
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.0));
+    Ok(backups.into_iter().map(|(_, info)| info).collect())
+}
+
+/// Delete all but the `keep` newest snapshots of `kind`, sparing `except`. Each
+/// kind counts on its own, so frequent automatic snapshots never push out a manual
+/// or pre-restore one.
+pub fn prune_backups(db_path: &Path, backup_dir: &Path, kind: &str, keep: usize, except: Option<&str>) -> Result<(), AppError> {
+    let old = list_backups(db_path, backup_dir)?
+        .into_iter()
+        .filter(|b| b.kind == kind && Some(b.file_name.as_str()) != except)
+        .skip(keep);
+    for old in old {
+        fs::remove_file(backup_dir.join(&old.file_name))?;
+    }
+    Ok(())
+}
+
+fn resolve_backup(db: &Database, file_name: &str) -> Result<PathBuf, AppError> {
+    list_backups(&db.path(), db.backup_dir())?
+        .into_iter()
+        .find(|b| b.file_name == file_name)
+        .map(|b| db.backup_dir().join(b.file_name))
+        .ok_or_else(|| AppError::not_found(format!("Backup {} not found", file_name)))
+}
+
+// ============================================================
+// Operations on the managed Database
+// ============================================================
+
+pub fn create_manual_backup(db: &Database) -> Result<BackupInfo, AppError> {
+    let conn = db.read();
+    snapshot(&conn, &db.path(), db.backup_dir(), KIND_MANUAL, backup_keep_setting(&conn))
+}
+
+/// Take an automatic snapshot if the newest one is older than the configured interval.
+pub fn run_scheduled_backup(db: &Database) -> Result<Option<BackupInfo>, AppError> {
+    let conn = db.read();
+    let settings = settings::get_settings(&conn)?;
+
+    let latest_auto = list_backups(&db.path(), db.backup_dir())?
+        .into_iter()
+        .find(|b| b.kind == KIND_AUTO);
+    if let Some(latest) = latest_auto {
+        let taken = chrono::DateTime::parse_from_rfc3339(&latest.created_at)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .unwrap_or_default();
+        if chrono::Utc::now() - taken < chrono::Duration::hours(settings.backup_interval_hours) {
+            return Ok(None);
+        }
+    }
+
+    snapshot(&conn, &db.path(), db.backup_dir(), KIND_AUTO, settings.backup_keep.max(1) as usize).map(Some)
+}
+
+/// Restore a snapshot over the live database. The current state is snapshotted
+/// first so a restore can itself be undone.
+pub fn restore_backup(db: &Database, file_name: &str) -> Result<(), AppError> {
+    let source = resolve_backup(db, file_name)?;
+
+    let keep = {
+        let conn = db.read();
+        create_backup(&conn, &db.path(), db.backup_dir(), KIND_PRE_RESTORE)?;
+        backup_keep_setting(&conn)
+    };
+
+    db.restore_from(&source)?;
+
+    // The snapshot just restored from stays, whatever its age
+    prune_backups(&db.path(), db.backup_dir(), KIND_PRE_RESTORE, keep, Some(file_name))
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    struct TestDir(PathBuf);
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn setup_db() -> (TestDir, Database) {
+        let dir = std::env::temp_dir().join(format!("watermelon-backup-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(&dir.join("watermelon.db"), &dir.join("backups")).unwrap();
+        (TestDir(dir), db)
+    }
+
+    fn insert_tag(db: &Database, name: &str) {
+        db.write()
+            .execute(
+                "INSERT INTO tags (id, name, color, created_at) VALUES (?1, ?1, NULL, '2024-01-01T00:00:00Z')",
+                params![name],
+            )
+            .unwrap();
+    }
+
+    fn tag_count(db: &Database) -> i64 {
+        db.read().query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_lists_and_prunes() {
+        let (_dir, db) = setup_db();
+        for _ in 0..3 {
+            let conn = db.read();
+            snapshot(&conn, &db.path(), db.backup_dir(), KIND_MANUAL, 2).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let backups = list_backups(&db.path(), db.backup_dir()).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert!(backups[0].created_at >= backups[1].created_at);
+        assert!(backups.iter().all(|b| b.kind == KIND_MANUAL && b.size_bytes > 0));
+    }
+
+    #[test]
+    fn test_restore_backup_replaces_contents() {
+        let (_dir, db) = setup_db();
+        insert_tag(&db, "before");
+        let backup = create_manual_backup(&db).unwrap();
+        insert_tag(&db, "after");
+        assert_eq!(tag_count(&db), 2);
+
+        restore_backup(&db, &backup.file_name).unwrap();
+        assert_eq!(tag_count(&db), 1);
+
+        // The pre-restore state was kept and the writer still works
+        let kinds: Vec<String> = list_backups(&db.path(), db.backup_dir()).unwrap().into_iter().map(|b| b.kind).collect();
+        assert!(kinds.contains(&KIND_PRE_RESTORE.to_string()));
+        insert_tag(&db, "again");
+        assert_eq!(tag_count(&db), 2);
+    }
+
+    #[test]
+    fn test_snapshots_are_pruned_per_kind() {
+        let (_dir, db) = setup_db();
+        let manual = create_manual_backup(&db).unwrap();
+        for _ in 0..3 {
+            let conn = db.read();
+            snapshot(&conn, &db.path(), db.backup_dir(), KIND_AUTO, 2).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let backups = list_backups(&db.path(), db.backup_dir()).unwrap();
+        assert_eq!(backups.iter().filter(|b| b.kind == KIND_AUTO).count(), 2);
+        assert!(backups.iter().any(|b| b.file_name == manual.file_name));
+    }
+
+    #[test]
+    fn test_scheduled_backup_respects_interval() {
+        let (_dir, db) = setup_db();
+        assert!(run_scheduled_backup(&db).unwrap().is_some());
+        assert!(run_scheduled_backup(&db).unwrap().is_none());
+        assert!(restore_backup(&db, "missing.db").is_err());
+    }
+}
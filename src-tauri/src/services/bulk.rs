@@ -0,0 +1,182 @@
+use rusqlite::{Connection, params};
+use crate::models::*;
+use crate::services::{change_log, tag, task};
+
+// A bulk update is all or nothing. Each task runs in its own savepoint so a failure
+// can be reported against its id, then the batch as a whole is kept only if every
+// task went through.
+
+/// Apply the same field changes, tag additions and removals, and action to every
+/// task in `input.ids`, in that order.
+pub fn bulk_update_tasks(conn: &Connection, input: BulkUpdateTasksInput) -> Result<BulkUpdateResult, AppError> {
+    let action = input
+        .action
+        .as_deref()
+        .map(|a| BulkTaskAction::parse("action", a))
+        .transpose()?;
+    let add_tag_ids = input.add_tag_ids.unwrap_or_default();
+    let remove_tag_ids = input.remove_tag_ids.unwrap_or_default();
+    for tag_id in &add_tag_ids {
+        let exists: bool = conn
+            .query_row("SELECT COUNT(*) > 0 FROM tags WHERE id = ?1 AND deleted_at IS NULL", params![tag_id], |row| row.get(0))?;
+        if !exists {
+            return Err(AppError::not_found("Tag not found").with_detail("id", tag_id.as_str()));
+        }
+    }
+
+    let mut ids: Vec<String> = Vec::new();
+    for id in input.ids {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    let mut results: Vec<BulkTaskResult> = Vec::new();
+    let outcome = change_log::in_transaction(conn, || {
+        for id in &ids {
+            let applied = change_log::in_transaction(conn, || {
+                apply(conn, id, input.changes.as_ref(), &add_tag_ids, &remove_tag_ids, action)
+            });
+            results.push(match applied {
+                Ok((task, next_task)) => BulkTaskResult { id: id.clone(), task, next_task, error: None },
+                Err(e) => BulkTaskResult { id: id.clone(), task: None, next_task: None, error: Some(e) },
+            });
+        }
+        match results.iter().find_map(|r| r.error.clone()) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    });
+
+    let applied = match outcome {
+        Ok(()) => true,
+        Err(_) if results.iter().any(|r| r.error.is_some()) => false,
+        // The batch itself failed to commit
+        Err(e) => return Err(e),
+    };
+    if !applied {
+        for result in &mut results {
+            result.task = None;
+            result.next_task = None;
+        }
+    }
+    Ok(BulkUpdateResult { applied, results })
+}
+
+/// Apply the bulk changes to one task. Returns the task afterwards and the next
+/// occurrence its completion spawned, if any.
+fn apply(
+    conn: &Connection,
+    id: &str,
+    changes: Option<&UpdateTaskInput>,
+    add_tag_ids: &[String],
+    remove_tag_ids: &[String],
+    action: Option<BulkTaskAction>,
+) -> Result<(Option<Task>, Option<Task>), AppError> {
+    let existing = task::get_task_by_id(conn, id)?
+        .ok_or_else(|| AppError::not_found("Task not found").with_detail("id", id))?;
+
+    if let Some(changes) = changes {
+        task::update_task(conn, id, changes.clone())?;
+    }
+    for tag_id in add_tag_ids {
+        tag::add_tag_to_task(conn, id, tag_id)?;
+    }
+    for tag_id in remove_tag_ids {
+        tag::remove_tag_from_task(conn, id, tag_id)?;
+    }
+
+    let mut next_task = None;
+    match action {
+        // Completing a closed task again would spawn its next occurrence twice
+        Some(BulkTaskAction::Complete) if existing.status == TaskStatus::Todo => {
+            next_task = task::complete_task(conn, id)?.next_task;
+        }
+        Some(BulkTaskAction::Uncomplete) if existing.status != TaskStatus::Todo => {
+            task::uncomplete_task(conn, id)?;
+        }
+        Some(BulkTaskAction::Delete) => task::delete_task(conn, id)?,
+        _ => {}
+    }
+
+    Ok((task::get_task_by_id(conn, id)?, next_task))
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+
+    fn new_task(conn: &Connection, title: &str, recurrence_rule: Option<RecurrenceRule>) -> Task {
+        task::create_task(conn, CreateTaskInput {
+            title: title.to_string(),
+            due_date: Some("2024-05-01".into()),
+            recurrence_rule,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bulk_changes_apply_to_every_task() {
+        let conn = open_test_connection();
+        let daily = RecurrenceRule { rule_type: "daily".into(), interval: 1, days_of_week: None, day_of_month: None, end_date: None };
+        let (a, b) = (new_task(&conn, "A", None), new_task(&conn, "B", Some(daily)));
+        let t = tag::create_tag(&conn, "work", None).unwrap();
+
+        let result = bulk_update_tasks(&conn, BulkUpdateTasksInput {
+            ids: vec![a.id.clone(), b.id.clone(), a.id.clone()],
+            changes: Some(UpdateTaskInput { priority: Some("high".into()), ..Default::default() }),
+            add_tag_ids: Some(vec![t.id.clone()]),
+            action: Some("complete".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(result.applied);
+        assert_eq!(result.results.len(), 2);
+        let done = result.results[1].task.as_ref().unwrap();
+        assert_eq!((done.priority, done.status), (Priority::High, TaskStatus::Completed));
+        assert_eq!(done.tags.as_ref().unwrap()[0].id, t.id);
+        let next = result.results[1].next_task.as_ref().unwrap();
+        assert_eq!(next.due_date.as_deref(), Some("2024-05-02"));
+
+        // Completing again doesn't spawn another occurrence
+        let again = bulk_update_tasks(&conn, BulkUpdateTasksInput {
+            ids: vec![b.id.clone()],
+            action: Some("complete".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(again.results[0].next_task.is_none());
+        assert_eq!(task::get_all_tasks(&conn, None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_one_failure_rolls_back_the_batch() {
+        let conn = open_test_connection();
+        let (a, b) = (new_task(&conn, "A", None), new_task(&conn, "B", None));
+
+        let result = bulk_update_tasks(&conn, BulkUpdateTasksInput {
+            ids: vec![a.id.clone(), "missing".into(), b.id.clone()],
+            action: Some("delete".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!result.applied);
+        let errors: Vec<Option<ErrorCode>> = result.results.iter().map(|r| r.error.as_ref().map(|e| e.code)).collect();
+        assert_eq!(errors, vec![None, Some(ErrorCode::NotFound), None]);
+        assert_eq!(task::get_all_tasks(&conn, None).unwrap().len(), 2);
+
+        let err = bulk_update_tasks(&conn, BulkUpdateTasksInput {
+            ids: vec![a.id.clone()],
+            action: Some("archive".into()),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "action");
+    }
+}
@@ -1,6 +1,8 @@
 use rusqlite::{Connection, params, OptionalExtension};
 use uuid::Uuid;
 use crate::models::*;
+use crate::services::change_log::{self, CATEGORY};
+use crate::utils::validate;
 
 fn row_to_category(row: &rusqlite::Row) -> rusqlite::Result<Category> {
     Ok(Category {
@@ -14,18 +16,15 @@ fn row_to_category(row: &rusqlite::Row) -> rusqlite::Result<Category> {
 
 pub fn create_category(conn: &Connection, input: CreateCategoryInput) -> Result<Category, AppError> {
     if input.name.trim().is_empty() {
-        return Err(AppError { code: "VALIDATION_ERROR".into(), message: "Category name must not be empty".into(), details: None });
+        return Err(AppError::validation("Category name must not be empty"));
     }
-
-    // Check duplicate
-    let dup: Option<String> = conn
-        .query_row("SELECT id FROM categories WHERE name = ?1", params![input.name.trim()], |row| row.get(0))
-        .optional()
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-    if dup.is_some() {
-        return Err(AppError { code: "VALIDATION_ERROR".into(), message: "Category name already exists".into(), details: None });
+    if let Some(ref color) = input.color {
+        validate::color("color", color)?;
     }
 
+    // Check duplicate (names stay reserved while a category is in the trash)
+    check_duplicate_name(conn, input.name.trim(), None)?;
+
     let id = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
@@ -33,11 +32,13 @@ pub fn create_category(conn: &Connection, input: CreateCategoryInput) -> Result<
         .query_row("SELECT COALESCE(MAX(sort_order), -1) FROM categories", [], |row| row.get(0))
         .unwrap_or(-1);
 
-    conn.execute(
-        "INSERT INTO categories (id, name, color, sort_order, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![id, input.name.trim(), input.color, max_order + 1, now],
-    )
-    .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    change_log::tracked(conn, CATEGORY, &id, "create", || {
+        conn.execute(
+            "INSERT INTO categories (id, name, color, sort_order, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, input.name.trim(), input.color, max_order + 1, now],
+        )
+        .map_err(AppError::from)
+    })?;
 
     Ok(Category {
         id,
@@ -49,36 +50,28 @@ pub fn create_category(conn: &Connection, input: CreateCategoryInput) -> Result<
 }
 
 pub fn get_all_categories(conn: &Connection) -> Result<Vec<Category>, AppError> {
-    let mut stmt = conn
-        .prepare("SELECT * FROM categories ORDER BY sort_order")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn.prepare("SELECT * FROM categories WHERE deleted_at IS NULL ORDER BY sort_order")?;
     let cats: Vec<Category> = stmt
-        .query_map([], row_to_category)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        .query_map([], row_to_category)?
+        .collect::<Result<_, _>>()?;
     Ok(cats)
 }
 
 pub fn update_category(conn: &Connection, id: &str, input: UpdateCategoryInput) -> Result<Category, AppError> {
     let _existing = conn
-        .query_row("SELECT * FROM categories WHERE id = ?1", params![id], row_to_category)
-        .optional()
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .ok_or_else(|| AppError { code: "NOT_FOUND".into(), message: "Category not found".into(), details: None })?;
+        .query_row("SELECT * FROM categories WHERE id = ?1 AND deleted_at IS NULL", params![id], row_to_category)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Category not found"))?;
 
     if let Some(ref name) = input.name {
         if name.trim().is_empty() {
-            return Err(AppError { code: "VALIDATION_ERROR".into(), message: "Category name must not be empty".into(), details: None });
+            return Err(AppError::validation("Category name must not be empty"));
         }
         // Check duplicate (excluding current)
-        let dup: Option<String> = conn
-            .query_row("SELECT id FROM categories WHERE name = ?1 AND id != ?2", params![name.trim(), id], |row| row.get(0))
-            .optional()
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-        if dup.is_some() {
-            return Err(AppError { code: "VALIDATION_ERROR".into(), message: "Category name already exists".into(), details: None });
-        }
+        check_duplicate_name(conn, name.trim(), Some(id))?;
+    }
+    if let Some(ref color) = input.color {
+        validate::color("color", color)?;
     }
 
     let mut sets: Vec<String> = Vec::new();
@@ -101,16 +94,41 @@ pub fn update_category(conn: &Connection, id: &str, input: UpdateCategoryInput)
         values.push(Box::new(id.to_string()));
         let sql = format!("UPDATE categories SET {} WHERE id = ?", sets.join(", "));
         let params_refs: Vec<&dyn rusqlite::types::ToSql> = values.iter().map(|v| v.as_ref()).collect();
-        conn.execute(&sql, params_refs.as_slice())
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+        change_log::tracked(conn, CATEGORY, id, "update", || {
+            conn.execute(&sql, params_refs.as_slice())
+                .map_err(AppError::from)
+        })?;
     }
 
     conn.query_row("SELECT * FROM categories WHERE id = ?1", params![id], row_to_category)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })
+        .map_err(AppError::from)
 }
 
+/// Move a category to the trash. Its tasks keep their `category_id` until the
+/// category is purged, so restoring it regroups them.
 pub fn delete_category(conn: &Connection, id: &str) -> Result<(), AppError> {
-    conn.execute("DELETE FROM categories WHERE id = ?1", params![id])
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let now = chrono::Utc::now().to_rfc3339();
+    change_log::tracked(conn, CATEGORY, id, "delete", || {
+        conn.execute(
+            "UPDATE categories SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(AppError::from)
+    })?;
     Ok(())
 }
+
+fn check_duplicate_name(conn: &Connection, name: &str, exclude_id: Option<&str>) -> Result<(), AppError> {
+    let dup: Option<bool> = conn
+        .query_row(
+            "SELECT deleted_at IS NOT NULL FROM categories WHERE name = ?1 AND id != COALESCE(?2, '')",
+            params![name, exclude_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match dup {
+        Some(true) => Err(AppError::validation("Category name already exists in the trash")),
+        Some(false) => Err(AppError::validation("Category name already exists")),
+        None => Ok(()),
+    }
+}
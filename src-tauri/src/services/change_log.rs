@@ -0,0 +1,246 @@
+use rusqlite::{Connection, params, OptionalExtension};
+use rusqlite::types::ValueRef;
+use serde_json::Value;
+use crate::models::*;
+
+pub const TASK: &str = "task";
+pub const SUB_TASK: &str = "subTask";
+pub const NOTE: &str = "note";
+pub const CATEGORY: &str = "category";
+pub const TAG: &str = "tag";
+pub const PROJECT: &str = "project";
+pub const HEADING: &str = "heading";
+pub const TIME_ENTRY: &str = "timeEntry";
+pub const TEMPLATE: &str = "template";
+/// A task ↔ tag link; `entity_id` is the task id.
+pub const TASK_TAG: &str = "taskTag";
+/// A "task depends on task" link; `entity_id` is the dependent task's id.
+pub const TASK_DEPENDENCY: &str = "taskDependency";
+
+/// A link table without an `id` column. Its rows are logged under the owning task,
+/// as `{ owner_column: .., other_column: .. }`.
+pub struct Link {
+    pub table: &'static str,
+    pub owner_column: &'static str,
+    pub other_column: &'static str,
+}
+
+pub fn link_for(entity_type: &str) -> Option<Link> {
+    match entity_type {
+        TASK_TAG => Some(Link { table: "task_tags", owner_column: "task_id", other_column: "tag_id" }),
+        TASK_DEPENDENCY => Some(Link { table: "task_dependencies", owner_column: "task_id", other_column: "depends_on_id" }),
+        _ => None,
+    }
+}
+
+pub fn table_for(entity_type: &str) -> Result<&'static str, AppError> {
+    match entity_type {
+        TASK => Ok("tasks"),
+        SUB_TASK => Ok("sub_tasks"),
+        NOTE => Ok("notes"),
+        CATEGORY => Ok("categories"),
+        TAG => Ok("tags"),
+        PROJECT => Ok("projects"),
+        HEADING => Ok("headings"),
+        TIME_ENTRY => Ok("time_entries"),
+        TEMPLATE => Ok("task_templates"),
+        _ => Err(AppError::validation(format!("Unknown entity type: {}", entity_type))),
+    }
+}
+
+// ============================================================
+// Transactions
+// ============================================================
+
+/// Run `f` inside a savepoint: everything it writes is committed together or not at
+/// all. Savepoints nest, so services can call each other freely.
+pub fn in_transaction<T>(conn: &Connection, f: impl FnOnce() -> Result<T, AppError>) -> Result<T, AppError> {
+    conn.execute_batch("SAVEPOINT change")?;
+    match f() {
+        Ok(value) => {
+            conn.execute_batch("RELEASE change")?;
+            Ok(value)
+        }
+        Err(e) => {
+            // Best effort: the original error is the one worth reporting
+            let _ = conn.execute_batch("ROLLBACK TO change; RELEASE change");
+            Err(e)
+        }
+    }
+}
+
+/// Run the write `f` against one entity and log its row before and after, all in
+/// one transaction. Nothing is logged when the row didn't change.
+pub fn tracked<T>(
+    conn: &Connection,
+    entity_type: &str,
+    entity_id: &str,
+    operation: &str,
+    f: impl FnOnce() -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    in_transaction(conn, || {
+        let before = snapshot(conn, entity_type, entity_id)?;
+        let value = f()?;
+        let after = snapshot(conn, entity_type, entity_id)?;
+        if before != after {
+            record(conn, entity_type, entity_id, operation, before.as_ref(), after.as_ref())?;
+        }
+        Ok(value)
+    })
+}
+
+// ============================================================
+// Log access
+// ============================================================
+
+/// The raw row of an entity as a JSON object keyed by column name, trashed or not.
+pub fn snapshot(conn: &Connection, entity_type: &str, id: &str) -> Result<Option<Value>, AppError> {
+    let table = table_for(entity_type)?;
+    let mut stmt = conn
+        .prepare(&format!("SELECT * FROM {} WHERE id = ?1", table))?;
+    let names: Vec<String> = stmt.column_names().iter().map(|n| n.to_string()).collect();
+
+    stmt.query_row(params![id], |row| {
+        let mut object = serde_json::Map::new();
+        for (i, name) in names.iter().enumerate() {
+            let value = match row.get_ref(i)? {
+                ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+                ValueRef::Integer(n) => Value::from(n),
+                ValueRef::Real(f) => Value::from(f),
+                ValueRef::Text(t) => Value::from(String::from_utf8_lossy(t).into_owned()),
+            };
+            object.insert(name.clone(), value);
+        }
+        Ok(Value::Object(object))
+    })
+    .optional()
+    .map_err(AppError::from)
+}
+
+pub fn record(
+    conn: &Connection,
+    entity_type: &str,
+    entity_id: &str,
+    operation: &str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> Result<(), AppError> {
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO change_log (entity_type, entity_id, operation, before_json, after_json, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            entity_type,
+            entity_id,
+            operation,
+            before.map(|v| v.to_string()),
+            after.map(|v| v.to_string()),
+            now,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Read a `before_json`/`after_json` column. NULL means there was no row on that
+/// side; JSON that doesn't parse fails the read rather than passing for NULL.
+pub(crate) fn snapshot_column(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Option<Value>> {
+    let Some(raw) = row.get::<_, Option<String>>(index)? else {
+        return Ok(None);
+    };
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+/// Every logged change to one entity, oldest first. A task's history includes its
+/// tag links and dependencies.
+pub fn get_entity_history(conn: &Connection, entity_type: &str, id: &str) -> Result<Vec<ChangeLogEntry>, AppError> {
+    table_for(entity_type)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, entity_type, entity_id, operation, before_json, after_json, created_at
+             FROM change_log
+             WHERE entity_id = ?2 AND (entity_type = ?1 OR (?1 = 'task' AND entity_type IN ('taskTag', 'taskDependency')))
+             ORDER BY id",
+        )?;
+
+    let entries: Vec<ChangeLogEntry> = stmt
+        .query_map(params![entity_type, id], |row| {
+            Ok(ChangeLogEntry {
+                id: row.get(0)?,
+                entity_type: row.get(1)?,
+                entity_id: row.get(2)?,
+                operation: row.get(3)?,
+                before: snapshot_column(row, 4)?,
+                after: snapshot_column(row, 5)?,
+                created_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(entries)
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_test_task, open_test_connection};
+    use crate::services::{tag, task};
+
+    #[test]
+    fn test_task_history_records_each_write() {
+        let conn = open_test_connection();
+        let t = create_test_task(&conn, "Draft");
+        task::update_task(&conn, &t.id, UpdateTaskInput { title: Some("Final".into()), ..Default::default() }).unwrap();
+        let work = tag::create_tag(&conn, "work", None).unwrap();
+        tag::add_tag_to_task(&conn, &t.id, &work.id).unwrap();
+        task::delete_task(&conn, &t.id).unwrap();
+
+        let history = get_entity_history(&conn, TASK, &t.id).unwrap();
+        let ops: Vec<(&str, &str)> = history.iter().map(|e| (e.entity_type.as_str(), e.operation.as_str())).collect();
+        assert_eq!(ops, vec![(TASK, "create"), (TASK, "update"), (TASK_TAG, "create"), (TASK, "delete")]);
+
+        assert!(history[0].before.is_none());
+        assert_eq!(history[1].before.as_ref().unwrap()["title"], "Draft");
+        assert_eq!(history[1].after.as_ref().unwrap()["title"], "Final");
+        assert!(history[3].after.as_ref().unwrap()["deleted_at"].is_string());
+    }
+
+    #[test]
+    fn test_failed_write_leaves_no_log_entry() {
+        let conn = open_test_connection();
+        let t = create_test_task(&conn, "Keep");
+        let result: Result<(), AppError> = tracked(&conn, TASK, &t.id, "update", || {
+            conn.execute("UPDATE tasks SET title = 'Lost' WHERE id = ?1", params![t.id])?;
+            Err(AppError::validation("nope"))
+        });
+        assert!(result.is_err());
+
+        assert_eq!(task::get_task_by_id(&conn, &t.id).unwrap().unwrap().title, "Keep");
+        assert_eq!(get_entity_history(&conn, TASK, &t.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_change_log_is_append_only() {
+        let conn = open_test_connection();
+        create_test_task(&conn, "Logged");
+        assert!(conn.execute("DELETE FROM change_log", []).is_err());
+        assert!(conn.execute("UPDATE change_log SET operation = 'x'", []).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_snapshot_fails_the_history_read() {
+        let conn = open_test_connection();
+        let t = create_test_task(&conn, "Audited");
+        conn.execute(
+            "INSERT INTO change_log (entity_type, entity_id, operation, before_json, after_json, created_at)
+             VALUES ('task', ?1, 'update', '{\"title\":', NULL, '')",
+            params![t.id],
+        )
+        .unwrap();
+        assert_eq!(get_entity_history(&conn, TASK, &t.id).unwrap_err().code, ErrorCode::DbError);
+    }
+}
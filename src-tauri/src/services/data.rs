@@ -1,13 +1,32 @@
 use rusqlite::{Connection, params};
+use std::path::Path;
 use crate::models::*;
+use crate::services::{attachment, focus, template, time_entry};
+use crate::utils::validate;
 
-pub fn export_data(conn: &Connection) -> Result<String, AppError> {
+/// Export everything live as JSON. With `attachment_dir`, the export is a bundle
+/// that also carries the attachments' files.
+pub fn export_data(conn: &Connection, attachment_dir: Option<&Path>) -> Result<String, AppError> {
+    // One read transaction, so every query sees the same snapshot even while
+    // the writer keeps going
+    let _snapshot = conn.unchecked_transaction()?;
     let all_tasks = query_all_tasks_raw(conn)?;
     let all_sub_tasks = query_all_sub_tasks_raw(conn)?;
     let all_categories = query_all_categories_raw(conn)?;
     let all_tags = query_all_tags_raw(conn)?;
     let all_task_tags = query_all_task_tags(conn)?;
     let all_notes = query_all_notes_raw(conn)?;
+    let all_task_dependencies = query_all_task_dependencies(conn)?;
+    let all_projects = query_all_projects_raw(conn)?;
+    let all_headings = query_all_headings_raw(conn)?;
+    let all_time_entries = query_all_time_entries_raw(conn)?;
+    let all_focus_sessions = focus::get_focus_sessions(conn, None)?;
+    let all_templates = template::get_templates(conn)?;
+    let all_attachments = query_all_attachments_raw(conn)?;
+    let attachment_files = match attachment_dir {
+        Some(dir) => attachment::bundle_files(dir, &all_attachments)?,
+        None => Vec::new(),
+    };
 
     let data = ExportData {
         version: 1,
@@ -18,19 +37,34 @@ pub fn export_data(conn: &Connection) -> Result<String, AppError> {
         tags: all_tags,
         task_tags: all_task_tags,
         notes: all_notes,
+        task_dependencies: all_task_dependencies,
+        projects: all_projects,
+        headings: all_headings,
+        time_entries: all_time_entries,
+        focus_sessions: all_focus_sessions,
+        templates: all_templates,
+        attachments: all_attachments,
+        attachment_files,
     };
 
     serde_json::to_string_pretty(&data)
-        .map_err(|e| AppError { code: "SERIALIZE_ERROR".into(), message: e.to_string(), details: None })
+        .map_err(AppError::from)
 }
 
-pub fn import_data(conn: &Connection, json_str: &str) -> Result<(), AppError> {
-    let data: ExportData = serde_json::from_str(json_str)
-        .map_err(|_| AppError { code: "VALIDATION_ERROR".into(), message: "Invalid JSON format".into(), details: None })?;
+/// Import an export or a bundle. Attachment files go into the store at
+/// `attachment_dir`.
+pub fn import_data(conn: &Connection, json_str: &str, attachment_dir: &Path) -> Result<(), AppError> {
+    let invalid_json = |_| AppError::validation("Invalid JSON format");
+    let value: serde_json::Value = serde_json::from_str(json_str).map_err(invalid_json)?;
+    validate_enum_fields(&value)?;
+    let data: ExportData = serde_json::from_value(value).map_err(invalid_json)?;
 
     if data.version != 1 {
-        return Err(AppError { code: "VALIDATION_ERROR".into(), message: "Unsupported export version".into(), details: None });
+        return Err(AppError::validation("Unsupported export version"));
     }
+    validate_fields(&data)?;
+    attachment::validate_rows(&data.attachments)?;
+    let attachment_files = attachment::decode_bundle_files(&data.attachment_files)?;
 
     // Import categories first
     for cat in &data.categories {
@@ -48,21 +82,39 @@ pub fn import_data(conn: &Connection, json_str: &str) -> Result<(), AppError> {
         );
     }
 
+    // Import projects and their headings, before the tasks placed in them
+    for project in &data.projects {
+        let _ = conn.execute(
+            "INSERT OR IGNORE INTO projects (id, category_id, title, notes, deadline, status, completed_at, sort_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                project.id, project.category_id, project.title, project.notes, project.deadline,
+                project.status, project.completed_at, project.sort_order, project.created_at, project.updated_at,
+            ],
+        );
+    }
+    for heading in &data.headings {
+        let _ = conn.execute(
+            "INSERT OR IGNORE INTO headings (id, project_id, title, sort_order, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![heading.id, heading.project_id, heading.title, heading.sort_order, heading.created_at],
+        );
+    }
+
     // Import tasks
     for task in &data.tasks {
         let _ = conn.execute(
-            "INSERT OR IGNORE INTO tasks (id, title, description, status, priority, category_id, due_date, reminder_time, recurrence_rule, completed_at, sort_order, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            "INSERT OR IGNORE INTO tasks (id, title, description, status, priority, category_id, project_id, heading_id, start_date, due_date, reminder_time, recurrence_rule, someday, completed_at, archived_at, sort_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
             params![
                 task.id, task.title, task.description, task.status, task.priority,
-                task.category_id, task.due_date, task.reminder_time, task.recurrence_rule,
-                task.completed_at, task.sort_order, task.created_at, task.updated_at,
+                task.category_id, task.project_id, task.heading_id, task.start_date, task.due_date, task.reminder_time, task.recurrence_rule,
+                task.someday as i64, task.completed_at, task.archived_at, task.sort_order, task.created_at, task.updated_at,
             ],
         );
     }
 
-    // Import sub-tasks
-    for st in &data.sub_tasks {
+    // Import sub-tasks, parents before children so their parent_id reference holds
+    for st in sub_tasks_parents_first(&data.sub_tasks) {
         let _ = conn.execute(
             "INSERT OR IGNORE INTO sub_tasks (id, task_id, parent_id, title, description, priority, due_date, completed, sort_order, created_at)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
@@ -81,6 +133,52 @@ pub fn import_data(conn: &Connection, json_str: &str) -> Result<(), AppError> {
         );
     }
 
+    // Import task dependencies
+    for dep in &data.task_dependencies {
+        let _ = conn.execute(
+            "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id, created_at) VALUES (?1, ?2, ?3)",
+            params![dep.task_id, dep.depends_on_id, dep.created_at],
+        );
+    }
+
+    // Import time entries
+    for entry in &data.time_entries {
+        let _ = conn.execute(
+            "INSERT OR IGNORE INTO time_entries (id, task_id, started_at, ended_at, note, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                entry.id, entry.task_id, entry.started_at, entry.ended_at,
+                entry.note, entry.created_at, entry.updated_at,
+            ],
+        );
+    }
+
+    // Import focus history
+    for session in &data.focus_sessions {
+        let _ = conn.execute(
+            "INSERT OR IGNORE INTO focus_sessions (id, task_id, started_at, ended_at, duration_seconds, completed, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                session.id, session.task_id, session.started_at, session.ended_at,
+                session.duration_seconds, session.completed as i64, session.created_at,
+            ],
+        );
+    }
+
+    // Import templates
+    for t in &data.templates {
+        let _ = conn.execute(
+            "INSERT OR IGNORE INTO task_templates (id, name, title, description, priority, category_id, start_offset_days, due_offset_days, tag_ids, sub_tasks, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                t.id, t.name, t.title, t.description, t.priority, t.category_id,
+                t.start_offset_days, t.due_offset_days,
+                serde_json::to_string(&t.tag_ids)?, serde_json::to_string(&t.sub_tasks)?,
+                t.created_at, t.updated_at,
+            ],
+        );
+    }
+
     // Import notes
     for note in &data.notes {
         let _ = conn.execute(
@@ -93,16 +191,123 @@ pub fn import_data(conn: &Connection, json_str: &str) -> Result<(), AppError> {
         );
     }
 
+    // Import attachments, after the tasks and notes they belong to
+    attachment::import_attachments(conn, attachment_dir, &data.attachments, &attachment_files)?;
+
     Ok(())
 }
 
+/// Check status and priority values up front so a bad file is rejected with the
+/// offending field (e.g. `tasks[3].priority`) rather than a generic parse error.
+fn validate_enum_fields(value: &serde_json::Value) -> Result<(), AppError> {
+    let rows = |key: &str| value.get(key).and_then(|v| v.as_array()).map_or(&[][..], |a| a.as_slice());
+
+    for (i, task) in rows("tasks").iter().enumerate() {
+        if let Some(status) = task.get("status").and_then(|v| v.as_str()) {
+            TaskStatus::parse(&format!("tasks[{}].status", i), status)?;
+        }
+        if let Some(priority) = task.get("priority").and_then(|v| v.as_str()) {
+            Priority::parse(&format!("tasks[{}].priority", i), priority)?;
+        }
+    }
+    for (i, st) in rows("subTasks").iter().enumerate() {
+        if let Some(priority) = st.get("priority").and_then(|v| v.as_str()) {
+            Priority::parse(&format!("subTasks[{}].priority", i), priority)?;
+        }
+    }
+    for (i, project) in rows("projects").iter().enumerate() {
+        if let Some(status) = project.get("status").and_then(|v| v.as_str()) {
+            ProjectStatus::parse(&format!("projects[{}].status", i), status)?;
+        }
+    }
+    for (i, template) in rows("templates").iter().enumerate() {
+        if let Some(priority) = template.get("priority").and_then(|v| v.as_str()) {
+            Priority::parse(&format!("templates[{}].priority", i), priority)?;
+        }
+    }
+    Ok(())
+}
+
+/// Run the same date, color and recurrence checks as the create/update paths,
+/// naming fields by their position in the file.
+fn validate_fields(data: &ExportData) -> Result<(), AppError> {
+    for (i, cat) in data.categories.iter().enumerate() {
+        if let Some(ref color) = cat.color {
+            validate::color(&format!("categories[{}].color", i), color)?;
+        }
+    }
+    for (i, tag) in data.tags.iter().enumerate() {
+        if let Some(ref color) = tag.color {
+            validate::color(&format!("tags[{}].color", i), color)?;
+        }
+    }
+    for (i, project) in data.projects.iter().enumerate() {
+        if let Some(ref deadline) = project.deadline {
+            validate::date(&format!("projects[{}].deadline", i), deadline)?;
+        }
+    }
+    for (i, task) in data.tasks.iter().enumerate() {
+        if let Some(ref start) = task.start_date {
+            validate::date(&format!("tasks[{}].startDate", i), start)?;
+        }
+        if let Some(ref due) = task.due_date {
+            validate::date(&format!("tasks[{}].dueDate", i), due)?;
+        }
+        if let Some(ref reminder) = task.reminder_time {
+            validate::date_time(&format!("tasks[{}].reminderTime", i), reminder)?;
+        }
+        if let Some(ref raw) = task.recurrence_rule {
+            let field = format!("tasks[{}].recurrenceRule", i);
+            let rule: RecurrenceRule = serde_json::from_str(raw).map_err(|_| {
+                AppError::validation(format!("{} is not a valid recurrence rule", field))
+                    .with_detail("field", field.as_str())
+                    .with_detail("value", raw.as_str())
+            })?;
+            validate::recurrence_rule(&field, &rule)?;
+        }
+    }
+    for (i, st) in data.sub_tasks.iter().enumerate() {
+        if let Some(ref due) = st.due_date {
+            validate::date(&format!("subTasks[{}].dueDate", i), due)?;
+        }
+    }
+    for (i, entry) in data.time_entries.iter().enumerate() {
+        validate::date_time(&format!("timeEntries[{}].startedAt", i), &entry.started_at)?;
+        if let Some(ref ended) = entry.ended_at {
+            validate::date_time(&format!("timeEntries[{}].endedAt", i), ended)?;
+        }
+    }
+    Ok(())
+}
+
+/// Order sub-tasks so every parent comes before its children.
+fn sub_tasks_parents_first(sub_tasks: &[ExportSubTaskRow]) -> Vec<&ExportSubTaskRow> {
+    let parents: std::collections::HashMap<&str, Option<&str>> = sub_tasks
+        .iter()
+        .map(|st| (st.id.as_str(), st.parent_id.as_deref()))
+        .collect();
+    let depth = |id: &str| {
+        let mut depth = 0;
+        let mut current = parents.get(id).copied().flatten();
+        // Bounded so a cyclic file can't loop forever
+        while let Some(parent) = current.filter(|_| depth < sub_tasks.len()) {
+            depth += 1;
+            current = parents.get(parent).copied().flatten();
+        }
+        depth
+    };
+
+    let mut ordered: Vec<&ExportSubTaskRow> = sub_tasks.iter().collect();
+    ordered.sort_by_cached_key(|st| depth(&st.id));
+    ordered
+}
+
 // ============================================================
-// Raw query helpers for export
+// Raw query helpers for export (trashed rows are left out)
 // ============================================================
 
 fn query_all_tasks_raw(conn: &Connection) -> Result<Vec<ExportTaskRow>, AppError> {
-    let mut stmt = conn.prepare("SELECT * FROM tasks")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn.prepare("SELECT * FROM tasks WHERE deleted_at IS NULL")?;
     let rows: Vec<ExportTaskRow> = stmt
         .query_map([], |row| {
             Ok(ExportTaskRow {
@@ -112,24 +317,30 @@ fn query_all_tasks_raw(conn: &Connection) -> Result<Vec<ExportTaskRow>, AppError
                 status: row.get("status")?,
                 priority: row.get("priority")?,
                 category_id: row.get("category_id")?,
+                project_id: row.get("project_id")?,
+                heading_id: row.get("heading_id")?,
+                start_date: row.get("start_date")?,
                 due_date: row.get("due_date")?,
                 reminder_time: row.get("reminder_time")?,
                 recurrence_rule: row.get("recurrence_rule")?,
+                someday: row.get::<_, i64>("someday")? != 0,
                 completed_at: row.get("completed_at")?,
+                archived_at: row.get("archived_at")?,
                 sort_order: row.get("sort_order")?,
                 created_at: row.get("created_at")?,
                 updated_at: row.get("updated_at")?,
             })
-        })
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        })?
+        .collect::<Result<_, _>>()?;
     Ok(rows)
 }
 
 fn query_all_sub_tasks_raw(conn: &Connection) -> Result<Vec<ExportSubTaskRow>, AppError> {
-    let mut stmt = conn.prepare("SELECT * FROM sub_tasks")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT * FROM sub_tasks
+             WHERE deleted_at IS NULL AND task_id IN (SELECT id FROM tasks WHERE deleted_at IS NULL)",
+        )?;
     let rows: Vec<ExportSubTaskRow> = stmt
         .query_map([], |row| {
             let completed_int: i64 = row.get("completed")?;
@@ -139,22 +350,19 @@ fn query_all_sub_tasks_raw(conn: &Connection) -> Result<Vec<ExportSubTaskRow>, A
                 parent_id: row.get("parent_id")?,
                 title: row.get("title")?,
                 description: row.get("description")?,
-                priority: row.get::<_, Option<String>>("priority")?.unwrap_or_else(|| "none".to_string()),
+                priority: row.get::<_, Option<Priority>>("priority")?.unwrap_or_default(),
                 due_date: row.get("due_date")?,
                 completed: completed_int != 0,
                 sort_order: row.get("sort_order")?,
                 created_at: row.get("created_at")?,
             })
-        })
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        })?
+        .collect::<Result<_, _>>()?;
     Ok(rows)
 }
 
 fn query_all_categories_raw(conn: &Connection) -> Result<Vec<Category>, AppError> {
-    let mut stmt = conn.prepare("SELECT * FROM categories")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn.prepare("SELECT * FROM categories WHERE deleted_at IS NULL")?;
     let rows: Vec<Category> = stmt
         .query_map([], |row| {
             Ok(Category {
@@ -164,16 +372,75 @@ fn query_all_categories_raw(conn: &Connection) -> Result<Vec<Category>, AppError
                 sort_order: row.get("sort_order")?,
                 created_at: row.get("created_at")?,
             })
-        })
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
+fn query_all_projects_raw(conn: &Connection) -> Result<Vec<ExportProjectRow>, AppError> {
+    let mut stmt = conn.prepare("SELECT * FROM projects WHERE deleted_at IS NULL")?;
+    let rows: Vec<ExportProjectRow> = stmt
+        .query_map([], |row| {
+            Ok(ExportProjectRow {
+                id: row.get("id")?,
+                category_id: row.get("category_id")?,
+                title: row.get("title")?,
+                notes: row.get("notes")?,
+                deadline: row.get("deadline")?,
+                status: row.get("status")?,
+                completed_at: row.get("completed_at")?,
+                sort_order: row.get("sort_order")?,
+                created_at: row.get("created_at")?,
+                updated_at: row.get("updated_at")?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
+fn query_all_headings_raw(conn: &Connection) -> Result<Vec<Heading>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT h.* FROM headings h INNER JOIN projects p ON p.id = h.project_id WHERE p.deleted_at IS NULL",
+    )?;
+    let rows: Vec<Heading> = stmt
+        .query_map([], |row| {
+            Ok(Heading {
+                id: row.get("id")?,
+                project_id: row.get("project_id")?,
+                title: row.get("title")?,
+                sort_order: row.get("sort_order")?,
+                created_at: row.get("created_at")?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
+fn query_all_time_entries_raw(conn: &Connection) -> Result<Vec<TimeEntry>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT e.* FROM time_entries e INNER JOIN tasks t ON t.id = e.task_id WHERE t.deleted_at IS NULL",
+    )?;
+    let rows: Vec<TimeEntry> = stmt
+        .query_map([], time_entry::row_to_entry)?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
+fn query_all_attachments_raw(conn: &Connection) -> Result<Vec<Attachment>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT a.* FROM attachments a
+         LEFT JOIN tasks t ON t.id = a.task_id
+         LEFT JOIN notes n ON n.id = a.note_id
+         WHERE t.deleted_at IS NULL AND n.deleted_at IS NULL",
+    )?;
+    let rows: Vec<Attachment> = stmt
+        .query_map([], attachment::row_to_attachment)?
+        .collect::<Result<_, _>>()?;
     Ok(rows)
 }
 
 fn query_all_tags_raw(conn: &Connection) -> Result<Vec<Tag>, AppError> {
-    let mut stmt = conn.prepare("SELECT * FROM tags")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn.prepare("SELECT * FROM tags WHERE deleted_at IS NULL")?;
     let rows: Vec<Tag> = stmt
         .query_map([], |row| {
             Ok(Tag {
@@ -182,16 +449,13 @@ fn query_all_tags_raw(conn: &Connection) -> Result<Vec<Tag>, AppError> {
                 color: row.get("color")?,
                 created_at: row.get("created_at")?,
             })
-        })
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        })?
+        .collect::<Result<_, _>>()?;
     Ok(rows)
 }
 
 fn query_all_notes_raw(conn: &Connection) -> Result<Vec<Note>, AppError> {
-    let mut stmt = conn.prepare("SELECT * FROM notes")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn.prepare("SELECT * FROM notes WHERE deleted_at IS NULL")?;
     let rows: Vec<Note> = stmt
         .query_map([], |row| {
             let is_pinned_int: i64 = row.get("is_pinned")?;
@@ -204,25 +468,80 @@ fn query_all_notes_raw(conn: &Connection) -> Result<Vec<Note>, AppError> {
                 created_at: row.get("created_at")?,
                 updated_at: row.get("updated_at")?,
             })
-        })
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        })?
+        .collect::<Result<_, _>>()?;
     Ok(rows)
 }
 
 fn query_all_task_tags(conn: &Connection) -> Result<Vec<TaskTagRow>, AppError> {
-    let mut stmt = conn.prepare("SELECT task_id, tag_id FROM task_tags")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT tt.task_id, tt.tag_id FROM task_tags tt
+             INNER JOIN tasks t ON t.id = tt.task_id
+             INNER JOIN tags g ON g.id = tt.tag_id
+             WHERE t.deleted_at IS NULL AND g.deleted_at IS NULL",
+        )?;
     let rows: Vec<TaskTagRow> = stmt
         .query_map([], |row| {
             Ok(TaskTagRow {
                 task_id: row.get(0)?,
                 tag_id: row.get(1)?,
             })
-        })
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        })?
+        .collect::<Result<_, _>>()?;
     Ok(rows)
 }
+
+fn query_all_task_dependencies(conn: &Connection) -> Result<Vec<TaskDependencyRow>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT d.task_id, d.depends_on_id, d.created_at FROM task_dependencies d
+             INNER JOIN tasks a ON a.id = d.task_id
+             INNER JOIN tasks b ON b.id = d.depends_on_id
+             WHERE a.deleted_at IS NULL AND b.deleted_at IS NULL",
+        )?;
+    let rows: Vec<TaskDependencyRow> = stmt
+        .query_map([], |row| {
+            Ok(TaskDependencyRow {
+                task_id: row.get(0)?,
+                depends_on_id: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+
+    #[test]
+    fn test_import_rejects_invalid_fields_by_position() {
+        let conn = open_test_connection();
+        let attachments = std::env::temp_dir().join("watermelon-import-test");
+        let file = |task: &str| format!(
+            r#"{{"version":1,"exportedAt":"","tasks":[{}],"subTasks":[],"categories":[],"tags":[],"taskTags":[]}}"#,
+            task,
+        );
+        let task = |extra: &str| format!(
+            r#"{{"id":"t1","title":"T","status":"todo","priority":"none","sortOrder":0,"createdAt":"","updatedAt":""{}}}"#,
+            extra,
+        );
+
+        let err = import_data(&conn, &file(&task(r#","dueDate":"2024-13-01""#)), &attachments).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ValidationError);
+        assert_eq!(err.details.unwrap()["field"], "tasks[0].dueDate");
+
+        let rule = r#","recurrenceRule":"{\"type\":\"daily\",\"interval\":0}""#;
+        let err = import_data(&conn, &file(&task(rule)), &attachments).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "tasks[0].recurrenceRule.interval");
+
+        assert!(import_data(&conn, &file(&task(r#","dueDate":"2024-12-01""#)), &attachments).is_ok());
+    }
+}
@@ -0,0 +1,196 @@
+use rusqlite::{Connection, params, OptionalExtension};
+use std::collections::HashMap;
+use crate::models::*;
+use crate::services::change_log::{self, TASK_DEPENDENCY};
+
+// Dependencies between trashed tasks are kept so a restore brings them back, but
+// they're left out of `blocked_by` / `blocking` and never block anything.
+
+/// Make `task_id` wait on `depends_on_id`. Rejects links that would close a cycle.
+pub fn add_dependency(conn: &Connection, task_id: &str, depends_on_id: &str) -> Result<(), AppError> {
+    for id in [task_id, depends_on_id] {
+        let exists: bool = conn
+            .query_row("SELECT COUNT(*) > 0 FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![id], |row| row.get(0))?;
+        if !exists {
+            return Err(AppError::not_found("Task not found").with_detail("id", id));
+        }
+    }
+
+    if task_id == depends_on_id || depends_on(conn, depends_on_id, task_id)? {
+        return Err(AppError::new(ErrorCode::Conflict, "A task can't depend on a task that waits on it")
+            .with_detail("taskId", task_id)
+            .with_detail("dependsOnId", depends_on_id));
+    }
+
+    let exists: Option<String> = conn
+        .query_row(
+            "SELECT task_id FROM task_dependencies WHERE task_id = ?1 AND depends_on_id = ?2",
+            params![task_id, depends_on_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if exists.is_some() {
+        return Ok(()); // idempotent
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    change_log::in_transaction(conn, || {
+        conn.execute(
+            "INSERT INTO task_dependencies (task_id, depends_on_id, created_at) VALUES (?1, ?2, ?3)",
+            params![task_id, depends_on_id, now],
+        )?;
+        let row = dependency_json(task_id, depends_on_id, &now);
+        change_log::record(conn, TASK_DEPENDENCY, task_id, "create", None, Some(&row))
+    })
+}
+
+pub fn remove_dependency(conn: &Connection, task_id: &str, depends_on_id: &str) -> Result<(), AppError> {
+    change_log::in_transaction(conn, || {
+        let created_at: Option<String> = conn
+            .query_row(
+                "DELETE FROM task_dependencies WHERE task_id = ?1 AND depends_on_id = ?2 RETURNING created_at",
+                params![task_id, depends_on_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(created_at) = created_at {
+            let row = dependency_json(task_id, depends_on_id, &created_at);
+            change_log::record(conn, TASK_DEPENDENCY, task_id, "delete", Some(&row), None)?;
+        }
+        Ok(())
+    })
+}
+
+/// A `task_dependencies` row in the shape `change_log` stores rows.
+fn dependency_json(task_id: &str, depends_on_id: &str, created_at: &str) -> serde_json::Value {
+    serde_json::json!({ "task_id": task_id, "depends_on_id": depends_on_id, "created_at": created_at })
+}
+
+/// Whether `task_id` waits on `other_id`, directly or through other tasks.
+fn depends_on(conn: &Connection, task_id: &str, other_id: &str) -> Result<bool, AppError> {
+    // UNION (not UNION ALL) drops rows already seen, so a cycle can't loop forever
+    let found: bool = conn.query_row(
+        "WITH RECURSIVE upstream(id) AS (
+            SELECT depends_on_id FROM task_dependencies WHERE task_id = ?1
+            UNION
+            SELECT d.depends_on_id FROM task_dependencies d INNER JOIN upstream u ON d.task_id = u.id
+        )
+        SELECT COUNT(*) > 0 FROM upstream WHERE id = ?2",
+        params![task_id, other_id],
+        |row| row.get(0),
+    )?;
+    Ok(found)
+}
+
+/// Fill in `blocked_by`, `blocking` and `is_blocked` for the given tasks.
+pub fn attach_dependencies(conn: &Connection, tasks: &mut [Task]) -> Result<(), AppError> {
+    if tasks.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    let placeholders = (1..=ids.len()).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(",");
+    let sql = format!(
+        "SELECT d.task_id, d.depends_on_id, b.status
+         FROM task_dependencies d
+         INNER JOIN tasks a ON a.id = d.task_id AND a.deleted_at IS NULL
+         INNER JOIN tasks b ON b.id = d.depends_on_id AND b.deleted_at IS NULL
+         WHERE d.task_id IN ({0}) OR d.depends_on_id IN ({0})
+         ORDER BY d.created_at",
+        placeholders
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let links: Vec<(String, String, TaskStatus)> = stmt
+        .query_map(rusqlite::params_from_iter(&ids), |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let mut blocked_by: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut blocking: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut blocked: HashMap<&str, bool> = HashMap::new();
+    for (task_id, depends_on_id, status) in &links {
+        blocked_by.entry(task_id.as_str()).or_default().push(depends_on_id.clone());
+        blocking.entry(depends_on_id.as_str()).or_default().push(task_id.clone());
+        *blocked.entry(task_id.as_str()).or_default() |= *status == TaskStatus::Todo;
+    }
+
+    for task in tasks.iter_mut() {
+        let id = task.id.as_str();
+        task.is_blocked = Some(blocked.get(id).copied().unwrap_or(false));
+        task.blocked_by = Some(blocked_by.remove(id).unwrap_or_default());
+        task.blocking = Some(blocking.remove(id).unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// Open tasks waiting on `id` that have nothing else open left to wait on.
+pub fn unblocked_by(conn: &Connection, id: &str) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT d.task_id FROM task_dependencies d
+         INNER JOIN tasks t ON t.id = d.task_id
+         WHERE d.depends_on_id = ?1 AND t.deleted_at IS NULL AND t.status = 'todo'
+           AND NOT EXISTS (
+               SELECT 1 FROM task_dependencies o INNER JOIN tasks b ON b.id = o.depends_on_id
+               WHERE o.task_id = d.task_id AND b.deleted_at IS NULL AND b.status = 'todo'
+           )
+         ORDER BY t.sort_order",
+    )?;
+    let ids: Vec<String> = stmt
+        .query_map(params![id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(ids)
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_test_task, open_test_connection};
+    use crate::services::task;
+
+    #[test]
+    fn test_cycles_are_rejected() {
+        let conn = open_test_connection();
+        let (a, b, c) = (create_test_task(&conn, "A"), create_test_task(&conn, "B"), create_test_task(&conn, "C"));
+        add_dependency(&conn, &b.id, &a.id).unwrap();
+        add_dependency(&conn, &c.id, &b.id).unwrap();
+        add_dependency(&conn, &c.id, &b.id).unwrap();
+
+        assert_eq!(add_dependency(&conn, &a.id, &c.id).unwrap_err().code, ErrorCode::Conflict);
+        assert_eq!(add_dependency(&conn, &a.id, &a.id).unwrap_err().code, ErrorCode::Conflict);
+        assert_eq!(add_dependency(&conn, &a.id, "missing").unwrap_err().code, ErrorCode::NotFound);
+
+        remove_dependency(&conn, &c.id, &b.id).unwrap();
+        add_dependency(&conn, &a.id, &c.id).unwrap();
+    }
+
+    #[test]
+    fn test_blocked_flags_and_unblocking_on_complete() {
+        let conn = open_test_connection();
+        let (a, b, c) = (create_test_task(&conn, "A"), create_test_task(&conn, "B"), create_test_task(&conn, "C"));
+        add_dependency(&conn, &c.id, &a.id).unwrap();
+        add_dependency(&conn, &c.id, &b.id).unwrap();
+
+        let loaded = task::get_task_by_id(&conn, &c.id).unwrap().unwrap();
+        assert_eq!(loaded.is_blocked, Some(true));
+        assert_eq!(loaded.blocked_by, Some(vec![a.id.clone(), b.id.clone()]));
+        let all = task::get_all_tasks(&conn, None).unwrap();
+        let a_loaded = all.iter().find(|t| t.id == a.id).unwrap();
+        assert_eq!(a_loaded.blocking, Some(vec![c.id.clone()]));
+        assert_eq!(a_loaded.is_blocked, Some(false));
+
+        // C still waits on B
+        assert!(task::complete_task(&conn, &a.id).unwrap().unblocked_tasks.is_empty());
+        let result = task::complete_task(&conn, &b.id).unwrap();
+        assert_eq!(result.unblocked_tasks.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec![c.id.as_str()]);
+        assert_eq!(result.unblocked_tasks[0].is_blocked, Some(false));
+
+        // A trashed blocker stops blocking
+        task::uncomplete_task(&conn, &b.id).unwrap();
+        task::delete_task(&conn, &b.id).unwrap();
+        let loaded = task::get_task_by_id(&conn, &c.id).unwrap().unwrap();
+        assert_eq!(loaded.is_blocked, Some(false));
+        assert_eq!(loaded.blocked_by, Some(vec![a.id.clone()]));
+    }
+}
@@ -0,0 +1,131 @@
+use rusqlite::{Connection, params};
+use uuid::Uuid;
+use crate::models::*;
+
+// Focus history written by the Pomodoro engine (see `pomodoro.rs`). Sessions are
+// a log rather than user data, so they aren't tracked in `change_log`.
+
+fn row_to_session(row: &rusqlite::Row) -> rusqlite::Result<FocusSession> {
+    Ok(FocusSession {
+        id: row.get("id")?,
+        task_id: row.get("task_id")?,
+        started_at: row.get("started_at")?,
+        ended_at: row.get("ended_at")?,
+        duration_seconds: row.get("duration_seconds")?,
+        completed: row.get::<_, i64>("completed")? != 0,
+        created_at: row.get("created_at")?,
+    })
+}
+
+/// Record a work session that ended at `ended_at`. A task that no longer exists
+/// (e.g. after switching workspaces mid-session) is dropped from the record.
+pub fn record_session(
+    conn: &Connection,
+    task_id: Option<&str>,
+    started_at: &str,
+    ended_at: &str,
+    completed: bool,
+) -> Result<FocusSession, AppError> {
+    let task_id = match task_id {
+        Some(id) => {
+            let exists: bool = conn
+                .query_row("SELECT COUNT(*) > 0 FROM tasks WHERE id = ?1", params![id], |row| row.get(0))?;
+            exists.then_some(id)
+        }
+        None => None,
+    };
+    let duration_seconds = match (
+        chrono::DateTime::parse_from_rfc3339(started_at),
+        chrono::DateTime::parse_from_rfc3339(ended_at),
+    ) {
+        (Ok(start), Ok(end)) => (end - start).num_seconds().max(0),
+        _ => return Err(AppError::validation("Focus session times must be RFC 3339")),
+    };
+
+    let session = FocusSession {
+        id: Uuid::new_v4().to_string(),
+        task_id: task_id.map(String::from),
+        started_at: started_at.to_string(),
+        ended_at: ended_at.to_string(),
+        duration_seconds,
+        completed,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO focus_sessions (id, task_id, started_at, ended_at, duration_seconds, completed, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            session.id, session.task_id, session.started_at, session.ended_at,
+            session.duration_seconds, session.completed as i64, session.created_at,
+        ],
+    )?;
+    Ok(session)
+}
+
+/// Sessions for one task, or all of them, most recent first.
+pub fn get_focus_sessions(conn: &Connection, task_id: Option<&str>) -> Result<Vec<FocusSession>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM focus_sessions WHERE ?1 IS NULL OR task_id = ?1 ORDER BY started_at DESC",
+    )?;
+    let sessions: Vec<FocusSession> = stmt
+        .query_map(params![task_id], row_to_session)?
+        .collect::<Result<_, _>>()?;
+    Ok(sessions)
+}
+
+// ============================================================
+// Cycle
+// ============================================================
+
+/// Length of a phase in minutes, as configured.
+pub fn phase_minutes(phase: PomodoroPhase, settings: &AppSettings) -> i64 {
+    match phase {
+        PomodoroPhase::Work => settings.pomodoro_work_minutes,
+        PomodoroPhase::ShortBreak => settings.pomodoro_short_break_minutes,
+        PomodoroPhase::LongBreak => settings.pomodoro_long_break_minutes,
+    }
+}
+
+/// The break after a work session, given the work sessions finished since the
+/// last long break, that one included.
+pub fn break_after(completed_in_cycle: i64, settings: &AppSettings) -> PomodoroPhase {
+    if completed_in_cycle >= settings.pomodoro_long_break_every {
+        PomodoroPhase::LongBreak
+    } else {
+        PomodoroPhase::ShortBreak
+    }
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_test_task, open_test_connection};
+
+    #[test]
+    fn test_sessions_are_recorded_against_tasks() {
+        let conn = open_test_connection();
+        let t = create_test_task(&conn, "Write");
+
+        let s = record_session(&conn, Some(&t.id), "2024-05-01T09:00:00Z", "2024-05-01T09:25:00Z", true).unwrap();
+        assert_eq!(s.duration_seconds, 25 * 60);
+        let orphan = record_session(&conn, Some("gone"), "2024-05-01T10:00:00Z", "2024-05-01T10:05:00Z", false).unwrap();
+        assert_eq!(orphan.task_id, None);
+
+        assert_eq!(get_focus_sessions(&conn, Some(&t.id)).unwrap().len(), 1);
+        let all = get_focus_sessions(&conn, None).unwrap();
+        assert_eq!(all.iter().map(|s| s.completed).collect::<Vec<_>>(), vec![false, true]);
+    }
+
+    #[test]
+    fn test_every_fourth_break_is_long() {
+        let settings = AppSettings::default();
+        let breaks: Vec<PomodoroPhase> = (1..=4).map(|n| break_after(n, &settings)).collect();
+        assert_eq!(breaks[..3], [PomodoroPhase::ShortBreak; 3]);
+        assert_eq!(breaks[3], PomodoroPhase::LongBreak);
+        assert_eq!(phase_minutes(breaks[3], &settings), 15);
+    }
+}
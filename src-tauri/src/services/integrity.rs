@@ -0,0 +1,273 @@
+use rusqlite::{Connection, params};
+use crate::models::*;
+use crate::utils::validate;
+
+/// Date-bearing columns checked by `check_database`. All of them are nullable, so a
+/// value that doesn't parse can be repaired by clearing it.
+const DATE_COLUMNS: &[(&str, &str, DateFormat)] = &[
+    ("tasks", "start_date", DateFormat::Date),
+    ("tasks", "due_date", DateFormat::Date),
+    ("tasks", "reminder_time", DateFormat::DateTime),
+    ("tasks", "completed_at", DateFormat::DateTime),
+    ("sub_tasks", "due_date", DateFormat::Date),
+    ("projects", "deadline", DateFormat::Date),
+];
+
+#[derive(Clone, Copy)]
+enum DateFormat {
+    /// `%Y-%m-%d`, as expected by `complete_task`
+    Date,
+    /// RFC 3339, as expected by the notification scheduler
+    DateTime,
+}
+
+impl DateFormat {
+    fn is_valid(self, value: &str) -> bool {
+        match self {
+            DateFormat::Date => validate::is_date(value),
+            DateFormat::DateTime => validate::is_date_time(value),
+        }
+    }
+}
+
+// ============================================================
+// Check
+// ============================================================
+
+pub fn check_database(conn: &Connection) -> Result<DatabaseReport, AppError> {
+    let integrity_errors = integrity_errors(conn)?;
+    let foreign_key_violations = foreign_key_violations(conn)?;
+    let orphaned_sub_tasks = orphaned_sub_tasks(conn)?;
+    let invalid_recurrence_rules = invalid_recurrence_rules(conn)?;
+    let invalid_dates = invalid_dates(conn)?;
+
+    let ok = integrity_errors.is_empty()
+        && foreign_key_violations.is_empty()
+        && orphaned_sub_tasks.is_empty()
+        && invalid_recurrence_rules.is_empty()
+        && invalid_dates.is_empty();
+
+    Ok(DatabaseReport {
+        ok,
+        integrity_errors,
+        foreign_key_violations,
+        orphaned_sub_tasks,
+        invalid_recurrence_rules,
+        invalid_dates,
+    })
+}
+
+fn integrity_errors(conn: &Connection) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let lines: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(lines.into_iter().filter(|l| l != "ok").collect())
+}
+
+fn foreign_key_violations(conn: &Connection) -> Result<Vec<ForeignKeyViolation>, AppError> {
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let rows: Vec<ForeignKeyViolation> = stmt
+        .query_map([], |row| {
+            Ok(ForeignKeyViolation {
+                table: row.get(0)?,
+                row_id: row.get::<_, Option<i64>>(1)?.unwrap_or(-1),
+                parent: row.get(2)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
+/// Sub-tasks whose `parent_id` points at a missing sub-task or one under a different task.
+fn orphaned_sub_tasks(conn: &Connection) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id FROM sub_tasks s
+             LEFT JOIN sub_tasks p ON p.id = s.parent_id
+             WHERE s.parent_id IS NOT NULL AND (p.id IS NULL OR p.task_id != s.task_id)",
+        )?;
+    let ids: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(ids)
+}
+
+fn invalid_recurrence_rules(conn: &Connection) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare("SELECT id, recurrence_rule FROM tasks WHERE recurrence_rule IS NOT NULL")?;
+    let rows: Vec<(String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    Ok(rows
+        .into_iter()
+        .filter(|(_, json)| serde_json::from_str::<RecurrenceRule>(json).is_err())
+        .map(|(id, _)| id)
+        .collect())
+}
+
+fn invalid_dates(conn: &Connection) -> Result<Vec<InvalidDateField>, AppError> {
+    let mut invalid = Vec::new();
+    for &(table, field, format) in DATE_COLUMNS {
+        let sql = format!("SELECT id, {field} FROM {table} WHERE {field} IS NOT NULL");
+        let mut stmt = conn.prepare(&sql)?;
+        let rows: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        for (id, value) in rows {
+            if !format.is_valid(&value) {
+                invalid.push(InvalidDateField {
+                    table: table.to_string(),
+                    id,
+                    field: field.to_string(),
+                    value,
+                });
+            }
+        }
+    }
+    Ok(invalid)
+}
+
+// ============================================================
+// Repair
+// ============================================================
+
+/// Fix everything `check_database` reports that can be fixed without guessing:
+/// dangling link rows are removed, dangling references and unparsable values are
+/// cleared, and orphaned sub-tasks become top-level sub-tasks of their task.
+/// `PRAGMA integrity_check` failures are left alone — restore a backup for those.
+pub fn repair_database(conn: &Connection) -> Result<RepairReport, AppError> {
+    let report = check_database(conn)?;
+    let tx = conn.unchecked_transaction()?;
+
+    let mut removed_rows = 0;
+    let mut cleared_references = 0;
+    for v in &report.foreign_key_violations {
+        match v.table.as_str() {
+            "tasks" | "projects" | "task_templates" => {
+                let column = match v.parent.as_str() {
+                    "projects" => "project_id",
+                    "headings" => "heading_id",
+                    _ => "category_id",
+                };
+                let sql = format!("UPDATE {} SET {} = NULL WHERE rowid = ?1", v.table, column);
+                cleared_references += tx.execute(&sql, params![v.row_id])? as i64;
+            }
+            "focus_sessions" => {
+                cleared_references += tx
+                    .execute("UPDATE focus_sessions SET task_id = NULL WHERE rowid = ?1", params![v.row_id])? as i64;
+            }
+            // Dangling parents are reattached with the orphans below
+            "sub_tasks" if v.parent == "sub_tasks" => {}
+            "sub_tasks" | "task_tags" | "task_dependencies" | "headings" | "time_entries" | "attachments" => {
+                removed_rows += tx
+                    .execute(&format!("DELETE FROM {} WHERE rowid = ?1", v.table), params![v.row_id])? as i64;
+            }
+            _ => {}
+        }
+    }
+
+    let mut reattached_sub_tasks = 0;
+    for id in &report.orphaned_sub_tasks {
+        reattached_sub_tasks += tx
+            .execute("UPDATE sub_tasks SET parent_id = NULL WHERE id = ?1", params![id])? as i64;
+    }
+
+    let mut cleared_recurrence_rules = 0;
+    for id in &report.invalid_recurrence_rules {
+        cleared_recurrence_rules += tx
+            .execute("UPDATE tasks SET recurrence_rule = NULL WHERE id = ?1", params![id])? as i64;
+    }
+
+    let mut cleared_dates = 0;
+    for d in &report.invalid_dates {
+        let sql = format!("UPDATE {} SET {} = NULL WHERE id = ?1", d.table, d.field);
+        cleared_dates += tx.execute(&sql, params![d.id])? as i64;
+    }
+
+    tx.commit()?;
+
+    Ok(RepairReport {
+        removed_rows,
+        cleared_references,
+        reattached_sub_tasks,
+        cleared_recurrence_rules,
+        cleared_dates,
+        remaining: check_database(conn)?,
+    })
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+
+    const NOW: &str = "2024-01-01T00:00:00+00:00";
+
+    fn insert_task(conn: &Connection, id: &str, category_id: Option<&str>, due_date: Option<&str>, recurrence: Option<&str>) {
+        conn.execute(
+            "INSERT INTO tasks (id, title, category_id, due_date, recurrence_rule, created_at, updated_at)
+             VALUES (?1, ?1, ?2, ?3, ?4, ?5, ?5)",
+            params![id, category_id, due_date, recurrence, NOW],
+        )
+        .unwrap();
+    }
+
+    fn insert_sub_task(conn: &Connection, id: &str, task_id: &str, parent_id: Option<&str>) {
+        conn.execute(
+            "INSERT INTO sub_tasks (id, task_id, parent_id, title, created_at) VALUES (?1, ?2, ?3, ?1, ?4)",
+            params![id, task_id, parent_id, NOW],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_clean_database_is_ok() {
+        let conn = open_test_connection();
+        insert_task(&conn, "t1", None, Some("2024-05-01"), Some(r#"{"type":"daily","interval":1}"#));
+        insert_sub_task(&conn, "s1", "t1", None);
+        insert_sub_task(&conn, "s2", "t1", Some("s1"));
+
+        assert!(check_database(&conn).unwrap().ok);
+    }
+
+    #[test]
+    fn test_check_and_repair_problems() {
+        let conn = open_test_connection();
+        insert_task(&conn, "t1", None, Some("next friday"), Some("{not json"));
+        insert_task(&conn, "t2", None, None, None);
+        insert_sub_task(&conn, "s2", "t2", None);
+
+        conn.execute_batch("PRAGMA foreign_keys = OFF").unwrap();
+        insert_sub_task(&conn, "s1", "t1", Some("gone"));
+        insert_task(&conn, "t3", Some("missing-category"), None, None);
+        conn.execute("INSERT INTO task_tags (task_id, tag_id) VALUES ('t1', 'missing-tag')", []).unwrap();
+        conn.execute(
+            "INSERT INTO attachments (id, task_id, file_name, size_bytes, sha256, created_at)
+             VALUES ('a1', 'gone', 'a.txt', 1, 'abc', '')",
+            [],
+        )
+        .unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+
+        let report = check_database(&conn).unwrap();
+        assert!(!report.ok);
+        assert!(report.integrity_errors.is_empty());
+        assert_eq!(report.foreign_key_violations.len(), 4);
+        assert_eq!(report.orphaned_sub_tasks, vec!["s1"]);
+        assert_eq!(report.invalid_recurrence_rules, vec!["t1"]);
+        assert_eq!(report.invalid_dates.len(), 1);
+        assert_eq!(report.invalid_dates[0].field, "due_date");
+
+        let repair = repair_database(&conn).unwrap();
+        assert_eq!(repair.removed_rows, 2);
+        assert_eq!(repair.cleared_references, 1);
+        assert_eq!(repair.reattached_sub_tasks, 1);
+        assert_eq!(repair.cleared_recurrence_rules, 1);
+        assert_eq!(repair.cleared_dates, 1);
+        assert!(repair.remaining.ok);
+    }
+}
@@ -0,0 +1,181 @@
+use rusqlite::{Connection, params};
+use crate::models::*;
+use crate::services::change_log::{self, TASK};
+use crate::services::{settings, task};
+
+// Closed tasks are archived `logbook_archive_days` after closing. Archived tasks
+// keep their status and relations but drop out of `get_all_tasks` and the views
+// (the Logbook view included), so those don't keep loading years of finished work.
+// `get_logbook` is the full history: every closed task, archived or not.
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Archive closed tasks whose `completed_at` is before `cutoff` (RFC 3339).
+pub fn archive_closed_before(conn: &Connection, cutoff: &str) -> Result<usize, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM tasks
+         WHERE deleted_at IS NULL AND archived_at IS NULL AND status != 'todo'
+         AND COALESCE(completed_at, updated_at) < ?1",
+    )?;
+    let ids: Vec<String> = stmt
+        .query_map(params![cutoff], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    change_log::in_transaction(conn, || {
+        let mut archived = 0;
+        for id in &ids {
+            archived += change_log::tracked(conn, TASK, id, "archive", || {
+                conn.execute("UPDATE tasks SET archived_at = ?1 WHERE id = ?2", params![now, id])
+                    .map_err(AppError::from)
+            })?;
+        }
+        Ok(archived)
+    })
+}
+
+/// Archive tasks closed longer ago than the configured number of days.
+pub fn archive_expired(conn: &Connection) -> Result<usize, AppError> {
+    let days = settings::get_settings(conn)?.logbook_archive_days;
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+    archive_closed_before(conn, &cutoff)
+}
+
+/// One page of closed tasks, archived or not, most recently closed first. `page`
+/// counts from 0.
+///
+/// Pages split on tasks, not dates, so a day can continue on the next page with
+/// a group of the same date.
+pub fn get_logbook(conn: &Connection, page: Option<i64>, page_size: Option<i64>) -> Result<LogbookPage, AppError> {
+    let page = page.unwrap_or(0);
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+    if page < 0 {
+        return Err(AppError::validation("page must not be negative")
+            .with_detail("field", "page")
+            .with_detail("value", page.to_string()));
+    }
+    if !(1..=MAX_PAGE_SIZE).contains(&page_size) {
+        return Err(AppError::validation(format!("pageSize must be between 1 and {}", MAX_PAGE_SIZE))
+            .with_detail("field", "pageSize")
+            .with_detail("value", page_size.to_string()));
+    }
+
+    let total: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM tasks WHERE deleted_at IS NULL AND status != 'todo'",
+        [],
+        |row| row.get(0),
+    )?;
+    let mut stmt = conn.prepare(
+        "SELECT * FROM tasks WHERE deleted_at IS NULL AND status != 'todo'
+         ORDER BY COALESCE(completed_at, updated_at) DESC, id
+         LIMIT ?1 OFFSET ?2",
+    )?;
+    let rows: Vec<Task> = stmt
+        .query_map(params![page_size, page.saturating_mul(page_size)], task::row_to_task)?
+        .collect::<Result<_, _>>()?;
+    let has_more = (page + 1).saturating_mul(page_size) < total;
+
+    let mut groups: Vec<LogbookGroup> = Vec::new();
+    for t in task::with_relations(conn, rows)? {
+        let date = closed_on(&t);
+        match groups.last_mut() {
+            Some(group) if group.date == date => group.tasks.push(t),
+            _ => groups.push(LogbookGroup { date, tasks: vec![t] }),
+        }
+    }
+
+    Ok(LogbookPage { groups, page, page_size, total, has_more })
+}
+
+/// The local date a task was closed on. Falls back to the raw date part of the
+/// timestamp when it doesn't parse (only possible in hand-edited data).
+fn closed_on(t: &Task) -> String {
+    let closed_at = t.completed_at.as_deref().unwrap_or(&t.updated_at);
+    match chrono::DateTime::parse_from_rfc3339(closed_at) {
+        Ok(at) => at.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string(),
+        Err(_) => closed_at.chars().take(10).collect(),
+    }
+}
+
+/// Bring an archived task back to the task list, reopened. Left closed, it would
+/// only be archived again on the next sweep.
+pub fn unarchive_task(conn: &Connection, id: &str) -> Result<Task, AppError> {
+    let archived: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM tasks WHERE id = ?1 AND deleted_at IS NULL AND archived_at IS NOT NULL",
+            params![id],
+            |row| row.get(0),
+        )?;
+    if !archived {
+        return Err(AppError::not_found("Archived task not found").with_detail("id", id));
+    }
+    // Reopening clears `archived_at` along with the completion
+    task::uncomplete_task(conn, id)
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_test_task, open_test_connection};
+    use crate::services::view;
+
+    fn closed_task(conn: &Connection, title: &str, completed_at: &str) -> Task {
+        let t = create_test_task(conn, title);
+        task::complete_task(conn, &t.id).unwrap();
+        conn.execute("UPDATE tasks SET completed_at = ?1 WHERE id = ?2", params![completed_at, t.id]).unwrap();
+        t
+    }
+
+    #[test]
+    fn test_archived_tasks_leave_the_lists() {
+        let conn = open_test_connection();
+        let old = closed_task(&conn, "Old", "2024-01-10T12:00:00+00:00");
+        closed_task(&conn, "Recent", "2024-03-01T12:00:00+00:00");
+
+        assert_eq!(archive_closed_before(&conn, "2024-02-01T00:00:00+00:00").unwrap(), 1);
+        assert_eq!(archive_closed_before(&conn, "2024-02-01T00:00:00+00:00").unwrap(), 0);
+        let titles = |tasks: Vec<Task>| tasks.into_iter().map(|t| t.title).collect::<Vec<_>>();
+        assert_eq!(titles(task::get_all_tasks(&conn, None).unwrap()), vec!["Recent"]);
+        assert_eq!(titles(view::get_view(&conn, "logbook").unwrap()), vec!["Recent"]);
+        assert!(task::get_task_by_id(&conn, &old.id).unwrap().unwrap().archived_at.is_some());
+
+        let restored = unarchive_task(&conn, &old.id).unwrap();
+        assert_eq!((restored.status, restored.archived_at), (TaskStatus::Todo, None));
+        assert_eq!(task::get_all_tasks(&conn, None).unwrap().len(), 2);
+        assert_eq!(unarchive_task(&conn, &old.id).unwrap_err().code, ErrorCode::NotFound);
+    }
+
+    #[test]
+    fn test_logbook_pages_group_by_completion_date() {
+        let conn = open_test_connection();
+        closed_task(&conn, "A", "2024-01-10T09:00:00+00:00");
+        closed_task(&conn, "B", "2024-01-12T10:00:00+00:00");
+        closed_task(&conn, "C", "2024-01-12T11:00:00+00:00");
+        archive_closed_before(&conn, "2024-02-01T00:00:00+00:00").unwrap();
+        // Not archived yet, but part of the history all the same
+        closed_task(&conn, "D", "2024-03-01T12:00:00+00:00");
+
+        let first = get_logbook(&conn, Some(0), Some(2)).unwrap();
+        assert_eq!((first.total, first.has_more), (4, true));
+        let titles: Vec<&str> = first.groups.iter().flat_map(|g| &g.tasks).map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["D", "C"]);
+
+        let second = get_logbook(&conn, Some(1), Some(2)).unwrap();
+        assert!(!second.has_more);
+        let titles: Vec<&str> = second.groups.iter().flat_map(|g| &g.tasks).map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["B", "A"]);
+        assert_eq!(second.groups.len(), 2);
+        assert_eq!(second.groups[0].date, first.groups[1].date);
+
+        let err = get_logbook(&conn, None, Some(0)).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "pageSize");
+    }
+}
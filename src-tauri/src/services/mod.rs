@@ -6,3 +6,20 @@ pub mod notification;
 pub mod statistics;
 pub mod data;
 pub mod note;
+pub mod backup;
+pub mod settings;
+pub mod integrity;
+pub mod trash;
+pub mod change_log;
+pub mod undo;
+pub mod workspace;
+pub mod dependency;
+pub mod view;
+pub mod project;
+pub mod time_entry;
+pub mod focus;
+pub mod pomodoro;
+pub mod bulk;
+pub mod template;
+pub mod logbook;
+pub mod attachment;
@@ -1,6 +1,7 @@
 use rusqlite::{Connection, params, OptionalExtension};
 use uuid::Uuid;
 use crate::models::*;
+use crate::services::change_log::{self, NOTE};
 
 // ============================================================
 // Row → Model mapper
@@ -19,14 +20,6 @@ fn row_to_note(row: &rusqlite::Row) -> rusqlite::Result<Note> {
     })
 }
 
-fn make_error(code: &str, message: &str) -> AppError {
-    AppError {
-        code: code.to_string(),
-        message: message.to_string(),
-        details: None,
-    }
-}
-
 // ============================================================
 // CRUD
 // ============================================================
@@ -37,43 +30,53 @@ pub fn create_note(conn: &Connection, input: CreateNoteInput) -> Result<Note, Ap
     let title = input.title.unwrap_or_default();
     let content = input.content.unwrap_or_default();
 
-    conn.execute(
-        "INSERT INTO notes (id, title, content, is_pinned, sort_order, created_at, updated_at)
-         VALUES (?1, ?2, ?3, 0, 0, ?4, ?5)",
-        params![id, title, content, now, now],
-    )
-    .map_err(|e| make_error("DB_ERROR", &e.to_string()))?;
+    change_log::tracked(conn, NOTE, &id, "create", || {
+        conn.execute(
+            "INSERT INTO notes (id, title, content, is_pinned, sort_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, 0, 0, ?4, ?5)",
+            params![id, title, content, now, now],
+        )
+        .map_err(AppError::from)
+    })?;
 
     get_note_by_id(conn, &id)?
-        .ok_or_else(|| make_error("NOT_FOUND", "Note not found after creation"))
+        .ok_or_else(|| AppError::not_found("Note not found after creation"))
 }
 
 pub fn update_note(conn: &Connection, id: &str, input: UpdateNoteInput) -> Result<Note, AppError> {
     let existing = get_note_by_id(conn, id)?
-        .ok_or_else(|| make_error("NOT_FOUND", &format!("Note {} not found", id)))?;
+        .ok_or_else(|| AppError::not_found(format!("Note {} not found", id)))?;
 
     let title = input.title.unwrap_or(existing.title);
     let content = input.content.unwrap_or(existing.content);
     let is_pinned = input.is_pinned.unwrap_or(existing.is_pinned);
     let now = chrono::Utc::now().to_rfc3339();
 
-    conn.execute(
-        "UPDATE notes SET title = ?1, content = ?2, is_pinned = ?3, updated_at = ?4 WHERE id = ?5",
-        params![title, content, is_pinned as i64, now, id],
-    )
-    .map_err(|e| make_error("DB_ERROR", &e.to_string()))?;
+    change_log::tracked(conn, NOTE, id, "update", || {
+        conn.execute(
+            "UPDATE notes SET title = ?1, content = ?2, is_pinned = ?3, updated_at = ?4 WHERE id = ?5",
+            params![title, content, is_pinned as i64, now, id],
+        )
+        .map_err(AppError::from)
+    })?;
 
     get_note_by_id(conn, id)?
-        .ok_or_else(|| make_error("NOT_FOUND", "Note not found after update"))
+        .ok_or_else(|| AppError::not_found("Note not found after update"))
 }
 
+/// Move a note to the trash.
 pub fn delete_note(conn: &Connection, id: &str) -> Result<(), AppError> {
-    let changes = conn
-        .execute("DELETE FROM notes WHERE id = ?1", params![id])
-        .map_err(|e| make_error("DB_ERROR", &e.to_string()))?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let changes = change_log::tracked(conn, NOTE, id, "delete", || {
+        conn.execute(
+            "UPDATE notes SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(AppError::from)
+    })?;
 
     if changes == 0 {
-        return Err(make_error("NOT_FOUND", &format!("Note {} not found", id)));
+        return Err(AppError::not_found(format!("Note {} not found", id)));
     }
     Ok(())
 }
@@ -81,28 +84,22 @@ pub fn delete_note(conn: &Connection, id: &str) -> Result<(), AppError> {
 pub fn get_all_notes(conn: &Connection) -> Result<Vec<Note>, AppError> {
     let mut stmt = conn
         .prepare(
-            "SELECT * FROM notes ORDER BY is_pinned DESC, updated_at DESC",
-        )
-        .map_err(|e| make_error("DB_ERROR", &e.to_string()))?;
+            "SELECT * FROM notes WHERE deleted_at IS NULL ORDER BY is_pinned DESC, updated_at DESC",
+        )?;
 
     let notes = stmt
-        .query_map([], |row| row_to_note(row))
-        .map_err(|e| make_error("DB_ERROR", &e.to_string()))?
-        .filter_map(|r| r.ok())
-        .collect();
+        .query_map([], |row| row_to_note(row))?
+        .collect::<Result<_, _>>()?;
 
     Ok(notes)
 }
 
 pub fn get_note_by_id(conn: &Connection, id: &str) -> Result<Option<Note>, AppError> {
-    let mut stmt = conn
-        .prepare("SELECT * FROM notes WHERE id = ?1")
-        .map_err(|e| make_error("DB_ERROR", &e.to_string()))?;
+    let mut stmt = conn.prepare("SELECT * FROM notes WHERE id = ?1 AND deleted_at IS NULL")?;
 
     let note = stmt
         .query_row(params![id], |row| row_to_note(row))
-        .optional()
-        .map_err(|e| make_error("DB_ERROR", &e.to_string()))?;
+        .optional()?;
 
     Ok(note)
 }
@@ -117,16 +114,13 @@ pub fn search_notes(conn: &Connection, query: Option<String>) -> Result<Vec<Note
     let mut stmt = conn
         .prepare(
             "SELECT * FROM notes
-             WHERE title LIKE ?1 OR content LIKE ?1
+             WHERE (title LIKE ?1 OR content LIKE ?1) AND deleted_at IS NULL
              ORDER BY is_pinned DESC, updated_at DESC",
-        )
-        .map_err(|e| make_error("DB_ERROR", &e.to_string()))?;
+        )?;
 
     let notes = stmt
-        .query_map(params![pattern], |row| row_to_note(row))
-        .map_err(|e| make_error("DB_ERROR", &e.to_string()))?
-        .filter_map(|r| r.ok())
-        .collect();
+        .query_map(params![pattern], |row| row_to_note(row))?
+        .collect::<Result<_, _>>()?;
 
     Ok(notes)
 }
@@ -141,19 +135,7 @@ mod tests {
     use rusqlite::Connection;
 
     fn setup_db() -> Connection {
-        let conn = Connection::open_in_memory().unwrap();
-        conn.execute_batch(
-            "CREATE TABLE notes (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL DEFAULT '',
-                content TEXT NOT NULL DEFAULT '',
-                is_pinned INTEGER NOT NULL DEFAULT 0,
-                sort_order INTEGER NOT NULL DEFAULT 0,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );"
-        ).unwrap();
-        conn
+        crate::db::open_test_connection()
     }
 
     #[test]
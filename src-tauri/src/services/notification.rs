@@ -69,7 +69,8 @@ pub fn check_missed_reminders(conn: &Connection, app: &tauri::AppHandle) {
     let now = chrono::Utc::now().to_rfc3339();
 
     let mut stmt = match conn.prepare(
-        "SELECT id, title FROM tasks WHERE status = 'todo' AND reminder_time IS NOT NULL AND reminder_time <= ?1"
+        "SELECT id, title FROM tasks
+         WHERE status = 'todo' AND reminder_time IS NOT NULL AND reminder_time <= ?1 AND deleted_at IS NULL"
     ) {
         Ok(s) => s,
         Err(_) => return,
@@ -78,7 +79,7 @@ pub fn check_missed_reminders(conn: &Connection, app: &tauri::AppHandle) {
     let missed: Vec<(String, String)> = match stmt.query_map(params![now], |row| {
         Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
     }) {
-        Ok(rows) => rows.filter_map(|r| r.ok()).collect(),
+        Ok(rows) => rows.filter_map(|r| r.map_err(|e| log::warn!("Skipping unreadable reminder row: {}", e)).ok()).collect(),
         Err(_) => vec![],
     };
 
@@ -93,7 +94,8 @@ pub fn schedule_all_future_reminders(conn: &Connection, state: &NotificationStat
     let now = chrono::Utc::now().to_rfc3339();
 
     let mut stmt = match conn.prepare(
-        "SELECT id, title, reminder_time FROM tasks WHERE status = 'todo' AND reminder_time IS NOT NULL"
+        "SELECT id, title, reminder_time FROM tasks
+         WHERE status = 'todo' AND reminder_time IS NOT NULL AND deleted_at IS NULL"
     ) {
         Ok(s) => s,
         Err(_) => return,
@@ -106,7 +108,7 @@ pub fn schedule_all_future_reminders(conn: &Connection, state: &NotificationStat
             row.get::<_, String>(2)?,
         ))
     }) {
-        Ok(rows) => rows.filter_map(|r| r.ok()).collect(),
+        Ok(rows) => rows.filter_map(|r| r.map_err(|e| log::warn!("Skipping unreadable reminder row: {}", e)).ok()).collect(),
         Err(_) => vec![],
     };
 
@@ -118,7 +120,6 @@ pub fn schedule_all_future_reminders(conn: &Connection, state: &NotificationStat
 }
 
 /// Clear all scheduled reminders.
-#[allow(dead_code)]
 pub fn clear_all(state: &NotificationState) {
     for (_, flag) in state.cancel_flags.lock().unwrap().drain() {
         flag.store(true, std::sync::atomic::Ordering::Relaxed);
@@ -127,7 +128,7 @@ pub fn clear_all(state: &NotificationState) {
 }
 
 /// Deliver a native macOS notification via Tauri plugin.
-fn deliver_notification(app: &tauri::AppHandle, title: &str) {
+pub(crate) fn deliver_notification(app: &tauri::AppHandle, title: &str) {
     use tauri_plugin_notification::NotificationExt;
     let _ = app
         .notification()
@@ -0,0 +1,145 @@
+use rusqlite::{Connection, params};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::Manager;
+use crate::db::Database;
+use crate::models::*;
+use crate::services::{focus, notification, settings};
+
+/// Pomodoro engine state. One phase runs at a time, timed by a sleeping thread like
+/// the reminders in `notification.rs`. It lives in memory only: quitting the app
+/// abandons the phase in progress without a record.
+///
+/// A finished work session is recorded and rolls straight into its break; a
+/// finished break leaves the engine idle until the next `start`.
+pub struct PomodoroState {
+    current: Mutex<Option<PomodoroStatus>>,
+    /// Work sessions finished since the last long break; kept while idle
+    completed_in_cycle: Mutex<i64>,
+    /// Bumped on every phase change, so a timer thread from an earlier phase
+    /// wakes up stale and does nothing
+    generation: AtomicU64,
+}
+
+impl PomodoroState {
+    pub fn new() -> Self {
+        Self {
+            current: Mutex::new(None),
+            completed_in_cycle: Mutex::new(0),
+            generation: AtomicU64::new(0),
+        }
+    }
+}
+
+pub fn status(state: &PomodoroState) -> Option<PomodoroStatus> {
+    state.current.lock().unwrap().clone()
+}
+
+/// Start a work session, cutting short any break in progress.
+pub fn start(
+    state: &PomodoroState,
+    app: &tauri::AppHandle,
+    conn: &Connection,
+    task_id: Option<String>,
+) -> Result<PomodoroStatus, AppError> {
+    if let Some(ref id) = task_id {
+        let exists: bool = conn
+            .query_row("SELECT COUNT(*) > 0 FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![id], |row| row.get(0))?;
+        if !exists {
+            return Err(AppError::not_found("Task not found").with_detail("id", id.as_str()));
+        }
+    }
+    let settings = settings::get_settings(conn)?;
+
+    let mut current = state.current.lock().unwrap();
+    if current.as_ref().is_some_and(|s| s.phase == PomodoroPhase::Work) {
+        return Err(AppError::new(ErrorCode::Conflict, "A focus session is already running"));
+    }
+    begin(state, app, &mut current, PomodoroPhase::Work, task_id, &settings)
+}
+
+/// Stop the phase in progress. A work session stopped early is recorded as not
+/// completed and returned.
+pub fn stop(state: &PomodoroState, conn: &Connection) -> Result<Option<FocusSession>, AppError> {
+    let mut current = state.current.lock().unwrap();
+    state.generation.fetch_add(1, Ordering::SeqCst);
+    match current.take() {
+        Some(status) if status.phase == PomodoroPhase::Work => {
+            let now = chrono::Utc::now().to_rfc3339();
+            focus::record_session(conn, status.task_id.as_deref(), &status.started_at, &now, false).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Enter `phase` and start its timer. `current` is the locked `state.current`.
+fn begin(
+    state: &PomodoroState,
+    app: &tauri::AppHandle,
+    current: &mut Option<PomodoroStatus>,
+    phase: PomodoroPhase,
+    task_id: Option<String>,
+    settings: &AppSettings,
+) -> Result<PomodoroStatus, AppError> {
+    let minutes = focus::phase_minutes(phase, settings);
+    let now = chrono::Utc::now();
+    // Settings are checked on write, but the stored value may predate the check
+    let invalid = || {
+        AppError::validation(format!("{} minutes isn't a usable phase length", minutes))
+            .with_detail("value", minutes.to_string())
+    };
+    let length = chrono::Duration::try_minutes(minutes).ok_or_else(invalid)?;
+    let sleep = length.to_std().ok().filter(|d| !d.is_zero()).ok_or_else(invalid)?;
+    let ends_at = now.checked_add_signed(length).ok_or_else(invalid)?;
+    let status = PomodoroStatus {
+        phase,
+        task_id,
+        started_at: now.to_rfc3339(),
+        ends_at: ends_at.to_rfc3339(),
+        completed_in_cycle: *state.completed_in_cycle.lock().unwrap(),
+    };
+    *current = Some(status.clone());
+
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(sleep);
+        finish_phase(&app_handle, generation);
+    });
+    Ok(status)
+}
+
+fn finish_phase(app: &tauri::AppHandle, generation: u64) {
+    let state = app.state::<PomodoroState>();
+    let db = app.state::<Database>();
+    // Same lock order as the commands: connection first, then the session
+    let conn = db.write();
+    let mut current = state.current.lock().unwrap();
+    if state.generation.load(Ordering::SeqCst) != generation {
+        return;
+    }
+    let Some(status) = current.take() else { return };
+    let settings = settings::get_settings(&conn).unwrap_or_default();
+
+    if status.phase != PomodoroPhase::Work {
+        notification::deliver_notification(app, "休息结束，开始下一个番茄吧");
+        return;
+    }
+
+    if let Err(e) = focus::record_session(&conn, status.task_id.as_deref(), &status.started_at, &status.ends_at, true) {
+        log::error!("Failed to record focus session: {}", e);
+    }
+    let next = {
+        let mut completed = state.completed_in_cycle.lock().unwrap();
+        *completed += 1;
+        let next = focus::break_after(*completed, &settings);
+        if next == PomodoroPhase::LongBreak {
+            *completed = 0;
+        }
+        next
+    };
+    if let Err(e) = begin(&state, app, &mut current, next, status.task_id, &settings) {
+        log::error!("Failed to start the break: {}", e);
+    }
+    notification::deliver_notification(app, "专注完成，休息一下吧");
+}
@@ -0,0 +1,471 @@
+use rusqlite::{Connection, params, OptionalExtension};
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::models::*;
+use crate::services::change_log::{self, HEADING, PROJECT, TASK};
+use crate::utils::validate;
+
+// Trashing a project trashes its live tasks with the same `deleted_at`, and
+// restoring it brings back exactly those, like a sub-task subtree. Headings have
+// no trash of their own: deleting one just takes its tasks out of it.
+
+fn row_to_project(row: &rusqlite::Row) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: row.get("id")?,
+        category_id: row.get("category_id")?,
+        title: row.get("title")?,
+        notes: row.get("notes")?,
+        deadline: row.get("deadline")?,
+        status: row.get("status")?,
+        completed_at: row.get("completed_at")?,
+        sort_order: row.get("sort_order")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        headings: Vec::new(),
+        progress: ProjectProgress::default(),
+    })
+}
+
+fn row_to_heading(row: &rusqlite::Row) -> rusqlite::Result<Heading> {
+    Ok(Heading {
+        id: row.get("id")?,
+        project_id: row.get("project_id")?,
+        title: row.get("title")?,
+        sort_order: row.get("sort_order")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+pub(crate) fn ensure_live(conn: &Connection, id: &str) -> Result<(), AppError> {
+    let exists: bool = conn
+        .query_row("SELECT COUNT(*) > 0 FROM projects WHERE id = ?1 AND deleted_at IS NULL", params![id], |row| row.get(0))?;
+    if !exists {
+        return Err(AppError::not_found("Project not found").with_detail("id", id));
+    }
+    Ok(())
+}
+
+fn validate_title(title: &str, what: &str) -> Result<(), AppError> {
+    if title.trim().is_empty() {
+        return Err(AppError::validation(format!("{} title must not be empty", what)).with_detail("field", "title"));
+    }
+    Ok(())
+}
+
+// ============================================================
+// Projects
+// ============================================================
+
+pub fn create_project(conn: &Connection, input: CreateProjectInput) -> Result<Project, AppError> {
+    validate_title(&input.title, "Project")?;
+    if let Some(ref deadline) = input.deadline {
+        validate::date("deadline", deadline)?;
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let max_order: i64 = conn
+        .query_row("SELECT COALESCE(MAX(sort_order), -1) FROM projects", [], |row| row.get(0))?;
+
+    change_log::tracked(conn, PROJECT, &id, "create", || {
+        conn.execute(
+            "INSERT INTO projects (id, category_id, title, notes, deadline, status, sort_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'active', ?6, ?7, ?7)",
+            params![id, input.category_id, input.title.trim(), input.notes, input.deadline, max_order + 1, now],
+        )
+        .map_err(AppError::from)
+    })?;
+
+    get_project_by_id(conn, &id)?.ok_or_else(|| AppError::not_found("Project not found"))
+}
+
+pub fn get_projects(conn: &Connection) -> Result<Vec<Project>, AppError> {
+    let mut stmt = conn.prepare("SELECT * FROM projects WHERE deleted_at IS NULL ORDER BY sort_order")?;
+    let mut projects: Vec<Project> = stmt
+        .query_map([], row_to_project)?
+        .collect::<Result<_, _>>()?;
+    attach_details(conn, &mut projects)?;
+    Ok(projects)
+}
+
+pub fn get_project_by_id(conn: &Connection, id: &str) -> Result<Option<Project>, AppError> {
+    let project = conn
+        .query_row("SELECT * FROM projects WHERE id = ?1 AND deleted_at IS NULL", params![id], row_to_project)
+        .optional()?;
+    let Some(mut project) = project else { return Ok(None) };
+    attach_details(conn, std::slice::from_mut(&mut project))?;
+    Ok(Some(project))
+}
+
+/// Fill in `headings` and `progress`. Progress counts live tasks; canceled ones
+/// are left out entirely so they don't hold a project below 100%.
+fn attach_details(conn: &Connection, projects: &mut [Project]) -> Result<(), AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT h.* FROM headings h INNER JOIN projects p ON p.id = h.project_id
+         WHERE p.deleted_at IS NULL ORDER BY h.sort_order",
+    )?;
+    let mut headings: HashMap<String, Vec<Heading>> = HashMap::new();
+    for heading in stmt.query_map([], row_to_heading)? {
+        let heading = heading?;
+        headings.entry(heading.project_id.clone()).or_default().push(heading);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT project_id, COUNT(*), SUM(status = 'completed') FROM tasks
+         WHERE project_id IS NOT NULL AND deleted_at IS NULL AND status != 'canceled'
+         GROUP BY project_id",
+    )?;
+    let counts: HashMap<String, (i64, i64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+        .collect::<Result<_, _>>()?;
+
+    for project in projects.iter_mut() {
+        project.headings = headings.remove(&project.id).unwrap_or_default();
+        let (total_tasks, completed_tasks) = counts.get(&project.id).copied().unwrap_or((0, 0));
+        let ratio = if total_tasks == 0 { 0.0 } else { completed_tasks as f64 / total_tasks as f64 };
+        project.progress = ProjectProgress { total_tasks, completed_tasks, ratio };
+    }
+    Ok(())
+}
+
+pub fn update_project(conn: &Connection, id: &str, input: UpdateProjectInput) -> Result<Project, AppError> {
+    ensure_live(conn, id)?;
+    if let Some(ref title) = input.title {
+        validate_title(title, "Project")?;
+    }
+    if let Some(Some(ref deadline)) = input.deadline {
+        validate::date("deadline", deadline)?;
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut sets: Vec<String> = vec!["updated_at = ?".to_string()];
+    let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(now)];
+
+    if let Some(ref title) = input.title {
+        sets.push("title = ?".to_string());
+        values.push(Box::new(title.trim().to_string()));
+    }
+    if let Some(ref notes) = input.notes {
+        sets.push("notes = ?".to_string());
+        values.push(Box::new(notes.clone()));
+    }
+    if let Some(ref category_id) = input.category_id {
+        sets.push("category_id = ?".to_string());
+        values.push(Box::new(category_id.clone()));
+    }
+    if let Some(ref deadline) = input.deadline {
+        sets.push("deadline = ?".to_string());
+        values.push(Box::new(deadline.clone()));
+    }
+    if let Some(order) = input.sort_order {
+        sets.push("sort_order = ?".to_string());
+        values.push(Box::new(order));
+    }
+
+    values.push(Box::new(id.to_string()));
+    let sql = format!("UPDATE projects SET {} WHERE id = ?", sets.join(", "));
+    let params_refs: Vec<&dyn rusqlite::types::ToSql> = values.iter().map(|v| v.as_ref()).collect();
+    change_log::tracked(conn, PROJECT, id, "update", || {
+        conn.execute(&sql, params_refs.as_slice()).map_err(AppError::from)
+    })?;
+
+    get_project_by_id(conn, id)?.ok_or_else(|| AppError::not_found("Project not found"))
+}
+
+/// Move a project and its live tasks to the trash.
+pub fn delete_project(conn: &Connection, id: &str) -> Result<(), AppError> {
+    ensure_live(conn, id)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let task_ids = project_task_ids(conn, "deleted_at IS NULL", params![id])?;
+    change_log::in_transaction(conn, || {
+        change_log::tracked(conn, PROJECT, id, "delete", || {
+            conn.execute("UPDATE projects SET deleted_at = ?1 WHERE id = ?2", params![now, id])
+                .map_err(AppError::from)
+        })?;
+        for task_id in &task_ids {
+            change_log::tracked(conn, TASK, task_id, "delete", || {
+                conn.execute("UPDATE tasks SET deleted_at = ?1 WHERE id = ?2", params![now, task_id])
+                    .map_err(AppError::from)
+            })?;
+        }
+        Ok(())
+    })
+}
+
+/// Bring a project back from the trash, with the tasks that went there with it.
+pub fn restore_project(conn: &Connection, id: &str) -> Result<(), AppError> {
+    let deleted_at: Option<Option<String>> = conn
+        .query_row("SELECT deleted_at FROM projects WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()?;
+    let Some(Some(deleted_at)) = deleted_at else {
+        return Err(AppError::not_found("Project not found in trash"));
+    };
+
+    let task_ids = project_task_ids(conn, "deleted_at = ?2", params![id, deleted_at])?;
+    change_log::in_transaction(conn, || {
+        change_log::tracked(conn, PROJECT, id, "restore", || {
+            conn.execute("UPDATE projects SET deleted_at = NULL WHERE id = ?1", params![id])
+                .map_err(AppError::from)
+        })?;
+        for task_id in &task_ids {
+            change_log::tracked(conn, TASK, task_id, "restore", || {
+                conn.execute("UPDATE tasks SET deleted_at = NULL WHERE id = ?1", params![task_id])
+                    .map_err(AppError::from)
+            })?;
+        }
+        Ok(())
+    })
+}
+
+/// Ids of the project's live tasks, e.g. to resync their reminders.
+pub fn live_task_ids(conn: &Connection, id: &str) -> Result<Vec<String>, AppError> {
+    project_task_ids(conn, "deleted_at IS NULL", params![id])
+}
+
+/// Ids of the project's tasks (`?1`) that match `condition`.
+fn project_task_ids(conn: &Connection, condition: &str, params: impl rusqlite::Params) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare(&format!("SELECT id FROM tasks WHERE project_id = ?1 AND {} ORDER BY sort_order", condition))?;
+    let ids: Vec<String> = stmt
+        .query_map(params, |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(ids)
+}
+
+/// Mark a project completed. `remaining` ("complete" or "cancel") closes the tasks
+/// still open; without it they stay open in the project.
+///
+/// Tasks closed this way don't spawn their next recurrence.
+pub fn complete_project(conn: &Connection, id: &str, remaining: Option<&str>) -> Result<Project, AppError> {
+    ensure_live(conn, id)?;
+    let remaining = remaining.map(|r| RemainingTasks::parse("remainingTasks", r)).transpose()?;
+    let now = chrono::Utc::now().to_rfc3339();
+
+    change_log::in_transaction(conn, || {
+        if let Some(remaining) = remaining {
+            let status = match remaining {
+                RemainingTasks::Complete => TaskStatus::Completed,
+                RemainingTasks::Cancel => TaskStatus::Canceled,
+            };
+            for task_id in project_task_ids(conn, "deleted_at IS NULL AND status = 'todo'", params![id])? {
+                change_log::tracked(conn, TASK, &task_id, "update", || {
+                    conn.execute(
+                        "UPDATE tasks SET status = ?1, completed_at = ?2, updated_at = ?2 WHERE id = ?3",
+                        params![status, now, task_id],
+                    )
+                    .map_err(AppError::from)
+                })?;
+            }
+        }
+        change_log::tracked(conn, PROJECT, id, "update", || {
+            conn.execute(
+                "UPDATE projects SET status = 'completed', completed_at = ?1, updated_at = ?1 WHERE id = ?2",
+                params![now, id],
+            )
+            .map_err(AppError::from)
+        })
+    })?;
+
+    get_project_by_id(conn, id)?.ok_or_else(|| AppError::not_found("Project not found"))
+}
+
+/// Mark a project active again. Its tasks are left as they are.
+pub fn reopen_project(conn: &Connection, id: &str) -> Result<Project, AppError> {
+    ensure_live(conn, id)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    change_log::tracked(conn, PROJECT, id, "update", || {
+        conn.execute(
+            "UPDATE projects SET status = 'active', completed_at = NULL, updated_at = ?1 WHERE id = ?2",
+            params![now, id],
+        )
+        .map_err(AppError::from)
+    })?;
+    get_project_by_id(conn, id)?.ok_or_else(|| AppError::not_found("Project not found"))
+}
+
+// ============================================================
+// Headings
+// ============================================================
+
+pub fn create_heading(conn: &Connection, project_id: &str, title: &str) -> Result<Heading, AppError> {
+    ensure_live(conn, project_id)?;
+    validate_title(title, "Heading")?;
+
+    let id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let max_order: i64 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(sort_order), -1) FROM headings WHERE project_id = ?1",
+            params![project_id],
+            |row| row.get(0),
+        )?;
+
+    change_log::tracked(conn, HEADING, &id, "create", || {
+        conn.execute(
+            "INSERT INTO headings (id, project_id, title, sort_order, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, project_id, title.trim(), max_order + 1, now],
+        )
+        .map_err(AppError::from)
+    })?;
+
+    Ok(Heading {
+        id,
+        project_id: project_id.to_string(),
+        title: title.trim().to_string(),
+        sort_order: max_order + 1,
+        created_at: now,
+    })
+}
+
+pub fn update_heading(conn: &Connection, id: &str, input: UpdateHeadingInput) -> Result<Heading, AppError> {
+    let exists: bool = conn
+        .query_row("SELECT COUNT(*) > 0 FROM headings WHERE id = ?1", params![id], |row| row.get(0))?;
+    if !exists {
+        return Err(AppError::not_found("Heading not found"));
+    }
+    if let Some(ref title) = input.title {
+        validate_title(title, "Heading")?;
+    }
+
+    change_log::tracked(conn, HEADING, id, "update", || {
+        conn.execute(
+            "UPDATE headings SET title = COALESCE(?1, title), sort_order = COALESCE(?2, sort_order) WHERE id = ?3",
+            params![input.title.as_deref().map(str::trim), input.sort_order, id],
+        )
+        .map_err(AppError::from)
+    })?;
+
+    conn.query_row("SELECT * FROM headings WHERE id = ?1", params![id], row_to_heading)
+        .map_err(AppError::from)
+}
+
+/// Delete a heading for good. Its tasks stay in the project, under no heading.
+pub fn delete_heading(conn: &Connection, id: &str) -> Result<(), AppError> {
+    let mut stmt = conn.prepare("SELECT id FROM tasks WHERE heading_id = ?1")?;
+    let task_ids: Vec<String> = stmt
+        .query_map(params![id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    // Clear the tasks first rather than leaving it to ON DELETE SET NULL, so the
+    // change log (and undo) sees every row that changed
+    change_log::in_transaction(conn, || {
+        for task_id in &task_ids {
+            change_log::tracked(conn, TASK, task_id, "update", || {
+                conn.execute("UPDATE tasks SET heading_id = NULL WHERE id = ?1", params![task_id])
+                    .map_err(AppError::from)
+            })?;
+        }
+        change_log::tracked(conn, HEADING, id, "delete", || {
+            conn.execute("DELETE FROM headings WHERE id = ?1", params![id])
+                .map_err(AppError::from)
+        })?;
+        Ok(())
+    })
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+    use crate::services::{task, trash};
+
+    fn new_project(conn: &Connection, title: &str) -> Project {
+        create_project(conn, CreateProjectInput { title: title.to_string(), notes: None, category_id: None, deadline: None }).unwrap()
+    }
+
+    fn new_task(conn: &Connection, title: &str, project_id: Option<&str>, heading_id: Option<&str>) -> Result<Task, AppError> {
+        task::create_task(conn, CreateTaskInput {
+            title: title.to_string(),
+            project_id: project_id.map(String::from),
+            heading_id: heading_id.map(String::from),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_task_placement_follows_headings() {
+        let conn = open_test_connection();
+        let (home, work) = (new_project(&conn, "Home"), new_project(&conn, "Work"));
+        let paint = create_heading(&conn, &home.id, "Paint").unwrap();
+
+        // A heading brings its project along, and must match one given with it
+        let t = new_task(&conn, "Buy rollers", None, Some(&paint.id)).unwrap();
+        assert_eq!(t.project_id.as_deref(), Some(home.id.as_str()));
+        let err = new_task(&conn, "Wrong", Some(&work.id), Some(&paint.id)).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "headingId");
+        assert_eq!(new_task(&conn, "Gone", Some("missing"), None).unwrap_err().code, ErrorCode::NotFound);
+
+        // Moving to another project drops the heading
+        let moved = task::update_task(&conn, &t.id, UpdateTaskInput {
+            project_id: Some(Some(work.id.clone())),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!((moved.project_id.as_deref(), moved.heading_id.as_deref()), (Some(work.id.as_str()), None));
+
+        let back = task::update_task(&conn, &t.id, UpdateTaskInput {
+            heading_id: Some(Some(paint.id.clone())),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(back.project_id.as_deref(), Some(home.id.as_str()));
+
+        delete_heading(&conn, &paint.id).unwrap();
+        let loaded = task::get_task_by_id(&conn, &t.id).unwrap().unwrap();
+        assert_eq!((loaded.project_id.as_deref(), loaded.heading_id), (Some(home.id.as_str()), None));
+        assert!(get_project_by_id(&conn, &home.id).unwrap().unwrap().headings.is_empty());
+    }
+
+    #[test]
+    fn test_progress_and_completion() {
+        let conn = open_test_connection();
+        let p = new_project(&conn, "Launch");
+        let a = new_task(&conn, "A", Some(&p.id), None).unwrap();
+        new_task(&conn, "B", Some(&p.id), None).unwrap();
+        new_task(&conn, "C", Some(&p.id), None).unwrap();
+        task::complete_task(&conn, &a.id).unwrap();
+
+        let progress = get_project_by_id(&conn, &p.id).unwrap().unwrap().progress;
+        assert_eq!((progress.total_tasks, progress.completed_tasks), (3, 1));
+
+        let err = complete_project(&conn, &p.id, Some("archive")).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "remainingTasks");
+
+        // Canceled tasks drop out of the count
+        let done = complete_project(&conn, &p.id, Some("cancel")).unwrap();
+        assert_eq!(done.status, ProjectStatus::Completed);
+        assert!(done.completed_at.is_some());
+        assert_eq!((done.progress.total_tasks, done.progress.completed_tasks), (1, 1));
+        assert_eq!(done.progress.ratio, 1.0);
+        let statuses: Vec<TaskStatus> = task::get_all_tasks(&conn, None).unwrap().into_iter().map(|t| t.status).collect();
+        assert_eq!(statuses.iter().filter(|s| **s == TaskStatus::Canceled).count(), 2);
+
+        let reopened = reopen_project(&conn, &p.id).unwrap();
+        assert_eq!((reopened.status, reopened.completed_at), (ProjectStatus::Active, None));
+    }
+
+    #[test]
+    fn test_trashed_project_takes_its_tasks_along() {
+        let conn = open_test_connection();
+        let p = new_project(&conn, "Move");
+        let earlier = new_task(&conn, "Trashed earlier", Some(&p.id), None).unwrap();
+        task::delete_task(&conn, &earlier.id).unwrap();
+        new_task(&conn, "Pack", Some(&p.id), None).unwrap();
+
+        delete_project(&conn, &p.id).unwrap();
+        assert!(get_projects(&conn).unwrap().is_empty());
+        assert!(task::get_all_tasks(&conn, None).unwrap().is_empty());
+        // Only the project stands for the tasks trashed with it
+        let trash = trash::get_trash(&conn).unwrap();
+        let mut listed: Vec<&str> = trash.iter().map(|i| i.title.as_str()).collect();
+        listed.sort();
+        assert_eq!(listed, vec!["Move", "Trashed earlier"]);
+
+        trash::restore_from_trash(&conn, PROJECT, &p.id).unwrap();
+        let titles: Vec<String> = task::get_all_tasks(&conn, None).unwrap().into_iter().map(|t| t.title).collect();
+        assert_eq!(titles, vec!["Pack"]);
+    }
+}
@@ -15,21 +15,30 @@ fn row_to_task(row: &rusqlite::Row) -> rusqlite::Result<Task> {
         status: row.get("status")?,
         priority: row.get("priority")?,
         category_id: row.get("category_id")?,
+        project_id: row.get("project_id")?,
+        heading_id: row.get("heading_id")?,
+        start_date: row.get("start_date")?,
         due_date: row.get("due_date")?,
         reminder_time: row.get("reminder_time")?,
         recurrence_rule,
+        someday: row.get::<_, i64>("someday")? != 0,
         completed_at: row.get("completed_at")?,
+        archived_at: row.get("archived_at")?,
         sort_order: row.get("sort_order")?,
         created_at: row.get("created_at")?,
         updated_at: row.get("updated_at")?,
         sub_tasks: None,
         tags: None,
         category: None,
+        blocked_by: None,
+        blocking: None,
+        is_blocked: None,
+        time_spent_seconds: None,
     })
 }
 
 pub fn search_tasks(conn: &Connection, query: Option<String>, filters: Option<TaskFilter>) -> Result<Vec<Task>, AppError> {
-    let mut conditions: Vec<String> = Vec::new();
+    let mut conditions: Vec<String> = vec!["deleted_at IS NULL".to_string()];
     let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
 
     // Helper: next 1-based parameter index
@@ -49,14 +58,19 @@ pub fn search_tasks(conn: &Connection, query: Option<String>, filters: Option<Ta
 
     if let Some(ref f) = filters {
         if let Some(ref status) = f.status {
+            let status = TaskStatus::parse("status", status)?;
             conditions.push(format!("status = ?{}", next_idx(&values)));
-            values.push(Box::new(status.clone()));
+            values.push(Box::new(status));
         }
 
         if let Some(ref cat_opt) = f.category_id {
             match cat_opt {
                 None => {
-                    conditions.push("category_id IS NULL".to_string());
+                    // Tasks whose category is in the trash count as uncategorized
+                    conditions.push(
+                        "(category_id IS NULL OR category_id IN (SELECT id FROM categories WHERE deleted_at IS NOT NULL))"
+                            .to_string(),
+                    );
                 }
                 Some(cat_id) => {
                     conditions.push(format!("category_id = ?{}", next_idx(&values)));
@@ -66,8 +80,9 @@ pub fn search_tasks(conn: &Connection, query: Option<String>, filters: Option<Ta
         }
 
         if let Some(ref priority) = f.priority {
+            let priority = Priority::parse("priority", priority)?;
             conditions.push(format!("priority = ?{}", next_idx(&values)));
-            values.push(Box::new(priority.clone()));
+            values.push(Box::new(priority));
         }
 
         if let Some(ref from) = f.due_date_from {
@@ -81,11 +96,7 @@ pub fn search_tasks(conn: &Connection, query: Option<String>, filters: Option<Ta
         }
     }
 
-    let where_clause = if conditions.is_empty() {
-        String::new()
-    } else {
-        format!("WHERE {}", conditions.join(" AND "))
-    };
+    let where_clause = format!("WHERE {}", conditions.join(" AND "));
 
     // Sort
     let sort_by = filters
@@ -100,10 +111,11 @@ pub fn search_tasks(conn: &Connection, query: Option<String>, filters: Option<Ta
     let order_clause = match sort_by.as_str() {
         "priority" => {
             let dir = if sort_order == "asc" { "ASC" } else { "DESC" };
-            format!(
-                "ORDER BY CASE priority WHEN 'high' THEN 0 WHEN 'medium' THEN 1 WHEN 'low' THEN 2 WHEN 'none' THEN 3 END {}",
-                dir
-            )
+            let ranks: Vec<String> = Priority::ALL
+                .iter()
+                .map(|p| format!("WHEN '{}' THEN {}", p.as_str(), p.rank()))
+                .collect();
+            format!("ORDER BY CASE priority {} END {}", ranks.join(" "), dir)
         }
         "dueDate" => {
             let dir = if sort_order == "asc" { "ASC" } else { "DESC" };
@@ -116,16 +128,13 @@ pub fn search_tasks(conn: &Connection, query: Option<String>, filters: Option<Ta
     };
 
     let sql = format!("SELECT * FROM tasks {} {}", where_clause, order_clause);
-    let mut stmt = conn.prepare(&sql)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn.prepare(&sql)?;
 
     let params_refs: Vec<&dyn rusqlite::types::ToSql> = values.iter().map(|v| v.as_ref()).collect();
 
     let mut results: Vec<Task> = stmt
-        .query_map(params_refs.as_slice(), row_to_task)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        .query_map(params_refs.as_slice(), row_to_task)?
+        .collect::<Result<_, _>>()?;
 
     // Tag filter (post-query)
     if let Some(ref f) = filters {
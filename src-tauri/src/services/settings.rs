@@ -0,0 +1,166 @@
+use rusqlite::{Connection, params};
+use std::collections::HashMap;
+use crate::models::*;
+
+const BACKUP_KEEP: &str = "backup_keep";
+const BACKUP_INTERVAL_HOURS: &str = "backup_interval_hours";
+const TRASH_RETENTION_DAYS: &str = "trash_retention_days";
+const LOGBOOK_ARCHIVE_DAYS: &str = "logbook_archive_days";
+const POMODORO_WORK_MINUTES: &str = "pomodoro_work_minutes";
+const POMODORO_SHORT_BREAK_MINUTES: &str = "pomodoro_short_break_minutes";
+const POMODORO_LONG_BREAK_MINUTES: &str = "pomodoro_long_break_minutes";
+const POMODORO_LONG_BREAK_EVERY: &str = "pomodoro_long_break_every";
+
+/// Longest Pomodoro phase, in minutes.
+const MAX_POMODORO_MINUTES: i64 = 720;
+/// Longest the trash keeps items, in days.
+const MAX_TRASH_RETENTION_DAYS: i64 = 36_500;
+/// Longest gap between automatic backups, in hours.
+const MAX_BACKUP_INTERVAL_HOURS: i64 = 8_760;
+/// Longest closed tasks wait before moving to the logbook, in days.
+const MAX_LOGBOOK_ARCHIVE_DAYS: i64 = 36_500;
+
+const DEFAULT_BACKUP_KEEP: i64 = 10;
+const DEFAULT_BACKUP_INTERVAL_HOURS: i64 = 24;
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+const DEFAULT_LOGBOOK_ARCHIVE_DAYS: i64 = 7;
+const DEFAULT_POMODORO_WORK_MINUTES: i64 = 25;
+const DEFAULT_POMODORO_SHORT_BREAK_MINUTES: i64 = 5;
+const DEFAULT_POMODORO_LONG_BREAK_MINUTES: i64 = 15;
+const DEFAULT_POMODORO_LONG_BREAK_EVERY: i64 = 4;
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            backup_keep: DEFAULT_BACKUP_KEEP,
+            backup_interval_hours: DEFAULT_BACKUP_INTERVAL_HOURS,
+            trash_retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+            logbook_archive_days: DEFAULT_LOGBOOK_ARCHIVE_DAYS,
+            pomodoro_work_minutes: DEFAULT_POMODORO_WORK_MINUTES,
+            pomodoro_short_break_minutes: DEFAULT_POMODORO_SHORT_BREAK_MINUTES,
+            pomodoro_long_break_minutes: DEFAULT_POMODORO_LONG_BREAK_MINUTES,
+            pomodoro_long_break_every: DEFAULT_POMODORO_LONG_BREAK_EVERY,
+        }
+    }
+}
+
+pub fn get_settings(conn: &Connection) -> Result<AppSettings, AppError> {
+    let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+    let stored: HashMap<String, String> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let int = |key: &str, default: i64| stored.get(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+
+    Ok(AppSettings {
+        backup_keep: int(BACKUP_KEEP, DEFAULT_BACKUP_KEEP),
+        backup_interval_hours: int(BACKUP_INTERVAL_HOURS, DEFAULT_BACKUP_INTERVAL_HOURS),
+        trash_retention_days: int(TRASH_RETENTION_DAYS, DEFAULT_TRASH_RETENTION_DAYS),
+        logbook_archive_days: int(LOGBOOK_ARCHIVE_DAYS, DEFAULT_LOGBOOK_ARCHIVE_DAYS),
+        pomodoro_work_minutes: int(POMODORO_WORK_MINUTES, DEFAULT_POMODORO_WORK_MINUTES),
+        pomodoro_short_break_minutes: int(POMODORO_SHORT_BREAK_MINUTES, DEFAULT_POMODORO_SHORT_BREAK_MINUTES),
+        pomodoro_long_break_minutes: int(POMODORO_LONG_BREAK_MINUTES, DEFAULT_POMODORO_LONG_BREAK_MINUTES),
+        pomodoro_long_break_every: int(POMODORO_LONG_BREAK_EVERY, DEFAULT_POMODORO_LONG_BREAK_EVERY),
+    })
+}
+
+pub fn update_settings(conn: &Connection, input: UpdateSettingsInput) -> Result<AppSettings, AppError> {
+    let mut updates: Vec<(&str, String)> = Vec::new();
+
+    if let Some(keep) = input.backup_keep {
+        validate_positive("backupKeep", keep)?;
+        updates.push((BACKUP_KEEP, keep.to_string()));
+    }
+    if let Some(hours) = input.backup_interval_hours {
+        validate_range("backupIntervalHours", hours, MAX_BACKUP_INTERVAL_HOURS)?;
+        updates.push((BACKUP_INTERVAL_HOURS, hours.to_string()));
+    }
+    if let Some(days) = input.trash_retention_days {
+        validate_range("trashRetentionDays", days, MAX_TRASH_RETENTION_DAYS)?;
+        updates.push((TRASH_RETENTION_DAYS, days.to_string()));
+    }
+    if let Some(days) = input.logbook_archive_days {
+        validate_range("logbookArchiveDays", days, MAX_LOGBOOK_ARCHIVE_DAYS)?;
+        updates.push((LOGBOOK_ARCHIVE_DAYS, days.to_string()));
+    }
+    if let Some(minutes) = input.pomodoro_work_minutes {
+        validate_range("pomodoroWorkMinutes", minutes, MAX_POMODORO_MINUTES)?;
+        updates.push((POMODORO_WORK_MINUTES, minutes.to_string()));
+    }
+    if let Some(minutes) = input.pomodoro_short_break_minutes {
+        validate_range("pomodoroShortBreakMinutes", minutes, MAX_POMODORO_MINUTES)?;
+        updates.push((POMODORO_SHORT_BREAK_MINUTES, minutes.to_string()));
+    }
+    if let Some(minutes) = input.pomodoro_long_break_minutes {
+        validate_range("pomodoroLongBreakMinutes", minutes, MAX_POMODORO_MINUTES)?;
+        updates.push((POMODORO_LONG_BREAK_MINUTES, minutes.to_string()));
+    }
+    if let Some(every) = input.pomodoro_long_break_every {
+        validate_positive("pomodoroLongBreakEvery", every)?;
+        updates.push((POMODORO_LONG_BREAK_EVERY, every.to_string()));
+    }
+
+    for (key, value) in &updates {
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+    }
+
+    get_settings(conn)
+}
+
+fn validate_range(field: &str, value: i64, max: i64) -> Result<(), AppError> {
+    if !(1..=max).contains(&value) {
+        return Err(AppError::validation(format!("{} must be between 1 and {}", field, max))
+            .with_detail("field", field)
+            .with_detail("value", value.to_string()));
+    }
+    Ok(())
+}
+
+fn validate_positive(field: &str, value: i64) -> Result<(), AppError> {
+    if value < 1 {
+        return Err(AppError::validation(format!("{} must be at least 1", field))
+            .with_detail("field", field)
+            .with_detail("value", value.to_string()));
+    }
+    Ok(())
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+
+    fn input(value: serde_json::Value) -> UpdateSettingsInput {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_pomodoro_lengths_are_bounded() {
+        let conn = open_test_connection();
+        let err = update_settings(&conn, input(serde_json::json!({ "pomodoroWorkMinutes": i64::MAX }))).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "pomodoroWorkMinutes");
+        assert!(update_settings(&conn, input(serde_json::json!({ "pomodoroLongBreakMinutes": 0 }))).is_err());
+
+        let settings = update_settings(&conn, input(serde_json::json!({ "pomodoroShortBreakMinutes": 720 }))).unwrap();
+        assert_eq!((settings.pomodoro_work_minutes, settings.pomodoro_short_break_minutes), (25, 720));
+    }
+
+    #[test]
+    fn test_retention_periods_are_bounded() {
+        let conn = open_test_connection();
+        let err = update_settings(&conn, input(serde_json::json!({ "trashRetentionDays": i64::MAX }))).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "trashRetentionDays");
+        let err = update_settings(&conn, input(serde_json::json!({ "backupIntervalHours": i64::MAX }))).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "backupIntervalHours");
+        let err = update_settings(&conn, input(serde_json::json!({ "logbookArchiveDays": i64::MAX }))).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "logbookArchiveDays");
+    }
+}
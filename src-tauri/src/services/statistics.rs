@@ -1,6 +1,9 @@
 use rusqlite::{Connection, params};
 use chrono::{NaiveDate, Datelike, Duration};
+use std::collections::HashMap;
 use crate::models::*;
+use crate::services::time_entry::seconds_between;
+use crate::utils::validate;
 
 pub fn get_stats(conn: &Connection, period: &str) -> Result<StatsSummary, AppError> {
     let now = chrono::Utc::now().naive_utc();
@@ -36,11 +39,7 @@ pub fn get_stats(conn: &Connection, period: &str) -> Result<StatsSummary, AppErr
             )
         }
         _ => {
-            return Err(AppError {
-                code: "VALIDATION_ERROR".into(),
-                message: "Invalid period. Use 'day', 'week', or 'month'".into(),
-                details: None,
-            });
+            return Err(AppError::validation("Invalid period. Use 'day', 'week', or 'month'"));
         }
     };
 
@@ -49,7 +48,8 @@ pub fn get_stats(conn: &Connection, period: &str) -> Result<StatsSummary, AppErr
 
     let completed_tasks: i64 = conn
         .query_row(
-            "SELECT COUNT(*) FROM tasks WHERE status = 'completed' AND completed_at >= ?1 AND completed_at <= ?2",
+            "SELECT COUNT(*) FROM tasks
+             WHERE status = 'completed' AND completed_at >= ?1 AND completed_at <= ?2 AND deleted_at IS NULL",
             params![start_str, end_str],
             |row| row.get(0),
         )
@@ -57,7 +57,7 @@ pub fn get_stats(conn: &Connection, period: &str) -> Result<StatsSummary, AppErr
 
     let total_tasks: i64 = conn
         .query_row(
-            "SELECT COUNT(*) FROM tasks WHERE created_at <= ?1",
+            "SELECT COUNT(*) FROM tasks WHERE created_at <= ?1 AND deleted_at IS NULL",
             params![end_str],
             |row| row.get(0),
         )
@@ -89,31 +89,25 @@ pub fn get_daily_trend(conn: &Connection, days: i64) -> Result<Vec<DailyTrend>,
     let mut stmt = conn
         .prepare(
             "SELECT DATE(completed_at) as d, COUNT(*) as c FROM tasks
-             WHERE status = 'completed' AND completed_at >= ?1 AND completed_at <= ?2
+             WHERE status = 'completed' AND completed_at >= ?1 AND completed_at <= ?2 AND deleted_at IS NULL
              GROUP BY DATE(completed_at)"
-        )
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+        )?;
 
     let completed_rows: Vec<(String, i64)> = stmt
-        .query_map(params![start_str, end_str], |row| Ok((row.get(0)?, row.get(1)?)))
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        .query_map(params![start_str, end_str], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
 
     // Created by date
     let mut stmt = conn
         .prepare(
             "SELECT DATE(created_at) as d, COUNT(*) as c FROM tasks
-             WHERE created_at >= ?1 AND created_at <= ?2
+             WHERE created_at >= ?1 AND created_at <= ?2 AND deleted_at IS NULL
              GROUP BY DATE(created_at)"
-        )
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+        )?;
 
     let created_rows: Vec<(String, i64)> = stmt
-        .query_map(params![start_str, end_str], |row| Ok((row.get(0)?, row.get(1)?)))
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        .query_map(params![start_str, end_str], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
 
     let completed_map: std::collections::HashMap<String, i64> = completed_rows.into_iter().collect();
     let created_map: std::collections::HashMap<String, i64> = created_rows.into_iter().collect();
@@ -131,3 +125,234 @@ pub fn get_daily_trend(conn: &Connection, days: i64) -> Result<Vec<DailyTrend>,
 
     Ok(trends)
 }
+
+/// Finished Pomodoro work sessions per day over the last `days` days, like
+/// `get_daily_trend`.
+pub fn get_focus_trend(conn: &Connection, days: i64) -> Result<Vec<FocusTrend>, AppError> {
+    let today = chrono::Utc::now().naive_utc().date();
+    let start_str = (today - Duration::days(days - 1)).and_hms_opt(0, 0, 0).unwrap().format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    let mut stmt = conn.prepare(
+        "SELECT DATE(started_at), COUNT(*), SUM(duration_seconds) FROM focus_sessions
+         WHERE completed = 1 AND started_at >= ?1
+         GROUP BY DATE(started_at)",
+    )?;
+    let by_date: HashMap<String, (i64, i64)> = stmt
+        .query_map(params![start_str], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+        .collect::<Result<_, _>>()?;
+
+    Ok((0..days)
+        .rev()
+        .map(|i| {
+            let date = (today - Duration::days(i)).format("%Y-%m-%d").to_string();
+            let (pomodoros, focus_seconds) = by_date.get(&date).copied().unwrap_or((0, 0));
+            FocusTrend { date, pomodoros, focus_seconds }
+        })
+        .collect())
+}
+
+/// Finished Pomodoro work sessions per task category over the last `days` days,
+/// most first.
+pub fn get_focus_by_category(conn: &Connection, days: i64) -> Result<Vec<FocusByCategory>, AppError> {
+    let today = chrono::Utc::now().naive_utc().date();
+    let start_str = (today - Duration::days(days - 1)).and_hms_opt(0, 0, 0).unwrap().format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    let mut stmt = conn.prepare(
+        "SELECT c.id, c.name, COUNT(*), SUM(f.duration_seconds) FROM focus_sessions f
+         LEFT JOIN tasks t ON t.id = f.task_id
+         LEFT JOIN categories c ON c.id = t.category_id AND c.deleted_at IS NULL
+         WHERE f.completed = 1 AND f.started_at >= ?1
+         GROUP BY c.id
+         ORDER BY COUNT(*) DESC, c.name",
+    )?;
+    let rows: Vec<FocusByCategory> = stmt
+        .query_map(params![start_str], |row| {
+            Ok(FocusByCategory {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                pomodoros: row.get(2)?,
+                focus_seconds: row.get(3)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
+/// Time logged in entries started between `from` and `to` (inclusive `%Y-%m-%d`
+/// dates). An entry counts toward the local date it started on, in full, and a
+/// running timer counts up to now. Entries of trashed tasks are left out.
+pub fn get_time_stats(conn: &Connection, from: &str, to: &str) -> Result<TimeStats, AppError> {
+    validate::date("from", from)?;
+    validate::date("to", to)?;
+    let first = NaiveDate::parse_from_str(from, validate::DATE_FORMAT).unwrap();
+    let last = NaiveDate::parse_from_str(to, validate::DATE_FORMAT).unwrap();
+    if last < first {
+        return Err(AppError::validation("to must not be before from")
+            .with_detail("field", "to")
+            .with_detail("value", to));
+    }
+
+    // Timestamps carry their own offset, so widen the text range by a day on each
+    // side and settle the local date per entry
+    let lower = (first - Duration::days(1)).format(validate::DATE_FORMAT).to_string();
+    let upper = (last + Duration::days(2)).format(validate::DATE_FORMAT).to_string();
+    let mut stmt = conn.prepare(
+        "SELECT e.started_at, e.ended_at, t.id, c.id, c.name FROM time_entries e
+         INNER JOIN tasks t ON t.id = e.task_id AND t.deleted_at IS NULL
+         LEFT JOIN categories c ON c.id = t.category_id AND c.deleted_at IS NULL
+         WHERE e.started_at >= ?1 AND e.started_at < ?2",
+    )?;
+    type EntryRow = (String, Option<String>, String, Option<String>, Option<String>);
+    let entries: Vec<EntryRow> = stmt
+        .query_map(params![lower, upper], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT tt.task_id, g.id, g.name FROM task_tags tt
+         INNER JOIN tags g ON g.id = tt.tag_id AND g.deleted_at IS NULL",
+    )?;
+    let mut tags_by_task: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?)))? {
+        let (task_id, tag_id, name) = row?;
+        tags_by_task.entry(task_id).or_default().push((tag_id, name));
+    }
+
+    let mut total_seconds = 0;
+    let mut by_day: HashMap<NaiveDate, i64> = HashMap::new();
+    let mut by_category: HashMap<Option<String>, (Option<String>, i64)> = HashMap::new();
+    let mut by_tag: HashMap<Option<String>, (Option<String>, i64)> = HashMap::new();
+    for (started_at, ended_at, task_id, category_id, category_name) in entries {
+        let Ok(start) = chrono::DateTime::parse_from_rfc3339(&started_at) else { continue };
+        let day = start.with_timezone(&chrono::Local).date_naive();
+        if day < first || day > last {
+            continue;
+        }
+        let seconds = seconds_between(&started_at, ended_at.as_deref());
+        total_seconds += seconds;
+        *by_day.entry(day).or_default() += seconds;
+        by_category.entry(category_id).or_insert((category_name, 0)).1 += seconds;
+        match tags_by_task.get(&task_id) {
+            Some(tags) => {
+                for (tag_id, name) in tags {
+                    by_tag.entry(Some(tag_id.clone())).or_insert((Some(name.clone()), 0)).1 += seconds;
+                }
+            }
+            None => by_tag.entry(None).or_insert((None, 0)).1 += seconds,
+        }
+    }
+
+    let days = (last - first).num_days() + 1;
+    let by_day = (0..days)
+        .map(|i| {
+            let date = first + Duration::days(i);
+            TimeByDay {
+                date: date.format(validate::DATE_FORMAT).to_string(),
+                seconds: by_day.get(&date).copied().unwrap_or(0),
+            }
+        })
+        .collect();
+
+    Ok(TimeStats {
+        from: from.to_string(),
+        to: to.to_string(),
+        total_seconds,
+        by_day,
+        by_category: into_groups(by_category),
+        by_tag: into_groups(by_tag),
+    })
+}
+
+/// Most time first.
+fn into_groups(groups: HashMap<Option<String>, (Option<String>, i64)>) -> Vec<TimeByGroup> {
+    let mut groups: Vec<TimeByGroup> = groups
+        .into_iter()
+        .map(|(id, (name, seconds))| TimeByGroup { id, name, seconds })
+        .collect();
+    groups.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.name.cmp(&b.name)));
+    groups
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+    use crate::services::{category, focus, tag, task, time_entry};
+
+    fn new_task(conn: &Connection, title: &str, category_id: Option<&str>) -> Task {
+        task::create_task(conn, CreateTaskInput {
+            title: title.to_string(),
+            category_id: category_id.map(String::from),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn log(conn: &Connection, task_id: &str, day: &str, minutes: i64) {
+        let start = chrono::NaiveDateTime::parse_from_str(&format!("{} 12:00", day), "%Y-%m-%d %H:%M")
+            .unwrap()
+            .and_local_timezone(chrono::Local)
+            .unwrap();
+        time_entry::create_time_entry(conn, CreateTimeEntryInput {
+            task_id: task_id.to_string(),
+            started_at: start.to_rfc3339(),
+            ended_at: (start + Duration::minutes(minutes)).to_rfc3339(),
+            note: None,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_time_stats_by_day_category_and_tag() {
+        let conn = open_test_connection();
+        let client = category::create_category(&conn, CreateCategoryInput { name: "Client".into(), color: None }).unwrap();
+        let billable = tag::create_tag(&conn, "billable", None).unwrap();
+        let a = new_task(&conn, "Design", Some(&client.id));
+        let b = new_task(&conn, "Email", None);
+        tag::add_tag_to_task(&conn, &a.id, &billable.id).unwrap();
+
+        log(&conn, &a.id, "2024-05-01", 60);
+        log(&conn, &a.id, "2024-05-03", 30);
+        log(&conn, &b.id, "2024-05-03", 15);
+        log(&conn, &b.id, "2024-05-04", 45); // outside the range
+
+        let stats = get_time_stats(&conn, "2024-05-01", "2024-05-03").unwrap();
+        assert_eq!(stats.total_seconds, 105 * 60);
+        let days: Vec<i64> = stats.by_day.iter().map(|d| d.seconds / 60).collect();
+        assert_eq!(days, vec![60, 0, 45]);
+        assert_eq!(stats.by_category[0].id.as_deref(), Some(client.id.as_str()));
+        assert_eq!((stats.by_category[0].seconds, stats.by_category[1].seconds), (90 * 60, 15 * 60));
+        assert_eq!(stats.by_tag[0].name.as_deref(), Some("billable"));
+        assert_eq!((stats.by_tag[1].id.as_deref(), stats.by_tag[1].seconds), (None, 15 * 60));
+
+        let err = get_time_stats(&conn, "2024-05-03", "2024-05-01").unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "to");
+    }
+
+    #[test]
+    fn test_focus_trend_and_categories_count_finished_sessions() {
+        let conn = open_test_connection();
+        let client = category::create_category(&conn, CreateCategoryInput { name: "Client".into(), color: None }).unwrap();
+        let a = new_task(&conn, "Design", Some(&client.id));
+        let now = chrono::Utc::now();
+        let at = |minutes_ago: i64| (now - Duration::minutes(minutes_ago)).to_rfc3339();
+
+        focus::record_session(&conn, Some(&a.id), &at(60), &at(35), true).unwrap();
+        focus::record_session(&conn, Some(&a.id), &at(30), &at(5), true).unwrap();
+        focus::record_session(&conn, None, &at(4), &at(2), false).unwrap();
+        focus::record_session(&conn, None, &at(120), &at(95), true).unwrap();
+
+        let trend = get_focus_trend(&conn, 7).unwrap();
+        assert_eq!(trend.len(), 7);
+        assert_eq!(trend.iter().map(|d| d.pomodoros).sum::<i64>(), 3);
+        assert_eq!(trend.iter().map(|d| d.focus_seconds).sum::<i64>(), 75 * 60);
+
+        let by_category = get_focus_by_category(&conn, 7).unwrap();
+        assert_eq!(by_category.len(), 2);
+        assert_eq!((by_category[0].name.as_deref(), by_category[0].pomodoros), (Some("Client"), 2));
+        assert_eq!((by_category[1].id.as_deref(), by_category[1].pomodoros), (None, 1));
+    }
+}
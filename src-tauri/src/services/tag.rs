@@ -1,6 +1,8 @@
 use rusqlite::{Connection, params, OptionalExtension};
 use uuid::Uuid;
 use crate::models::*;
+use crate::services::change_log::{self, TAG, TASK_TAG};
+use crate::utils::validate;
 
 fn row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
     Ok(Tag {
@@ -13,25 +15,24 @@ fn row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
 
 pub fn create_tag(conn: &Connection, name: &str, color: Option<String>) -> Result<Tag, AppError> {
     if name.trim().is_empty() {
-        return Err(AppError { code: "VALIDATION_ERROR".into(), message: "Tag name must not be empty".into(), details: None });
+        return Err(AppError::validation("Tag name must not be empty"));
     }
-
-    let dup: Option<String> = conn
-        .query_row("SELECT id FROM tags WHERE name = ?1", params![name.trim()], |row| row.get(0))
-        .optional()
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-    if dup.is_some() {
-        return Err(AppError { code: "VALIDATION_ERROR".into(), message: "Tag name already exists".into(), details: None });
+    if let Some(ref color) = color {
+        validate::color("color", color)?;
     }
 
+    check_duplicate_name(conn, name.trim(), None)?;
+
     let id = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
-    conn.execute(
-        "INSERT INTO tags (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
-        params![id, name.trim(), color, now],
-    )
-    .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    change_log::tracked(conn, TAG, &id, "create", || {
+        conn.execute(
+            "INSERT INTO tags (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![id, name.trim(), color, now],
+        )
+        .map_err(AppError::from)
+    })?;
 
     Ok(Tag {
         id,
@@ -43,52 +44,69 @@ pub fn create_tag(conn: &Connection, name: &str, color: Option<String>) -> Resul
 
 pub fn update_tag(conn: &Connection, id: &str, name: &str, color: Option<String>) -> Result<Tag, AppError> {
     let exists: bool = conn
-        .query_row("SELECT COUNT(*) FROM tags WHERE id = ?1", params![id], |row| row.get::<_, i64>(0))
+        .query_row("SELECT COUNT(*) FROM tags WHERE id = ?1 AND deleted_at IS NULL", params![id], |row| row.get::<_, i64>(0))
         .unwrap_or(0) > 0;
     if !exists {
-        return Err(AppError { code: "NOT_FOUND".into(), message: "Tag not found".into(), details: None });
+        return Err(AppError::not_found("Tag not found"));
     }
 
     if name.trim().is_empty() {
-        return Err(AppError { code: "VALIDATION_ERROR".into(), message: "Tag name must not be empty".into(), details: None });
+        return Err(AppError::validation("Tag name must not be empty"));
     }
-
-    let dup: Option<String> = conn
-        .query_row("SELECT id FROM tags WHERE name = ?1 AND id != ?2", params![name.trim(), id], |row| row.get(0))
-        .optional()
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-    if dup.is_some() {
-        return Err(AppError { code: "VALIDATION_ERROR".into(), message: "Tag name already exists".into(), details: None });
+    if let Some(ref color) = color {
+        validate::color("color", color)?;
     }
 
-    conn.execute(
-        "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
-        params![name.trim(), color, id],
-    )
-    .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    check_duplicate_name(conn, name.trim(), Some(id))?;
+
+    change_log::tracked(conn, TAG, id, "update", || {
+        conn.execute(
+            "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
+            params![name.trim(), color, id],
+        )
+        .map_err(AppError::from)
+    })?;
 
     conn.query_row("SELECT * FROM tags WHERE id = ?1", params![id], row_to_tag)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })
+        .map_err(AppError::from)
 }
 
 pub fn get_all_tags(conn: &Connection) -> Result<Vec<Tag>, AppError> {
-    let mut stmt = conn
-        .prepare("SELECT * FROM tags ORDER BY name")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn.prepare("SELECT * FROM tags WHERE deleted_at IS NULL ORDER BY name")?;
     let tags: Vec<Tag> = stmt
-        .query_map([], row_to_tag)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        .query_map([], row_to_tag)?
+        .collect::<Result<_, _>>()?;
     Ok(tags)
 }
 
+/// Move a tag to the trash. Task links are kept (and hidden) until the tag is purged.
 pub fn delete_tag(conn: &Connection, id: &str) -> Result<(), AppError> {
-    conn.execute("DELETE FROM tags WHERE id = ?1", params![id])
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let now = chrono::Utc::now().to_rfc3339();
+    change_log::tracked(conn, TAG, id, "delete", || {
+        conn.execute(
+            "UPDATE tags SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(AppError::from)
+    })?;
     Ok(())
 }
 
+fn check_duplicate_name(conn: &Connection, name: &str, exclude_id: Option<&str>) -> Result<(), AppError> {
+    let dup: Option<bool> = conn
+        .query_row(
+            "SELECT deleted_at IS NOT NULL FROM tags WHERE name = ?1 AND id != COALESCE(?2, '')",
+            params![name, exclude_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match dup {
+        Some(true) => Err(AppError::validation("Tag name already exists in the trash")),
+        Some(false) => Err(AppError::validation("Tag name already exists")),
+        None => Ok(()),
+    }
+}
+
 pub fn add_tag_to_task(conn: &Connection, task_id: &str, tag_id: &str) -> Result<(), AppError> {
     let exists: Option<String> = conn
         .query_row(
@@ -96,28 +114,38 @@ pub fn add_tag_to_task(conn: &Connection, task_id: &str, tag_id: &str) -> Result
             params![task_id, tag_id],
             |row| row.get(0),
         )
-        .optional()
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+        .optional()?;
 
     if exists.is_some() {
         return Ok(()); // idempotent
     }
 
-    conn.execute(
-        "INSERT INTO task_tags (task_id, tag_id) VALUES (?1, ?2)",
-        params![task_id, tag_id],
-    )
-    .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-    Ok(())
+    change_log::in_transaction(conn, || {
+        conn.execute(
+            "INSERT INTO task_tags (task_id, tag_id) VALUES (?1, ?2)",
+            params![task_id, tag_id],
+        )?;
+        change_log::record(conn, TASK_TAG, task_id, "create", None, Some(&task_tag_json(task_id, tag_id)))
+    })
 }
 
 pub fn remove_tag_from_task(conn: &Connection, task_id: &str, tag_id: &str) -> Result<(), AppError> {
-    conn.execute(
-        "DELETE FROM task_tags WHERE task_id = ?1 AND tag_id = ?2",
-        params![task_id, tag_id],
-    )
-    .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-    Ok(())
+    change_log::in_transaction(conn, || {
+        let removed = conn
+            .execute(
+                "DELETE FROM task_tags WHERE task_id = ?1 AND tag_id = ?2",
+                params![task_id, tag_id],
+            )?;
+        if removed > 0 {
+            change_log::record(conn, TASK_TAG, task_id, "delete", Some(&task_tag_json(task_id, tag_id)), None)?;
+        }
+        Ok(())
+    })
+}
+
+/// A `task_tags` row in the shape `change_log` stores rows.
+fn task_tag_json(task_id: &str, tag_id: &str) -> serde_json::Value {
+    serde_json::json!({ "task_id": task_id, "tag_id": tag_id })
 }
 
 /// Find task IDs that have ALL of the specified tags (intersection).
@@ -129,13 +157,13 @@ pub fn find_task_ids_by_tags(conn: &Connection, tag_ids: &[String]) -> Result<Ve
     let mut sets: Vec<std::collections::HashSet<String>> = Vec::new();
     for tag_id in tag_ids {
         let mut stmt = conn
-            .prepare("SELECT task_id FROM task_tags WHERE tag_id = ?1")
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+            .prepare(
+                "SELECT tt.task_id FROM task_tags tt INNER JOIN tasks t ON t.id = tt.task_id
+                 WHERE tt.tag_id = ?1 AND t.deleted_at IS NULL",
+            )?;
         let ids: std::collections::HashSet<String> = stmt
-            .query_map(params![tag_id], |row| row.get(0))
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-            .filter_map(|r| r.ok())
-            .collect();
+            .query_map(params![tag_id], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
         sets.push(ids);
     }
 
@@ -1,14 +1,17 @@
 use rusqlite::{Connection, params, OptionalExtension};
 use uuid::Uuid;
 use crate::models::*;
+use crate::services::change_log::{self, SUB_TASK, TASK};
+use crate::services::{dependency, project, tag, time_entry};
 use crate::utils::recurrence::get_next_occurrence;
+use crate::utils::validate;
 use chrono::NaiveDate;
 
 // ============================================================
 // Row → Model mappers
 // ============================================================
 
-fn row_to_task(row: &rusqlite::Row) -> rusqlite::Result<Task> {
+pub(crate) fn row_to_task(row: &rusqlite::Row) -> rusqlite::Result<Task> {
     let recurrence_str: Option<String> = row.get("recurrence_rule")?;
     let recurrence_rule = recurrence_str
         .as_deref()
@@ -21,16 +24,25 @@ fn row_to_task(row: &rusqlite::Row) -> rusqlite::Result<Task> {
         status: row.get("status")?,
         priority: row.get("priority")?,
         category_id: row.get("category_id")?,
+        project_id: row.get("project_id")?,
+        heading_id: row.get("heading_id")?,
+        start_date: row.get("start_date")?,
         due_date: row.get("due_date")?,
         reminder_time: row.get("reminder_time")?,
         recurrence_rule,
+        someday: row.get::<_, i64>("someday")? != 0,
         completed_at: row.get("completed_at")?,
+        archived_at: row.get("archived_at")?,
         sort_order: row.get("sort_order")?,
         created_at: row.get("created_at")?,
         updated_at: row.get("updated_at")?,
         sub_tasks: None,
         tags: None,
         category: None,
+        blocked_by: None,
+        blocking: None,
+        is_blocked: None,
+        time_spent_seconds: None,
     })
 }
 
@@ -42,7 +54,7 @@ fn row_to_sub_task(row: &rusqlite::Row) -> rusqlite::Result<SubTask> {
         parent_id: row.get("parent_id")?,
         title: row.get("title")?,
         description: row.get("description")?,
-        priority: row.get::<_, Option<String>>("priority")?.unwrap_or_else(|| "none".to_string()),
+        priority: row.get::<_, Option<Priority>>("priority")?.unwrap_or_default(),
         due_date: row.get("due_date")?,
         completed: completed_int != 0,
         sort_order: row.get("sort_order")?,
@@ -51,41 +63,54 @@ fn row_to_sub_task(row: &rusqlite::Row) -> rusqlite::Result<SubTask> {
     })
 }
 
-/// Build a tree of sub-tasks from a flat list.
+/// Build a tree of sub-tasks from a flat list. Sub-tasks whose parent isn't in the
+/// list end up at the top level; every level is ordered by `sort_order`.
 pub fn build_sub_task_tree(flat: Vec<SubTask>) -> Vec<SubTask> {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
 
-    let mut by_id: HashMap<String, SubTask> = HashMap::new();
+    let ids: HashSet<String> = flat.iter().map(|st| st.id.clone()).collect();
+    let mut by_parent: HashMap<Option<String>, Vec<SubTask>> = HashMap::new();
     for st in flat {
-        by_id.insert(st.id.clone(), SubTask { children: Some(vec![]), ..st });
+        let parent = st.parent_id.clone().filter(|pid| ids.contains(pid));
+        by_parent.entry(parent).or_default().push(st);
     }
 
-    let mut roots: Vec<SubTask> = Vec::new();
-    let ids: Vec<String> = by_id.keys().cloned().collect();
-
-    for id in &ids {
-        let parent_id = by_id.get(id).and_then(|st| st.parent_id.clone());
-        if let Some(ref pid) = parent_id {
-            if by_id.contains_key(pid) {
-                let child = by_id.remove(id).unwrap();
-                by_id.get_mut(pid).unwrap().children.as_mut().unwrap().push(child);
-            }
+    fn attach(parent: Option<String>, by_parent: &mut HashMap<Option<String>, Vec<SubTask>>) -> Vec<SubTask> {
+        let mut level = by_parent.remove(&parent).unwrap_or_default();
+        level.sort_by_key(|st| st.sort_order);
+        for st in &mut level {
+            st.children = Some(attach(Some(st.id.clone()), by_parent));
         }
+        level
     }
 
-    for (_id, st) in by_id {
-        roots.push(st);
-    }
-    roots.sort_by_key(|s| s.sort_order);
-    roots
+    attach(None, &mut by_parent)
 }
 
+/// Live sub-tasks of the given tasks, walked down from the top level so that
+/// descendants of a trashed sub-task stay hidden with it.
+const LIVE_SUB_TASK_TREE_SQL: &str = "
+    WITH RECURSIVE tree AS (
+        SELECT * FROM sub_tasks WHERE task_id IN ({}) AND parent_id IS NULL AND deleted_at IS NULL
+        UNION ALL
+        SELECT s.* FROM sub_tasks s INNER JOIN tree ON s.parent_id = tree.id WHERE s.deleted_at IS NULL
+    )
+    SELECT * FROM tree ORDER BY sort_order";
+
 // ============================================================
 // Task Service
 // ============================================================
 
 pub fn create_task(conn: &Connection, input: CreateTaskInput) -> Result<Task, AppError> {
     validate_title(&input.title)?;
+    let priority = parse_priority(input.priority.as_deref())?.unwrap_or_default();
+    validate_schedule(
+        input.start_date.as_deref(),
+        input.due_date.as_deref(),
+        input.reminder_time.as_deref(),
+        input.recurrence_rule.as_ref(),
+    )?;
+    let (project_id, heading_id) = resolve_placement(conn, input.project_id.clone(), input.heading_id.clone())?;
 
     let id = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
@@ -101,46 +126,48 @@ pub fn create_task(conn: &Connection, input: CreateTaskInput) -> Result<Task, Ap
         .as_ref()
         .map(|r| serde_json::to_string(r).unwrap_or_default());
 
-    conn.execute(
-        "INSERT INTO tasks (id, title, description, status, priority, category_id, due_date, reminder_time, recurrence_rule, completed_at, sort_order, created_at, updated_at)
-         VALUES (?1, ?2, ?3, 'todo', ?4, ?5, ?6, ?7, ?8, NULL, ?9, ?10, ?11)",
-        params![
-            id,
-            input.title.trim(),
-            input.description,
-            input.priority.as_deref().unwrap_or("none"),
-            input.category_id,
-            input.due_date,
-            input.reminder_time,
-            recurrence_json,
-            new_sort_order,
-            now,
-            now,
-        ],
-    )
-    .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    change_log::tracked(conn, TASK, &id, "create", || {
+        conn.execute(
+            "INSERT INTO tasks (id, title, description, status, priority, category_id, project_id, heading_id, start_date, due_date, reminder_time, recurrence_rule, someday, completed_at, sort_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, 'todo', ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, NULL, ?13, ?14, ?15)",
+            params![
+                id,
+                input.title.trim(),
+                input.description,
+                priority,
+                input.category_id,
+                project_id,
+                heading_id,
+                input.start_date,
+                input.due_date,
+                input.reminder_time,
+                recurrence_json,
+                input.someday.unwrap_or(false) as i64,
+                new_sort_order,
+                now,
+                now,
+            ],
+        )
+        .map_err(AppError::from)
+    })?;
 
     get_task_by_id(conn, &id)
-        .and_then(|t| t.ok_or_else(|| AppError { code: "DB_ERROR".into(), message: "Failed to read created task".into(), details: None }))
+        .and_then(|t| t.ok_or_else(|| AppError::new(ErrorCode::DbError, "Failed to read created task")))
 }
 
 pub fn get_task_by_id(conn: &Connection, id: &str) -> Result<Option<Task>, AppError> {
     let task = conn
-        .query_row("SELECT * FROM tasks WHERE id = ?1", params![id], row_to_task)
-        .optional()
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+        .query_row("SELECT * FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![id], row_to_task)
+        .optional()?;
 
     let Some(mut task) = task else { return Ok(None) };
 
     // Load sub-tasks
     let mut stmt = conn
-        .prepare("SELECT * FROM sub_tasks WHERE task_id = ?1 ORDER BY sort_order")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+        .prepare(&LIVE_SUB_TASK_TREE_SQL.replace("{}", "?1"))?;
     let sub_tasks: Vec<SubTask> = stmt
-        .query_map(params![id], row_to_sub_task)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        .query_map(params![id], row_to_sub_task)?
+        .collect::<Result<_, _>>()?;
     task.sub_tasks = Some(build_sub_task_tree(sub_tasks));
 
     // Load tags
@@ -148,9 +175,8 @@ pub fn get_task_by_id(conn: &Connection, id: &str) -> Result<Option<Task>, AppEr
         .prepare(
             "SELECT t.id, t.name, t.color, t.created_at
              FROM task_tags tt INNER JOIN tags t ON tt.tag_id = t.id
-             WHERE tt.task_id = ?1",
-        )
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+             WHERE tt.task_id = ?1 AND t.deleted_at IS NULL",
+        )?;
     let tag_list: Vec<Tag> = stmt
         .query_map(params![id], |row| {
             Ok(Tag {
@@ -159,36 +185,42 @@ pub fn get_task_by_id(conn: &Connection, id: &str) -> Result<Option<Task>, AppEr
                 color: row.get(2)?,
                 created_at: row.get(3)?,
             })
-        })
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        })?
+        .collect::<Result<_, _>>()?;
     task.tags = Some(tag_list);
 
+    dependency::attach_dependencies(conn, std::slice::from_mut(&mut task))?;
+    time_entry::attach_time_spent(conn, std::slice::from_mut(&mut task))?;
+
     Ok(Some(task))
 }
 
 pub fn get_all_tasks(conn: &Connection, filter: Option<TaskFilter>) -> Result<Vec<Task>, AppError> {
-    let status_filter = filter.as_ref().and_then(|f| f.status.clone());
+    let status_filter = filter
+        .as_ref()
+        .and_then(|f| f.status.as_deref())
+        .map(|s| TaskStatus::parse("status", s))
+        .transpose()?;
 
     let rows: Vec<Task> = if let Some(ref status) = status_filter {
         let mut stmt = conn
-            .prepare("SELECT * FROM tasks WHERE status = ?1 ORDER BY sort_order")
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-        let mapped = stmt.query_map(params![status], row_to_task)
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-        let result: Vec<Task> = mapped.filter_map(|r| r.ok()).collect();
+            .prepare("SELECT * FROM tasks WHERE status = ?1 AND deleted_at IS NULL AND archived_at IS NULL ORDER BY sort_order")?;
+        let mapped = stmt.query_map(params![status], row_to_task)?;
+        let result: Vec<Task> = mapped.collect::<Result<_, _>>()?;
         result
     } else {
-        let mut stmt = conn
-            .prepare("SELECT * FROM tasks ORDER BY sort_order")
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-        let mapped = stmt.query_map([], row_to_task)
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-        let result: Vec<Task> = mapped.filter_map(|r| r.ok()).collect();
+        let mut stmt = conn.prepare("SELECT * FROM tasks WHERE deleted_at IS NULL AND archived_at IS NULL ORDER BY sort_order")?;
+        let mapped = stmt.query_map([], row_to_task)?;
+        let result: Vec<Task> = mapped.collect::<Result<_, _>>()?;
         result
     };
 
+    with_relations(conn, rows)
+}
+
+/// Attach sub-task trees, tags and dependencies to `rows`, loading each relation for
+/// all of them in one query.
+pub(crate) fn with_relations(conn: &Connection, rows: Vec<Task>) -> Result<Vec<Task>, AppError> {
     if rows.is_empty() {
         return Ok(rows);
     }
@@ -197,15 +229,12 @@ pub fn get_all_tasks(conn: &Connection, filter: Option<TaskFilter>) -> Result<Ve
     let placeholders = task_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
 
     // Batch-load sub-tasks
-    let sql = format!("SELECT * FROM sub_tasks WHERE task_id IN ({}) ORDER BY sort_order", placeholders);
-    let mut stmt = conn.prepare(&sql)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let sql = LIVE_SUB_TASK_TREE_SQL.replace("{}", &placeholders);
+    let mut stmt = conn.prepare(&sql)?;
     let params_refs: Vec<&dyn rusqlite::types::ToSql> = task_ids.iter().map(|s| s as &dyn rusqlite::types::ToSql).collect();
     let all_sub_tasks: Vec<SubTask> = stmt
-        .query_map(params_refs.as_slice(), row_to_sub_task)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        .query_map(params_refs.as_slice(), row_to_sub_task)?
+        .collect::<Result<_, _>>()?;
 
     let mut sub_tasks_by_task: std::collections::HashMap<String, Vec<SubTask>> = std::collections::HashMap::new();
     for st in all_sub_tasks {
@@ -216,11 +245,10 @@ pub fn get_all_tasks(conn: &Connection, filter: Option<TaskFilter>) -> Result<Ve
     let sql = format!(
         "SELECT tt.task_id, t.id, t.name, t.color, t.created_at
          FROM task_tags tt INNER JOIN tags t ON tt.tag_id = t.id
-         WHERE tt.task_id IN ({})",
+         WHERE tt.task_id IN ({}) AND t.deleted_at IS NULL",
         placeholders
     );
-    let mut stmt = conn.prepare(&sql)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let mut stmt = conn.prepare(&sql)?;
 
     struct TaskTag {
         task_id: String,
@@ -238,10 +266,8 @@ pub fn get_all_tasks(conn: &Connection, filter: Option<TaskFilter>) -> Result<Ve
                     created_at: row.get(4)?,
                 },
             })
-        })
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
+        })?
+        .collect::<Result<_, _>>()?;
 
     let mut tags_by_task: std::collections::HashMap<String, Vec<Tag>> = std::collections::HashMap::new();
     for tt in all_task_tags {
@@ -255,6 +281,8 @@ pub fn get_all_tasks(conn: &Connection, filter: Option<TaskFilter>) -> Result<Ve
         task.sub_tasks = Some(build_sub_task_tree(subs));
         task.tags = Some(tags_by_task.remove(&task.id).unwrap_or_default());
     }
+    dependency::attach_dependencies(conn, &mut result)?;
+    time_entry::attach_time_spent(conn, &mut result)?;
 
     Ok(result)
 }
@@ -263,15 +291,40 @@ pub fn update_task(conn: &Connection, id: &str, input: UpdateTaskInput) -> Resul
     if let Some(ref title) = input.title {
         validate_title(title)?;
     }
+    let priority = parse_priority(input.priority.as_deref())?;
+    validate_schedule(
+        input.start_date.as_ref().and_then(|d| d.as_deref()),
+        input.due_date.as_ref().and_then(|d| d.as_deref()),
+        input.reminder_time.as_ref().and_then(|r| r.as_deref()),
+        input.recurrence_rule.as_ref().and_then(|r| r.as_ref()),
+    )?;
+
+    let (current_project, current_heading): (Option<String>, Option<String>) = conn
+        .query_row(
+            "SELECT project_id, heading_id FROM tasks WHERE id = ?1 AND deleted_at IS NULL",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Task not found"))?;
 
-    // Check exists
-    let exists: bool = conn
-        .query_row("SELECT COUNT(*) FROM tasks WHERE id = ?1", params![id], |row| row.get::<_, i64>(0))
-        .unwrap_or(0)
-        > 0;
-    if !exists {
-        return Err(AppError { code: "NOT_FOUND".into(), message: "Task not found".into(), details: None });
-    }
+    // A heading only makes sense inside its own project: moving the task to
+    // another project drops it, and picking a heading moves the task along.
+    let placement = if input.project_id.is_none() && input.heading_id.is_none() {
+        None
+    } else {
+        let heading_id = match input.heading_id.clone() {
+            Some(heading_id) => heading_id,
+            None if input.project_id.as_ref() == Some(&current_project) => current_heading,
+            None => None,
+        };
+        let project_id = match input.project_id.clone() {
+            Some(project_id) => project_id,
+            None if input.heading_id.as_ref().is_some_and(Option::is_some) => None,
+            None => current_project,
+        };
+        Some(resolve_placement(conn, project_id, heading_id)?)
+    };
 
     let now = chrono::Utc::now().to_rfc3339();
     let mut sets: Vec<String> = vec!["updated_at = ?".to_string()];
@@ -285,14 +338,24 @@ pub fn update_task(conn: &Connection, id: &str, input: UpdateTaskInput) -> Resul
         sets.push("description = ?".to_string());
         values.push(Box::new(desc.clone()));
     }
-    if let Some(ref priority) = input.priority {
+    if let Some(priority) = priority {
         sets.push("priority = ?".to_string());
-        values.push(Box::new(priority.clone()));
+        values.push(Box::new(priority));
     }
     if let Some(ref cat_id) = input.category_id {
         sets.push("category_id = ?".to_string());
         values.push(Box::new(cat_id.clone()));
     }
+    if let Some((project_id, heading_id)) = placement {
+        sets.push("project_id = ?".to_string());
+        values.push(Box::new(project_id));
+        sets.push("heading_id = ?".to_string());
+        values.push(Box::new(heading_id));
+    }
+    if let Some(ref start) = input.start_date {
+        sets.push("start_date = ?".to_string());
+        values.push(Box::new(start.clone()));
+    }
     if let Some(ref due) = input.due_date {
         sets.push("due_date = ?".to_string());
         values.push(Box::new(due.clone()));
@@ -306,42 +369,69 @@ pub fn update_task(conn: &Connection, id: &str, input: UpdateTaskInput) -> Resul
         let json = rec.as_ref().map(|r| serde_json::to_string(r).unwrap_or_default());
         values.push(Box::new(json));
     }
+    if let Some(someday) = input.someday {
+        sets.push("someday = ?".to_string());
+        values.push(Box::new(someday as i64));
+    }
 
     values.push(Box::new(id.to_string()));
 
     let sql = format!("UPDATE tasks SET {} WHERE id = ?", sets.join(", "));
     let params_refs: Vec<&dyn rusqlite::types::ToSql> = values.iter().map(|v| v.as_ref()).collect();
-    conn.execute(&sql, params_refs.as_slice())
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    change_log::tracked(conn, TASK, id, "update", || {
+        conn.execute(&sql, params_refs.as_slice())
+            .map_err(AppError::from)
+    })?;
 
     get_task_by_id(conn, id)?
-        .ok_or_else(|| AppError { code: "NOT_FOUND".into(), message: "Task not found".into(), details: None })
+        .ok_or_else(|| AppError::not_found("Task not found"))
 }
 
+/// Move a task to the trash. Its sub-tasks and tag links are left in place so that
+/// restoring the task brings them back.
 pub fn delete_task(conn: &Connection, id: &str) -> Result<(), AppError> {
-    conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    let now = chrono::Utc::now().to_rfc3339();
+    change_log::tracked(conn, TASK, id, "delete", || {
+        conn.execute(
+            "UPDATE tasks SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(AppError::from)
+    })?;
     Ok(())
 }
 
 pub fn complete_task(conn: &Connection, id: &str) -> Result<CompleteTaskResult, AppError> {
+    change_log::in_transaction(conn, || complete_task_inner(conn, id))
+}
+
+fn complete_task_inner(conn: &Connection, id: &str) -> Result<CompleteTaskResult, AppError> {
     // Read existing task (raw data for recurrence)
     let existing = conn
-        .query_row("SELECT * FROM tasks WHERE id = ?1", params![id], row_to_task)
-        .optional()
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .ok_or_else(|| AppError { code: "NOT_FOUND".into(), message: "Task not found".into(), details: None })?;
+        .query_row("SELECT * FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![id], row_to_task)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Task not found"))?;
 
     let now = chrono::Utc::now().to_rfc3339();
 
-    conn.execute(
-        "UPDATE tasks SET status = 'completed', completed_at = ?1, updated_at = ?2 WHERE id = ?3",
-        params![now, now, id],
-    )
-    .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    change_log::tracked(conn, TASK, id, "complete", || {
+        conn.execute(
+            "UPDATE tasks SET status = 'completed', completed_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![now, now, id],
+        )
+        .map_err(AppError::from)
+    })?;
 
     let completed_task = get_task_by_id(conn, id)?
-        .ok_or_else(|| AppError { code: "NOT_FOUND".into(), message: "Task not found".into(), details: None })?;
+        .ok_or_else(|| AppError::not_found("Task not found"))?;
+
+    // Completing an already completed task can't unblock anything new
+    let mut unblocked_tasks = Vec::new();
+    if existing.status == TaskStatus::Todo {
+        for unblocked_id in dependency::unblocked_by(conn, id)? {
+            unblocked_tasks.extend(get_task_by_id(conn, &unblocked_id)?);
+        }
+    }
 
     // If recurring, create next instance
     let mut next_task = None;
@@ -355,43 +445,56 @@ pub fn complete_task(conn: &Connection, id: &str) -> Result<CompleteTaskResult,
         if let Some(ref end_str) = rule.end_date {
             if let Ok(end_date) = NaiveDate::parse_from_str(end_str, "%Y-%m-%d") {
                 if next_date > end_date {
-                    return Ok(CompleteTaskResult { completed_task, next_task: None });
+                    return Ok(CompleteTaskResult { completed_task, next_task: None, unblocked_tasks });
                 }
             }
         }
 
+        // The next instance keeps the same lead time between start and due date
+        let start_date = existing
+            .start_date
+            .as_deref()
+            .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+            .map(|start| (start + (next_date - current_date)).format("%Y-%m-%d").to_string());
+
         let new_task = create_task(conn, CreateTaskInput {
             title: existing.title.clone(),
             description: existing.description.clone(),
-            priority: Some(existing.priority.clone()),
+            priority: Some(existing.priority.as_str().to_string()),
             category_id: existing.category_id.clone(),
+            start_date,
             due_date: Some(next_date.format("%Y-%m-%d").to_string()),
             reminder_time: existing.reminder_time.clone(),
             recurrence_rule: Some(rule.clone()),
+            someday: Some(existing.someday),
+            project_id: existing.project_id.clone(),
+            heading_id: existing.heading_id.clone(),
         })?;
         next_task = Some(new_task);
     }
 
-    Ok(CompleteTaskResult { completed_task, next_task })
+    Ok(CompleteTaskResult { completed_task, next_task, unblocked_tasks })
 }
 
 pub fn uncomplete_task(conn: &Connection, id: &str) -> Result<Task, AppError> {
     let exists: bool = conn
-        .query_row("SELECT COUNT(*) FROM tasks WHERE id = ?1", params![id], |row| row.get::<_, i64>(0))
+        .query_row("SELECT COUNT(*) FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![id], |row| row.get::<_, i64>(0))
         .unwrap_or(0) > 0;
     if !exists {
-        return Err(AppError { code: "NOT_FOUND".into(), message: "Task not found".into(), details: None });
+        return Err(AppError::not_found("Task not found"));
     }
 
     let now = chrono::Utc::now().to_rfc3339();
-    conn.execute(
-        "UPDATE tasks SET status = 'todo', completed_at = NULL, updated_at = ?1 WHERE id = ?2",
-        params![now, id],
-    )
-    .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    change_log::tracked(conn, TASK, id, "uncomplete", || {
+        conn.execute(
+            "UPDATE tasks SET status = 'todo', completed_at = NULL, archived_at = NULL, updated_at = ?1 WHERE id = ?2",
+            params![now, id],
+        )
+        .map_err(AppError::from)
+    })?;
 
     get_task_by_id(conn, id)?
-        .ok_or_else(|| AppError { code: "NOT_FOUND".into(), message: "Task not found".into(), details: None })
+        .ok_or_else(|| AppError::not_found("Task not found"))
 }
 
 pub fn reorder_tasks(conn: &Connection, items: Vec<ReorderTaskItem>) -> Result<(), AppError> {
@@ -399,14 +502,158 @@ pub fn reorder_tasks(conn: &Connection, items: Vec<ReorderTaskItem>) -> Result<(
         return Ok(());
     }
     let now = chrono::Utc::now().to_rfc3339();
-    for item in &items {
-        conn.execute(
-            "UPDATE tasks SET sort_order = ?1, updated_at = ?2 WHERE id = ?3",
-            params![item.sort_order, now, item.id],
-        )
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-    }
-    Ok(())
+    change_log::in_transaction(conn, || {
+        for item in &items {
+            change_log::tracked(conn, TASK, &item.id, "reorder", || {
+                conn.execute(
+                    "UPDATE tasks SET sort_order = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![item.sort_order, now, item.id],
+                )
+                .map_err(AppError::from)
+            })?;
+        }
+        Ok(())
+    })
+}
+
+// ============================================================
+// Duplicate
+// ============================================================
+
+/// Copy a task together with its live sub-task tree and its tags. The copy goes
+/// right after the original in `sort_order`.
+///
+/// `options.reset_completion` (on by default) starts the copy and its sub-tasks
+/// over as open; `options.date_offset_days` moves every date of the copy.
+pub fn duplicate_task(conn: &Connection, id: &str, options: DuplicateTaskOptions) -> Result<Task, AppError> {
+    let original = conn
+        .query_row("SELECT * FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![id], row_to_task)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Task not found").with_detail("id", id))?;
+    let reset = options.reset_completion.unwrap_or(true);
+    let offset = options.date_offset_days.unwrap_or(0);
+    let shift = |date: &Option<String>| shift_date(date.as_deref(), offset);
+    let (start_date, due_date) = (shift(&original.start_date)?, shift(&original.due_date)?);
+    let reminder_time = match original.reminder_time.as_deref() {
+        Some(value) => Some(shift_date_time(value, offset)?),
+        None => None,
+    };
+
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE tree AS (
+            SELECT *, 0 AS depth FROM sub_tasks WHERE task_id = ?1 AND parent_id IS NULL AND deleted_at IS NULL
+            UNION ALL
+            SELECT s.*, tree.depth + 1 FROM sub_tasks s INNER JOIN tree ON s.parent_id = tree.id WHERE s.deleted_at IS NULL
+         )
+         SELECT * FROM tree ORDER BY depth, sort_order",
+    )?;
+    let sub_tasks: Vec<SubTask> = stmt
+        .query_map(params![id], row_to_sub_task)?
+        .collect::<Result<_, _>>()?;
+    let tag_ids = query_ids(conn, "SELECT tag_id FROM task_tags WHERE task_id = ?1", id)?;
+
+    let copy_id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    change_log::in_transaction(conn, || {
+        // Make room right after the original, moving only the live tasks that
+        // sit in unbroken order behind it
+        let mut stmt = conn.prepare(
+            "SELECT id, sort_order FROM tasks
+             WHERE deleted_at IS NULL AND archived_at IS NULL AND sort_order > ?1
+             ORDER BY sort_order",
+        )?;
+        let rows = stmt.query_map(params![original.sort_order], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?;
+        let mut later: Vec<String> = Vec::new();
+        let mut next_free = original.sort_order + 1;
+        for row in rows {
+            let (later_id, sort_order) = row?;
+            if sort_order > next_free {
+                break;
+            }
+            later.push(later_id);
+            next_free = sort_order + 1;
+        }
+        for later_id in &later {
+            change_log::tracked(conn, TASK, later_id, "reorder", || {
+                conn.execute("UPDATE tasks SET sort_order = sort_order + 1 WHERE id = ?1", params![later_id])
+                    .map_err(AppError::from)
+            })?;
+        }
+
+        change_log::tracked(conn, TASK, &copy_id, "create", || {
+            conn.execute(
+                "INSERT INTO tasks (id, title, description, status, priority, category_id, project_id, heading_id, start_date, due_date, reminder_time, recurrence_rule, someday, completed_at, sort_order, created_at, updated_at)
+                 SELECT ?1, title, description, CASE WHEN ?2 THEN 'todo' ELSE status END, priority, category_id, project_id, heading_id, ?3, ?4, ?5, recurrence_rule, someday, CASE WHEN ?2 THEN NULL ELSE completed_at END, sort_order + 1, ?6, ?6
+                 FROM tasks WHERE id = ?7",
+                params![copy_id, reset, start_date, due_date, reminder_time, now, id],
+            )
+            .map_err(AppError::from)
+        })?;
+
+        // Parents come before their children, so their copies' ids are known
+        let mut copied: std::collections::HashMap<&str, String> = std::collections::HashMap::new();
+        for sub_task in &sub_tasks {
+            let sub_task_id = Uuid::new_v4().to_string();
+            let parent_id = sub_task.parent_id.as_deref().and_then(|pid| copied.get(pid)).cloned();
+            let due_date = shift_date(sub_task.due_date.as_deref(), offset)?;
+            change_log::tracked(conn, SUB_TASK, &sub_task_id, "create", || {
+                conn.execute(
+                    "INSERT INTO sub_tasks (id, task_id, parent_id, title, description, priority, due_date, completed, sort_order, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![
+                        sub_task_id,
+                        copy_id,
+                        parent_id,
+                        sub_task.title,
+                        sub_task.description,
+                        sub_task.priority,
+                        due_date,
+                        (sub_task.completed && !reset) as i64,
+                        sub_task.sort_order,
+                        now,
+                    ],
+                )
+                .map_err(AppError::from)
+            })?;
+            copied.insert(sub_task.id.as_str(), sub_task_id);
+        }
+
+        for tag_id in &tag_ids {
+            tag::add_tag_to_task(conn, &copy_id, tag_id)?;
+        }
+
+        get_task_by_id(conn, &copy_id)?
+            .ok_or_else(|| AppError::new(ErrorCode::DbError, "Failed to read duplicated task"))
+    })
+}
+
+/// A `YYYY-MM-DD` date moved by `days`. Values that aren't dates are kept as they are.
+fn shift_date(value: Option<&str>, days: i64) -> Result<Option<String>, AppError> {
+    let Some(value) = value else { return Ok(None) };
+    let Ok(date) = NaiveDate::parse_from_str(value, validate::DATE_FORMAT) else {
+        return Ok(Some(value.to_string()));
+    };
+    chrono::Duration::try_days(days)
+        .and_then(|d| date.checked_add_signed(d))
+        .map(|d| Some(d.format(validate::DATE_FORMAT).to_string()))
+        .ok_or_else(|| offset_out_of_range(days))
+}
+
+/// An RFC 3339 date-time moved by `days`, keeping its UTC offset.
+fn shift_date_time(value: &str, days: i64) -> Result<String, AppError> {
+    let Ok(date_time) = chrono::DateTime::parse_from_rfc3339(value) else {
+        return Ok(value.to_string());
+    };
+    chrono::Duration::try_days(days)
+        .and_then(|d| date_time.checked_add_signed(d))
+        .map(|d| d.to_rfc3339())
+        .ok_or_else(|| offset_out_of_range(days))
+}
+
+fn offset_out_of_range(days: i64) -> AppError {
+    AppError::validation("dateOffsetDays moves the dates out of range")
+        .with_detail("field", "dateOffsetDays")
+        .with_detail("value", days.to_string())
 }
 
 // ============================================================
@@ -415,13 +662,15 @@ pub fn reorder_tasks(conn: &Connection, items: Vec<ReorderTaskItem>) -> Result<(
 
 pub fn create_sub_task(conn: &Connection, task_id: &str, input: CreateSubTaskInput, parent_id_override: Option<String>) -> Result<SubTask, AppError> {
     validate_title(&input.title)?;
+    let priority = parse_priority(input.priority.as_deref())?.unwrap_or_default();
+    validate_schedule(None, input.due_date.as_deref(), None, None)?;
 
     // Verify parent task exists
     let exists: bool = conn
-        .query_row("SELECT COUNT(*) FROM tasks WHERE id = ?1", params![task_id], |row| row.get::<_, i64>(0))
+        .query_row("SELECT COUNT(*) FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![task_id], |row| row.get::<_, i64>(0))
         .unwrap_or(0) > 0;
     if !exists {
-        return Err(AppError { code: "NOT_FOUND".into(), message: "Parent task not found".into(), details: None });
+        return Err(AppError::not_found("Parent task not found"));
     }
 
     let parent_id = parent_id_override.or(input.parent_id.clone());
@@ -429,17 +678,12 @@ pub fn create_sub_task(conn: &Connection, task_id: &str, input: CreateSubTaskInp
     // Verify parent sub-task if nesting
     if let Some(ref pid) = parent_id {
         let parent_check: Option<String> = conn
-            .query_row("SELECT task_id FROM sub_tasks WHERE id = ?1", params![pid], |row| row.get(0))
-            .optional()
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+            .query_row("SELECT task_id FROM sub_tasks WHERE id = ?1 AND deleted_at IS NULL", params![pid], |row| row.get(0))
+            .optional()?;
         match parent_check {
-            None => return Err(AppError { code: "NOT_FOUND".into(), message: "Parent sub-task not found".into(), details: None }),
+            None => return Err(AppError::not_found("Parent sub-task not found")),
             Some(ref ptid) if ptid != task_id => {
-                return Err(AppError {
-                    code: "VALIDATION_ERROR".into(),
-                    message: "Parent sub-task does not belong to this task".into(),
-                    details: None,
-                });
+                return Err(AppError::validation("Parent sub-task does not belong to this task"));
             }
             _ => {}
         }
@@ -465,22 +709,24 @@ pub fn create_sub_task(conn: &Connection, task_id: &str, input: CreateSubTaskInp
 
     let sort_order = input.sort_order.unwrap_or(max_order + 1);
 
-    conn.execute(
-        "INSERT INTO sub_tasks (id, task_id, parent_id, title, description, priority, due_date, completed, sort_order, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, ?8, ?9)",
-        params![
-            id,
-            task_id,
-            parent_id,
-            input.title.trim(),
-            input.description,
-            input.priority.as_deref().unwrap_or("none"),
-            input.due_date,
-            sort_order,
-            now,
-        ],
-    )
-    .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+    change_log::tracked(conn, SUB_TASK, &id, "create", || {
+        conn.execute(
+            "INSERT INTO sub_tasks (id, task_id, parent_id, title, description, priority, due_date, completed, sort_order, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, ?8, ?9)",
+            params![
+                id,
+                task_id,
+                parent_id,
+                input.title.trim(),
+                input.description,
+                priority,
+                input.due_date,
+                sort_order,
+                now,
+            ],
+        )
+        .map_err(AppError::from)
+    })?;
 
     Ok(SubTask {
         id,
@@ -488,7 +734,7 @@ pub fn create_sub_task(conn: &Connection, task_id: &str, input: CreateSubTaskInp
         parent_id,
         title: input.title.trim().to_string(),
         description: input.description,
-        priority: input.priority.unwrap_or_else(|| "none".to_string()),
+        priority,
         due_date: input.due_date,
         completed: false,
         sort_order,
@@ -501,12 +747,14 @@ pub fn update_sub_task(conn: &Connection, id: &str, input: UpdateSubTaskInput) -
     if let Some(ref title) = input.title {
         validate_title(title)?;
     }
+    let priority = parse_priority(input.priority.as_deref())?;
+    validate_schedule(None, input.due_date.as_ref().and_then(|d| d.as_deref()), None, None)?;
 
     let exists: bool = conn
-        .query_row("SELECT COUNT(*) FROM sub_tasks WHERE id = ?1", params![id], |row| row.get::<_, i64>(0))
+        .query_row("SELECT COUNT(*) FROM sub_tasks WHERE id = ?1 AND deleted_at IS NULL", params![id], |row| row.get::<_, i64>(0))
         .unwrap_or(0) > 0;
     if !exists {
-        return Err(AppError { code: "NOT_FOUND".into(), message: "Sub-task not found".into(), details: None });
+        return Err(AppError::not_found("Sub-task not found"));
     }
 
     let mut sets: Vec<String> = Vec::new();
@@ -520,9 +768,9 @@ pub fn update_sub_task(conn: &Connection, id: &str, input: UpdateSubTaskInput) -
         sets.push("description = ?".to_string());
         values.push(Box::new(desc.clone()));
     }
-    if let Some(ref priority) = input.priority {
+    if let Some(priority) = priority {
         sets.push("priority = ?".to_string());
-        values.push(Box::new(priority.clone()));
+        values.push(Box::new(priority));
     }
     if let Some(ref due) = input.due_date {
         sets.push("due_date = ?".to_string());
@@ -541,50 +789,624 @@ pub fn update_sub_task(conn: &Connection, id: &str, input: UpdateSubTaskInput) -
         values.push(Box::new(id.to_string()));
         let sql = format!("UPDATE sub_tasks SET {} WHERE id = ?", sets.join(", "));
         let params_refs: Vec<&dyn rusqlite::types::ToSql> = values.iter().map(|v| v.as_ref()).collect();
-        conn.execute(&sql, params_refs.as_slice())
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+        change_log::tracked(conn, SUB_TASK, id, "update", || {
+            conn.execute(&sql, params_refs.as_slice())
+                .map_err(AppError::from)
+        })?;
     }
 
     conn.query_row("SELECT * FROM sub_tasks WHERE id = ?1", params![id], row_to_sub_task)
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })
+        .map_err(AppError::from)
 }
 
+/// Move a sub-task and its descendants to the trash. Descendants share the parent's
+/// `deleted_at`, which is how `restore_sub_task` finds the subtree again.
 pub fn delete_sub_task(conn: &Connection, id: &str) -> Result<(), AppError> {
-    delete_sub_task_descendants(conn, id)?;
-    conn.execute("DELETE FROM sub_tasks WHERE id = ?1", params![id])
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-    Ok(())
+    let now = chrono::Utc::now().to_rfc3339();
+    let ids = sub_task_subtree_ids(conn, id)?;
+    change_log::in_transaction(conn, || {
+        for sub_task_id in &ids {
+            change_log::tracked(conn, SUB_TASK, sub_task_id, "delete", || {
+                conn.execute(
+                    "UPDATE sub_tasks SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                    params![now, sub_task_id],
+                )
+                .map_err(AppError::from)
+            })?;
+        }
+        Ok(())
+    })
 }
 
-fn delete_sub_task_descendants(conn: &Connection, parent_id: &str) -> Result<(), AppError> {
-    let mut stmt = conn
-        .prepare("SELECT id FROM sub_tasks WHERE parent_id = ?1")
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
-    let children: Vec<String> = stmt
-        .query_map(params![parent_id], |row| row.get(0))
-        .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?
-        .filter_map(|r| r.ok())
-        .collect();
-
-    for child_id in &children {
-        delete_sub_task_descendants(conn, child_id)?;
-        conn.execute("DELETE FROM sub_tasks WHERE id = ?1", params![child_id])
-            .map_err(|e| AppError { code: "DB_ERROR".into(), message: e.to_string(), details: None })?;
+/// Bring a trashed sub-task back together with the descendants trashed alongside it.
+/// Trashed ancestors are restored too so the sub-task doesn't come back detached.
+pub fn restore_sub_task(conn: &Connection, id: &str) -> Result<(), AppError> {
+    let row: Option<(String, Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT task_id, parent_id, deleted_at FROM sub_tasks WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let Some((task_id, parent_id, Some(deleted_at))) = row else {
+        return Err(AppError::not_found("Sub-task not found in trash"));
+    };
+
+    let task_trashed: bool = conn
+        .query_row("SELECT deleted_at IS NOT NULL FROM tasks WHERE id = ?1", params![task_id], |row| row.get(0))?;
+    if task_trashed {
+        return Err(AppError::validation("Restore the parent task before its sub-tasks"));
+    }
+
+    let ids = sub_task_subtree_ids(conn, id)?;
+    let ancestors = match parent_id {
+        Some(ref pid) => sub_task_ancestor_ids(conn, pid)?,
+        None => vec![],
+    };
+    change_log::in_transaction(conn, || {
+        for sub_task_id in &ids {
+            change_log::tracked(conn, SUB_TASK, sub_task_id, "restore", || {
+                conn.execute(
+                    "UPDATE sub_tasks SET deleted_at = NULL WHERE id = ?1 AND deleted_at = ?2",
+                    params![sub_task_id, deleted_at],
+                )
+                .map_err(AppError::from)
+            })?;
+        }
+
+        for ancestor_id in &ancestors {
+            change_log::tracked(conn, SUB_TASK, ancestor_id, "restore", || {
+                conn.execute("UPDATE sub_tasks SET deleted_at = NULL WHERE id = ?1", params![ancestor_id])
+                    .map_err(AppError::from)
+            })?;
+        }
+        Ok(())
+    })
+}
+
+// ============================================================
+// Moving Sub-tasks
+// ============================================================
+
+/// Move a sub-task with its descendants under `new_parent_id` (or to the top level)
+/// of `new_task_id`, at `position` among its new siblings (the end when `None`).
+/// The new siblings are renumbered so their `sort_order`s run 0, 1, 2, ...
+pub fn move_sub_task(
+    conn: &Connection,
+    id: &str,
+    new_task_id: &str,
+    new_parent_id: Option<String>,
+    position: Option<i64>,
+) -> Result<SubTask, AppError> {
+    let exists: bool = conn
+        .query_row("SELECT COUNT(*) > 0 FROM sub_tasks WHERE id = ?1 AND deleted_at IS NULL", params![id], |row| row.get(0))?;
+    if !exists {
+        return Err(AppError::not_found("Sub-task not found").with_detail("id", id));
+    }
+    let task_exists: bool = conn
+        .query_row("SELECT COUNT(*) > 0 FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![new_task_id], |row| row.get(0))?;
+    if !task_exists {
+        return Err(AppError::not_found("Task not found").with_detail("id", new_task_id));
+    }
+    if let Some(ref pid) = new_parent_id {
+        let parent_task: Option<String> = conn
+            .query_row("SELECT task_id FROM sub_tasks WHERE id = ?1 AND deleted_at IS NULL", params![pid], |row| row.get(0))
+            .optional()?;
+        match parent_task {
+            None => return Err(AppError::not_found("Parent sub-task not found").with_detail("id", pid.as_str())),
+            Some(ref ptid) if ptid != new_task_id => {
+                return Err(AppError::validation("Parent sub-task does not belong to this task")
+                    .with_detail("field", "newParentId")
+                    .with_detail("value", pid.as_str()));
+            }
+            _ => {}
+        }
+        if sub_task_subtree_ids(conn, id)?.contains(pid) {
+            return Err(AppError::new(ErrorCode::Conflict, "A sub-task can't be moved under itself or its descendants")
+                .with_detail("id", id)
+                .with_detail("newParentId", pid.as_str()));
+        }
+    }
+    if position.is_some_and(|p| p < 0) {
+        return Err(AppError::validation("Position must not be negative")
+            .with_detail("field", "position")
+            .with_detail("value", position.unwrap_or_default().to_string()));
+    }
+
+    change_log::in_transaction(conn, || {
+        move_sub_trees(conn, "id = ?1", id, new_task_id, new_parent_id.as_deref())?;
+
+        // `IS` so that a `NULL` parent matches the top level
+        let mut stmt = conn.prepare(
+            "SELECT id FROM sub_tasks
+             WHERE task_id = ?1 AND parent_id IS ?2 AND deleted_at IS NULL
+             ORDER BY sort_order, created_at",
+        )?;
+        let mut siblings: Vec<String> = stmt
+            .query_map(params![new_task_id, new_parent_id], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        siblings.retain(|sibling| sibling != id);
+        let index = position.map_or(siblings.len(), |p| (p as usize).min(siblings.len()));
+        siblings.insert(index, id.to_string());
+
+        for (order, sibling) in siblings.iter().enumerate() {
+            change_log::tracked(conn, SUB_TASK, sibling, "reorder", || {
+                conn.execute(
+                    "UPDATE sub_tasks SET sort_order = ?1 WHERE id = ?2 AND sort_order != ?1",
+                    params![order as i64, sibling],
+                )
+                .map_err(AppError::from)
+            })?;
+        }
+
+        get_sub_task_tree(conn, id)
+    })
+}
+
+/// Turn a sub-task into a task of its own. The new task keeps the sub-task's
+/// priority, due date and completion, takes its category and project from the task
+/// it came from, and gets the sub-task's descendants as its sub-tasks.
+pub fn promote_sub_task(conn: &Connection, id: &str) -> Result<Task, AppError> {
+    let sub_task = conn
+        .query_row("SELECT * FROM sub_tasks WHERE id = ?1 AND deleted_at IS NULL", params![id], row_to_sub_task)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Sub-task not found"))?;
+    let source = conn
+        .query_row("SELECT * FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![sub_task.task_id], row_to_task)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Parent task not found"))?;
+
+    change_log::in_transaction(conn, || {
+        let task = create_task(conn, CreateTaskInput {
+            title: sub_task.title.clone(),
+            description: sub_task.description.clone(),
+            priority: Some(sub_task.priority.as_str().to_string()),
+            category_id: source.category_id.clone(),
+            due_date: sub_task.due_date.clone(),
+            reminder_time: None,
+            recurrence_rule: None,
+            start_date: None,
+            someday: None,
+            project_id: source.project_id.clone(),
+            heading_id: source.heading_id.clone(),
+        })?;
+        move_sub_trees(conn, "parent_id = ?1", id, &task.id, None)?;
+
+        if sub_task.completed {
+            let now = chrono::Utc::now().to_rfc3339();
+            change_log::tracked(conn, TASK, &task.id, "complete", || {
+                conn.execute(
+                    "UPDATE tasks SET status = 'completed', completed_at = ?1, updated_at = ?1 WHERE id = ?2",
+                    params![now, task.id],
+                )
+                .map_err(AppError::from)
+            })?;
+        }
+        change_log::tracked(conn, SUB_TASK, id, "delete", || {
+            conn.execute("DELETE FROM sub_tasks WHERE id = ?1", params![id])
+                .map_err(AppError::from)
+        })?;
+
+        get_task_by_id(conn, &task.id)?
+            .ok_or_else(|| AppError::new(ErrorCode::DbError, "Failed to read promoted task"))
+    })
+}
+
+/// Turn a task into a sub-task of `target_task_id`, under `parent_id` or at the top
+/// level. The task's own sub-tasks come along as the new sub-task's descendants.
+///
+/// The task itself goes to the trash rather than away, so its tags, dependencies and
+/// time entries can still be recovered from there.
+pub fn demote_task(
+    conn: &Connection,
+    task_id: &str,
+    target_task_id: &str,
+    parent_id: Option<String>,
+) -> Result<SubTask, AppError> {
+    let task = conn
+        .query_row("SELECT * FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![task_id], row_to_task)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Task not found").with_detail("id", task_id))?;
+    if task_id == target_task_id {
+        return Err(AppError::validation("A task can't be moved under itself")
+            .with_detail("field", "targetTaskId")
+            .with_detail("value", target_task_id));
+    }
+
+    change_log::in_transaction(conn, || {
+        let sub_task = create_sub_task(conn, target_task_id, CreateSubTaskInput {
+            title: task.title.clone(),
+            description: task.description.clone(),
+            priority: Some(task.priority.as_str().to_string()),
+            due_date: task.due_date.clone(),
+            sort_order: None,
+            parent_id,
+        }, None)?;
+        move_sub_trees(conn, "task_id = ?1 AND parent_id IS NULL", task_id, target_task_id, Some(&sub_task.id))?;
+
+        if task.status != TaskStatus::Todo {
+            change_log::tracked(conn, SUB_TASK, &sub_task.id, "complete", || {
+                conn.execute("UPDATE sub_tasks SET completed = 1 WHERE id = ?1", params![sub_task.id])
+                    .map_err(AppError::from)
+            })?;
+        }
+        delete_task(conn, task_id)?;
+
+        get_sub_task_tree(conn, &sub_task.id)
+    })
+}
+
+/// Move the sub-trees rooted at the sub-tasks matching `roots` (with `?1` bound to
+/// `root_param`) into `task_id`, hanging the roots under `parent_id`.
+///
+/// Rows move top-down so every parent is already in the new task when its children
+/// follow, as the same-task trigger on `sub_tasks` requires.
+fn move_sub_trees(
+    conn: &Connection,
+    roots: &str,
+    root_param: &str,
+    task_id: &str,
+    parent_id: Option<&str>,
+) -> Result<(), AppError> {
+    let sql = format!(
+        "WITH RECURSIVE tree(id, depth) AS (
+            SELECT id, 0 FROM sub_tasks WHERE {}
+            UNION ALL
+            SELECT s.id, tree.depth + 1 FROM sub_tasks s INNER JOIN tree ON s.parent_id = tree.id
+         )
+         SELECT id, depth FROM tree ORDER BY depth",
+        roots
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows: Vec<(String, i64)> = stmt
+        .query_map(params![root_param], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    for (id, depth) in rows {
+        change_log::tracked(conn, SUB_TASK, &id, "move", || {
+            if depth == 0 {
+                conn.execute(
+                    "UPDATE sub_tasks SET task_id = ?1, parent_id = ?2 WHERE id = ?3",
+                    params![task_id, parent_id, id],
+                )
+            } else {
+                conn.execute("UPDATE sub_tasks SET task_id = ?1 WHERE id = ?2", params![task_id, id])
+            }
+            .map_err(AppError::from)
+        })?;
     }
     Ok(())
 }
 
+/// A live sub-task with its live descendants filled in as `children`.
+fn get_sub_task_tree(conn: &Connection, id: &str) -> Result<SubTask, AppError> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE tree AS (
+            SELECT * FROM sub_tasks WHERE id = ?1 AND deleted_at IS NULL
+            UNION ALL
+            SELECT s.* FROM sub_tasks s INNER JOIN tree ON s.parent_id = tree.id WHERE s.deleted_at IS NULL
+         )
+         SELECT * FROM tree ORDER BY sort_order",
+    )?;
+    let flat: Vec<SubTask> = stmt
+        .query_map(params![id], row_to_sub_task)?
+        .collect::<Result<_, _>>()?;
+    build_sub_task_tree(flat)
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::not_found("Sub-task not found"))
+}
+
+/// `id` together with all of its descendants.
+fn sub_task_subtree_ids(conn: &Connection, id: &str) -> Result<Vec<String>, AppError> {
+    query_ids(
+        conn,
+        "WITH RECURSIVE subtree(id) AS (
+            SELECT ?1
+            UNION
+            SELECT s.id FROM sub_tasks s INNER JOIN subtree ON s.parent_id = subtree.id
+         )
+         SELECT id FROM subtree",
+        id,
+    )
+}
+
+/// `id` together with its parent, grandparent and so on up to the top level.
+fn sub_task_ancestor_ids(conn: &Connection, id: &str) -> Result<Vec<String>, AppError> {
+    query_ids(
+        conn,
+        "WITH RECURSIVE ancestors(id, parent_id) AS (
+            SELECT id, parent_id FROM sub_tasks WHERE id = ?1
+            UNION
+            SELECT s.id, s.parent_id FROM sub_tasks s INNER JOIN ancestors ON s.id = ancestors.parent_id
+         )
+         SELECT id FROM ancestors",
+        id,
+    )
+}
+
+fn query_ids(conn: &Connection, sql: &str, id: &str) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn
+        .prepare(sql)?;
+    let ids = stmt
+        .query_map(params![id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(ids)
+}
+
 // ============================================================
 // Helpers
 // ============================================================
 
 fn validate_title(title: &str) -> Result<(), AppError> {
     if title.trim().is_empty() {
-        return Err(AppError {
-            code: "VALIDATION_ERROR".into(),
-            message: "Title must not be empty or whitespace-only".into(),
-            details: None,
-        });
+        return Err(AppError::validation("Title must not be empty or whitespace-only"));
     }
     Ok(())
 }
+
+fn parse_priority(value: Option<&str>) -> Result<Option<Priority>, AppError> {
+    value.map(|p| Priority::parse("priority", p)).transpose()
+}
+
+/// Check a task's project and heading together. The project must be live, and the
+/// heading must belong to it; a heading given without a project brings its own.
+fn resolve_placement(
+    conn: &Connection,
+    project_id: Option<String>,
+    heading_id: Option<String>,
+) -> Result<(Option<String>, Option<String>), AppError> {
+    let Some(heading_id) = heading_id else {
+        if let Some(ref project_id) = project_id {
+            project::ensure_live(conn, project_id)?;
+        }
+        return Ok((project_id, None));
+    };
+
+    let heading_project: String = conn
+        .query_row(
+            "SELECT h.project_id FROM headings h INNER JOIN projects p ON p.id = h.project_id
+             WHERE h.id = ?1 AND p.deleted_at IS NULL",
+            params![heading_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Heading not found").with_detail("field", "headingId"))?;
+    if project_id.as_ref().is_some_and(|p| *p != heading_project) {
+        return Err(AppError::validation("Heading belongs to a different project")
+            .with_detail("field", "headingId")
+            .with_detail("value", heading_id));
+    }
+    Ok((Some(heading_project), Some(heading_id)))
+}
+
+/// Checks the date-like fields being set; `None` means "not being set".
+fn validate_schedule(
+    start_date: Option<&str>,
+    due_date: Option<&str>,
+    reminder_time: Option<&str>,
+    rule: Option<&RecurrenceRule>,
+) -> Result<(), AppError> {
+    if let Some(start) = start_date {
+        validate::date("startDate", start)?;
+    }
+    if let Some(due) = due_date {
+        validate::date("dueDate", due)?;
+    }
+    if let Some(reminder) = reminder_time {
+        validate::date_time("reminderTime", reminder)?;
+    }
+    if let Some(rule) = rule {
+        validate::recurrence_rule("recurrenceRule", rule)?;
+    }
+    Ok(())
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+
+    fn input(title: &str, priority: Option<&str>) -> CreateTaskInput {
+        CreateTaskInput { title: title.to_string(), priority: priority.map(String::from), ..Default::default() }
+    }
+
+    #[test]
+    fn test_invalid_priority_and_status_name_the_field() {
+        let conn = open_test_connection();
+        let err = create_task(&conn, input("Bad", Some("urgent"))).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ValidationError);
+        let details = err.details.unwrap();
+        assert_eq!(details["field"], "priority");
+        assert_eq!(details["value"], "urgent");
+
+        let t = create_task(&conn, input("Good", Some("high"))).unwrap();
+        assert_eq!(t.priority, Priority::High);
+        assert_eq!(t.status, TaskStatus::Todo);
+
+        let filter = TaskFilter { status: Some("done".into()), ..Default::default() };
+        assert_eq!(get_all_tasks(&conn, Some(filter)).unwrap_err().details.unwrap()["field"], "status");
+    }
+
+    #[test]
+    fn test_invalid_dates_and_rules_are_rejected_before_writing() {
+        let conn = open_test_connection();
+        let bad_due = CreateTaskInput { due_date: Some("31/01/2024".into()), ..input("Due", None) };
+        assert_eq!(create_task(&conn, bad_due).unwrap_err().details.unwrap()["field"], "dueDate");
+
+        let rule = RecurrenceRule { rule_type: "weekly".into(), interval: 1, days_of_week: Some(vec![]), day_of_month: None, end_date: None };
+        let bad_rule = CreateTaskInput { recurrence_rule: Some(rule), ..input("Rule", None) };
+        assert_eq!(create_task(&conn, bad_rule).unwrap_err().details.unwrap()["field"], "recurrenceRule.daysOfWeek");
+
+        let task = create_task(&conn, input("Fine", None)).unwrap();
+        let update = UpdateTaskInput { reminder_time: Some(Some("tomorrow".into())), ..Default::default() };
+        assert_eq!(update_task(&conn, &task.id, update).unwrap_err().details.unwrap()["field"], "reminderTime");
+
+        // Clearing is always allowed
+        let clear = UpdateTaskInput { due_date: Some(None), reminder_time: Some(None), ..Default::default() };
+        assert!(update_task(&conn, &task.id, clear).is_ok());
+        assert_eq!(get_all_tasks(&conn, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_unreadable_rows_are_reported() {
+        let conn = open_test_connection();
+        create_task(&conn, input("Fine", None)).unwrap();
+        conn.execute_batch(
+            "PRAGMA ignore_check_constraints = ON;
+             INSERT INTO tasks (id, title, status, created_at, updated_at) VALUES ('bad', 'Bad', 'archived', '', '');
+             PRAGMA ignore_check_constraints = OFF;",
+        )
+        .unwrap();
+
+        // A row that can't be mapped fails the read instead of silently vanishing
+        assert_eq!(get_all_tasks(&conn, None).unwrap_err().code, ErrorCode::DbError);
+    }
+
+    fn sub_task(conn: &Connection, task_id: &str, title: &str, parent_id: Option<&str>) -> SubTask {
+        create_sub_task(conn, task_id, CreateSubTaskInput {
+            title: title.to_string(),
+            parent_id: parent_id.map(String::from),
+            ..Default::default()
+        }, None)
+        .unwrap()
+    }
+
+    fn titles(sub_tasks: &[SubTask]) -> Vec<&str> {
+        sub_tasks.iter().map(|st| st.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_promote_and_demote_carry_the_subtree() {
+        let conn = open_test_connection();
+        let trip = create_task(&conn, input("Trip", None)).unwrap();
+        let pack = create_sub_task(&conn, &trip.id, CreateSubTaskInput {
+            title: "Pack".into(),
+            priority: Some("high".into()),
+            due_date: Some("2024-06-01".into()),
+            ..Default::default()
+        }, None)
+        .unwrap();
+        let clothes = sub_task(&conn, &trip.id, "Clothes", Some(&pack.id));
+        sub_task(&conn, &trip.id, "Socks", Some(&clothes.id));
+
+        let promoted = promote_sub_task(&conn, &pack.id).unwrap();
+        assert_eq!((promoted.priority, promoted.due_date.as_deref()), (Priority::High, Some("2024-06-01")));
+        let subs = promoted.sub_tasks.unwrap();
+        assert_eq!(titles(&subs), vec!["Clothes"]);
+        assert_eq!(titles(subs[0].children.as_ref().unwrap()), vec!["Socks"]);
+        assert!(get_task_by_id(&conn, &trip.id).unwrap().unwrap().sub_tasks.unwrap().is_empty());
+
+        let demoted = demote_task(&conn, &promoted.id, &trip.id, None).unwrap();
+        assert_eq!((demoted.title.as_str(), demoted.priority), ("Pack", Priority::High));
+        assert_eq!(titles(demoted.children.as_ref().unwrap()), vec!["Clothes"]);
+        assert!(get_task_by_id(&conn, &promoted.id).unwrap().is_none());
+        let subs = get_task_by_id(&conn, &trip.id).unwrap().unwrap().sub_tasks.unwrap();
+        assert_eq!(titles(subs[0].children.as_ref().unwrap()[0].children.as_ref().unwrap()), vec!["Socks"]);
+
+        let err = demote_task(&conn, &trip.id, &trip.id, None).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "targetTaskId");
+    }
+
+    #[test]
+    fn test_move_sub_task_reparents_and_renumbers() {
+        let conn = open_test_connection();
+        let (a, b) = (create_task(&conn, input("A", None)).unwrap(), create_task(&conn, input("B", None)).unwrap());
+        let one = sub_task(&conn, &a.id, "One", None);
+        let two = sub_task(&conn, &a.id, "Two", None);
+        let child = sub_task(&conn, &a.id, "Child", Some(&one.id));
+        let x = sub_task(&conn, &b.id, "X", None);
+        sub_task(&conn, &b.id, "Y", None);
+
+        // Within a task
+        move_sub_task(&conn, &two.id, &a.id, None, Some(0)).unwrap();
+        let subs = get_task_by_id(&conn, &a.id).unwrap().unwrap().sub_tasks.unwrap();
+        assert_eq!(titles(&subs), vec!["Two", "One"]);
+        assert_eq!(subs.iter().map(|st| st.sort_order).collect::<Vec<_>>(), vec![0, 1]);
+
+        // Across tasks, subtree included
+        let moved = move_sub_task(&conn, &one.id, &b.id, None, Some(1)).unwrap();
+        assert_eq!(titles(moved.children.as_ref().unwrap()), vec!["Child"]);
+        let subs = get_task_by_id(&conn, &b.id).unwrap().unwrap().sub_tasks.unwrap();
+        assert_eq!(titles(&subs), vec!["X", "One", "Y"]);
+        assert_eq!(subs[1].children.as_ref().unwrap()[0].task_id, b.id);
+
+        // Under another sub-task, past the end
+        move_sub_task(&conn, &two.id, &b.id, Some(x.id.clone()), Some(10)).unwrap();
+        let subs = get_task_by_id(&conn, &b.id).unwrap().unwrap().sub_tasks.unwrap();
+        assert_eq!(titles(subs[0].children.as_ref().unwrap()), vec!["Two"]);
+
+        let err = move_sub_task(&conn, &one.id, &b.id, Some(child.id.clone()), None).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        let err = move_sub_task(&conn, &one.id, &a.id, Some(x.id.clone()), None).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "newParentId");
+        let err = move_sub_task(&conn, &one.id, &b.id, None, Some(-1)).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "position");
+    }
+
+    #[test]
+    fn test_duplicate_copies_tree_and_tags_after_the_original() {
+        let conn = open_test_connection();
+        let other = create_task(&conn, input("Other", None)).unwrap();
+        let weekly = create_task(&conn, CreateTaskInput {
+            due_date: Some("2024-05-06".into()),
+            reminder_time: Some("2024-05-06T09:00:00+02:00".into()),
+            ..input("Weekly review", Some("high"))
+        })
+        .unwrap();
+        let inbox = sub_task(&conn, &weekly.id, "Inbox", None);
+        sub_task(&conn, &weekly.id, "Email", Some(&inbox.id));
+        update_sub_task(&conn, &inbox.id, UpdateSubTaskInput { completed: Some(true), ..Default::default() }).unwrap();
+        let t = tag::create_tag(&conn, "routine", None).unwrap();
+        tag::add_tag_to_task(&conn, &weekly.id, &t.id).unwrap();
+        complete_task(&conn, &weekly.id).unwrap();
+
+        let copy = duplicate_task(&conn, &weekly.id, DuplicateTaskOptions {
+            reset_completion: None,
+            date_offset_days: Some(7),
+        })
+        .unwrap();
+        assert_ne!(copy.id, weekly.id);
+        assert_eq!((copy.status, copy.priority), (TaskStatus::Todo, Priority::High));
+        assert_eq!(copy.due_date.as_deref(), Some("2024-05-13"));
+        assert_eq!(copy.reminder_time.as_deref(), Some("2024-05-13T09:00:00+02:00"));
+        assert_eq!(copy.tags.unwrap()[0].id, t.id);
+        let subs = copy.sub_tasks.unwrap();
+        assert!(!subs[0].completed);
+        assert_eq!(titles(subs[0].children.as_ref().unwrap()), vec!["Email"]);
+        assert_eq!(get_task_by_id(&conn, &weekly.id).unwrap().unwrap().sub_tasks.unwrap()[0].children.as_ref().unwrap().len(), 1);
+
+        // Right after the original, ahead of anything that followed it
+        let order: Vec<String> = get_all_tasks(&conn, None).unwrap().into_iter().map(|t| t.id).collect();
+        let position = |id: &str| order.iter().position(|o| o == id).unwrap();
+        assert_eq!(position(&copy.id), position(&weekly.id) + 1);
+        assert!(position(&other.id) > position(&copy.id));
+
+        let kept = duplicate_task(&conn, &weekly.id, DuplicateTaskOptions { reset_completion: Some(false), date_offset_days: None }).unwrap();
+        assert_eq!(kept.status, TaskStatus::Completed);
+        let err = duplicate_task(&conn, &weekly.id, DuplicateTaskOptions { reset_completion: None, date_offset_days: Some(i64::MAX) }).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "dateOffsetDays");
+    }
+
+    #[test]
+    fn test_duplicate_only_moves_the_tasks_in_the_way() {
+        let conn = open_test_connection();
+        let ids: Vec<String> = ["A", "B", "C", "Trashed"].iter().map(|t| create_task(&conn, input(t, None)).unwrap().id).collect();
+        for (id, order) in ids.iter().zip([0, 1, 5, 2]) {
+            conn.execute("UPDATE tasks SET sort_order = ?1 WHERE id = ?2", params![order, id]).unwrap();
+        }
+        delete_task(&conn, &ids[3]).unwrap();
+
+        duplicate_task(&conn, &ids[0], DuplicateTaskOptions::default()).unwrap();
+        let order_of = |id: &str| conn.query_row("SELECT sort_order FROM tasks WHERE id = ?1", params![id], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!([order_of(&ids[1]), order_of(&ids[2]), order_of(&ids[3])], [2, 5, 2]);
+        let reorders: i64 = conn
+            .query_row("SELECT COUNT(*) FROM change_log WHERE operation = 'reorder'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(reorders, 1);
+    }
+}
@@ -0,0 +1,509 @@
+use chrono::NaiveDate;
+use rusqlite::{Connection, params, OptionalExtension};
+use uuid::Uuid;
+use crate::models::*;
+use crate::services::change_log::{self, TEMPLATE};
+use crate::services::{tag, task};
+use crate::utils::validate;
+
+// Templates are saved as data rather than as tasks: using one creates a fresh task
+// through `create_task` / `create_sub_task`, filling in `{{placeholders}}` and
+// turning day offsets into dates.
+
+/// Read a JSON column, failing the row when it doesn't parse.
+fn json_column<T: serde::de::DeserializeOwned>(row: &rusqlite::Row, column: &str) -> rusqlite::Result<T> {
+    let raw: String = row.get(column)?;
+    serde_json::from_str(&raw).map_err(|e| {
+        let index = row.as_ref().column_index(column).unwrap_or_default();
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+pub(crate) fn row_to_template(row: &rusqlite::Row) -> rusqlite::Result<TaskTemplate> {
+    let mut template = TaskTemplate {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        title: row.get("title")?,
+        description: row.get("description")?,
+        priority: row.get("priority")?,
+        category_id: row.get("category_id")?,
+        start_offset_days: row.get("start_offset_days")?,
+        due_offset_days: row.get("due_offset_days")?,
+        tag_ids: json_column(row, "tag_ids")?,
+        sub_tasks: json_column(row, "sub_tasks")?,
+        placeholders: Vec::new(),
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    };
+    template.placeholders = placeholders(&template);
+    Ok(template)
+}
+
+fn get_template(conn: &Connection, id: &str) -> Result<TaskTemplate, AppError> {
+    conn.query_row("SELECT * FROM task_templates WHERE id = ?1", params![id], row_to_template)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Template not found").with_detail("id", id))
+}
+
+pub fn get_templates(conn: &Connection) -> Result<Vec<TaskTemplate>, AppError> {
+    let mut stmt = conn.prepare("SELECT * FROM task_templates ORDER BY name")?;
+    let templates: Vec<TaskTemplate> = stmt
+        .query_map([], row_to_template)?
+        .collect::<Result<_, _>>()?;
+    Ok(templates)
+}
+
+pub fn create_template(conn: &Connection, input: CreateTemplateInput) -> Result<TaskTemplate, AppError> {
+    let id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let template = TaskTemplate {
+        id: id.clone(),
+        name: input.name.trim().to_string(),
+        title: input.title.trim().to_string(),
+        description: input.description,
+        priority: parse_priority("priority", input.priority.as_deref())?,
+        category_id: input.category_id,
+        start_offset_days: input.start_offset_days,
+        due_offset_days: input.due_offset_days,
+        tag_ids: input.tag_ids.unwrap_or_default(),
+        sub_tasks: sub_task_tree("subTasks", input.sub_tasks.unwrap_or_default())?,
+        placeholders: Vec::new(),
+        created_at: now.clone(),
+        updated_at: now,
+    };
+    validate_template(conn, &template)?;
+
+    change_log::tracked(conn, TEMPLATE, &id, "create", || {
+        conn.execute(
+            "INSERT INTO task_templates (id, name, title, description, priority, category_id, start_offset_days, due_offset_days, tag_ids, sub_tasks, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)",
+            params![
+                template.id,
+                template.name,
+                template.title,
+                template.description,
+                template.priority,
+                template.category_id,
+                template.start_offset_days,
+                template.due_offset_days,
+                to_json(&template.tag_ids)?,
+                to_json(&template.sub_tasks)?,
+                template.created_at,
+            ],
+        )
+        .map_err(AppError::from)
+    })?;
+    get_template(conn, &id)
+}
+
+/// Save a task, with its live sub-task tree and its tags, as a template named `name`.
+/// Its dates become offsets from `base_date`, which defaults to the task's due date
+/// (or start date, or today).
+pub fn create_template_from_task(
+    conn: &Connection,
+    task_id: &str,
+    name: &str,
+    base_date: Option<String>,
+) -> Result<TaskTemplate, AppError> {
+    let task = task::get_task_by_id(conn, task_id)?
+        .ok_or_else(|| AppError::not_found("Task not found").with_detail("id", task_id))?;
+    if let Some(ref base) = base_date {
+        validate::date("baseDate", base)?;
+    }
+    let base = base_date
+        .or_else(|| task.due_date.clone())
+        .or_else(|| task.start_date.clone())
+        .and_then(|d| NaiveDate::parse_from_str(&d, validate::DATE_FORMAT).ok())
+        .unwrap_or_else(|| chrono::Local::now().date_naive());
+
+    fn to_input(sub_task: &SubTask, base: NaiveDate) -> TemplateSubTaskInput {
+        TemplateSubTaskInput {
+            title: sub_task.title.clone(),
+            description: sub_task.description.clone(),
+            priority: Some(sub_task.priority.as_str().to_string()),
+            due_offset_days: offset_from(sub_task.due_date.as_deref(), base),
+            children: Some(sub_task.children.iter().flatten().map(|c| to_input(c, base)).collect()),
+        }
+    }
+
+    create_template(conn, CreateTemplateInput {
+        name: name.to_string(),
+        title: task.title.clone(),
+        description: task.description.clone(),
+        priority: Some(task.priority.as_str().to_string()),
+        category_id: task.category_id.clone(),
+        start_offset_days: offset_from(task.start_date.as_deref(), base),
+        due_offset_days: offset_from(task.due_date.as_deref(), base),
+        tag_ids: Some(task.tags.iter().flatten().map(|t| t.id.clone()).collect()),
+        sub_tasks: Some(task.sub_tasks.iter().flatten().map(|st| to_input(st, base)).collect()),
+    })
+}
+
+pub fn update_template(conn: &Connection, id: &str, input: UpdateTemplateInput) -> Result<TaskTemplate, AppError> {
+    let mut template = get_template(conn, id)?;
+    if let Some(name) = input.name {
+        template.name = name.trim().to_string();
+    }
+    if let Some(title) = input.title {
+        template.title = title.trim().to_string();
+    }
+    if let Some(description) = input.description {
+        template.description = description;
+    }
+    if input.priority.is_some() {
+        template.priority = parse_priority("priority", input.priority.as_deref())?;
+    }
+    if let Some(category_id) = input.category_id {
+        template.category_id = category_id;
+    }
+    if let Some(offset) = input.start_offset_days {
+        template.start_offset_days = offset;
+    }
+    if let Some(offset) = input.due_offset_days {
+        template.due_offset_days = offset;
+    }
+    if let Some(tag_ids) = input.tag_ids {
+        template.tag_ids = tag_ids;
+    }
+    if let Some(sub_tasks) = input.sub_tasks {
+        template.sub_tasks = sub_task_tree("subTasks", sub_tasks)?;
+    }
+    validate_template(conn, &template)?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    change_log::tracked(conn, TEMPLATE, id, "update", || {
+        conn.execute(
+            "UPDATE task_templates SET name = ?1, title = ?2, description = ?3, priority = ?4, category_id = ?5,
+                 start_offset_days = ?6, due_offset_days = ?7, tag_ids = ?8, sub_tasks = ?9, updated_at = ?10
+             WHERE id = ?11",
+            params![
+                template.name,
+                template.title,
+                template.description,
+                template.priority,
+                template.category_id,
+                template.start_offset_days,
+                template.due_offset_days,
+                to_json(&template.tag_ids)?,
+                to_json(&template.sub_tasks)?,
+                now,
+                id,
+            ],
+        )
+        .map_err(AppError::from)
+    })?;
+    get_template(conn, id)
+}
+
+pub fn delete_template(conn: &Connection, id: &str) -> Result<(), AppError> {
+    let changes = change_log::tracked(conn, TEMPLATE, id, "delete", || {
+        conn.execute("DELETE FROM task_templates WHERE id = ?1", params![id])
+            .map_err(AppError::from)
+    })?;
+    if changes == 0 {
+        return Err(AppError::not_found("Template not found").with_detail("id", id));
+    }
+    Ok(())
+}
+
+// ============================================================
+// Instantiate
+// ============================================================
+
+/// Create a task from a template. Placeholders are filled from `input.values`, with
+/// `{{date}}` standing for the base date unless given; offsets count from the base
+/// date. Tags that no longer exist are skipped.
+pub fn instantiate_template(conn: &Connection, id: &str, input: InstantiateTemplateInput) -> Result<Task, AppError> {
+    let template = get_template(conn, id)?;
+    let base = match input.base_date {
+        Some(ref date) => {
+            validate::date("baseDate", date)?;
+            NaiveDate::parse_from_str(date, validate::DATE_FORMAT).unwrap_or_default()
+        }
+        None => chrono::Local::now().date_naive(),
+    };
+    let mut values = input.values.unwrap_or_default();
+    values
+        .entry("date".to_string())
+        .or_insert_with(|| base.format(validate::DATE_FORMAT).to_string());
+    let fill = |text: &str| {
+        substitute(text, |name| {
+            values.get(name).cloned().ok_or_else(|| {
+                AppError::validation(format!("No value for {{{{{}}}}}", name))
+                    .with_detail("field", "values")
+                    .with_detail("value", name)
+            })
+        })
+    };
+
+    change_log::in_transaction(conn, || {
+        let created = task::create_task(conn, CreateTaskInput {
+            title: fill(&template.title)?,
+            description: template.description.as_deref().map(fill).transpose()?,
+            priority: Some(template.priority.as_str().to_string()),
+            category_id: template.category_id.clone(),
+            due_date: date_at(base, template.due_offset_days, "dueOffsetDays")?,
+            reminder_time: None,
+            recurrence_rule: None,
+            start_date: date_at(base, template.start_offset_days, "startOffsetDays")?,
+            someday: None,
+            project_id: input.project_id.clone(),
+            heading_id: input.heading_id.clone(),
+        })?;
+
+        for tag_id in &template.tag_ids {
+            let exists: bool = conn
+                .query_row("SELECT COUNT(*) > 0 FROM tags WHERE id = ?1 AND deleted_at IS NULL", params![tag_id], |row| row.get(0))?;
+            if exists {
+                tag::add_tag_to_task(conn, &created.id, tag_id)?;
+            }
+        }
+
+        let mut pending: Vec<(Option<String>, &TemplateSubTask)> =
+            template.sub_tasks.iter().rev().map(|st| (None, st)).collect();
+        while let Some((parent_id, item)) = pending.pop() {
+            let sub_task = task::create_sub_task(conn, &created.id, CreateSubTaskInput {
+                title: fill(&item.title)?,
+                description: item.description.as_deref().map(fill).transpose()?,
+                priority: Some(item.priority.as_str().to_string()),
+                due_date: date_at(base, item.due_offset_days, "subTasks.dueOffsetDays")?,
+                sort_order: None,
+                parent_id,
+            }, None)?;
+            pending.extend(item.children.iter().rev().map(|child| (Some(sub_task.id.clone()), child)));
+        }
+
+        task::get_task_by_id(conn, &created.id)?
+            .ok_or_else(|| AppError::new(ErrorCode::DbError, "Failed to read created task"))
+    })
+}
+
+/// Replace every `{{name}}` in `text` with `value_of(name)`. Names are trimmed, so
+/// `{{ name }}` works too; an unclosed `{{` is left as it is.
+fn substitute(text: &str, mut value_of: impl FnMut(&str) -> Result<String, AppError>) -> Result<String, AppError> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else { break };
+        out.push_str(&rest[..start]);
+        out.push_str(&value_of(rest[start + 2..start + 2 + len].trim())?);
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Placeholder names a template asks for, leaving out the built-in `date`.
+fn placeholders(template: &TaskTemplate) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut collect = |text: &str| {
+        let _ = substitute(text, |name| {
+            if name != "date" && !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+            Ok(String::new())
+        });
+    };
+
+    collect(&template.title);
+    collect(template.description.as_deref().unwrap_or(""));
+    let mut pending: Vec<&TemplateSubTask> = template.sub_tasks.iter().rev().collect();
+    while let Some(item) = pending.pop() {
+        collect(&item.title);
+        collect(item.description.as_deref().unwrap_or(""));
+        pending.extend(item.children.iter().rev());
+    }
+    names
+}
+
+// ============================================================
+// Helpers
+// ============================================================
+
+fn validate_template(conn: &Connection, template: &TaskTemplate) -> Result<(), AppError> {
+    if template.name.is_empty() {
+        return Err(AppError::validation("Template name must not be empty").with_detail("field", "name"));
+    }
+    if template.title.is_empty() {
+        return Err(AppError::validation("Title must not be empty or whitespace-only").with_detail("field", "title"));
+    }
+    let duplicate: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM task_templates WHERE name = ?1 AND id != ?2",
+        params![template.name, template.id],
+        |row| row.get(0),
+    )?;
+    if duplicate {
+        return Err(AppError::validation("Template name already exists")
+            .with_detail("field", "name")
+            .with_detail("value", template.name.as_str()));
+    }
+    Ok(())
+}
+
+fn parse_priority(field: &str, value: Option<&str>) -> Result<Priority, AppError> {
+    value.map(|v| Priority::parse(field, v)).transpose().map(Option::unwrap_or_default)
+}
+
+/// Check and convert a sub-task tree from its input form. `field` names the list,
+/// e.g. `subTasks[0].children`, so errors point at the offending item.
+fn sub_task_tree(field: &str, items: Vec<TemplateSubTaskInput>) -> Result<Vec<TemplateSubTask>, AppError> {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let field = format!("{}[{}]", field, i);
+            if item.title.trim().is_empty() {
+                return Err(AppError::validation("Title must not be empty or whitespace-only")
+                    .with_detail("field", format!("{}.title", field)));
+            }
+            Ok(TemplateSubTask {
+                title: item.title.trim().to_string(),
+                description: item.description,
+                priority: parse_priority(&format!("{}.priority", field), item.priority.as_deref())?,
+                due_offset_days: item.due_offset_days,
+                children: sub_task_tree(&format!("{}.children", field), item.children.unwrap_or_default())?,
+            })
+        })
+        .collect()
+}
+
+/// Days from `base` to the `YYYY-MM-DD` date `value`.
+fn offset_from(value: Option<&str>, base: NaiveDate) -> Option<i64> {
+    let date = NaiveDate::parse_from_str(value?, validate::DATE_FORMAT).ok()?;
+    Some((date - base).num_days())
+}
+
+/// `base` moved by `offset` days, as a `YYYY-MM-DD` date.
+fn date_at(base: NaiveDate, offset: Option<i64>, field: &str) -> Result<Option<String>, AppError> {
+    let Some(offset) = offset else { return Ok(None) };
+    chrono::Duration::try_days(offset)
+        .and_then(|d| base.checked_add_signed(d))
+        .map(|d| Some(d.format(validate::DATE_FORMAT).to_string()))
+        .ok_or_else(|| {
+            AppError::validation(format!("{} moves the date out of range", field))
+                .with_detail("field", field)
+                .with_detail("value", offset.to_string())
+        })
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string(value).map_err(AppError::from)
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+    use std::collections::HashMap;
+
+    fn item(title: &str, due_offset_days: Option<i64>, children: Vec<TemplateSubTaskInput>) -> TemplateSubTaskInput {
+        TemplateSubTaskInput {
+            title: title.to_string(),
+            description: None,
+            priority: None,
+            due_offset_days,
+            children: Some(children),
+        }
+    }
+
+    #[test]
+    fn test_instantiate_fills_placeholders_and_dates() {
+        let conn = open_test_connection();
+        let t = tag::create_tag(&conn, "release", None).unwrap();
+        let template = create_template(&conn, CreateTemplateInput {
+            name: "Release".into(),
+            title: "Release {{version}}".into(),
+            description: Some("Cut on {{ date }}".into()),
+            priority: Some("high".into()),
+            category_id: None,
+            start_offset_days: Some(-2),
+            due_offset_days: Some(0),
+            tag_ids: Some(vec![t.id.clone()]),
+            sub_tasks: Some(vec![
+                item("Freeze {{branch}}", Some(-2), vec![item("Tell {{team}}", None, vec![])]),
+                item("Publish", None, vec![]),
+            ]),
+        })
+        .unwrap();
+        assert_eq!(template.placeholders, vec!["version", "branch", "team"]);
+
+        let values: HashMap<String, String> = [("version", "2.1"), ("branch", "main"), ("team", "QA")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let created = instantiate_template(&conn, &template.id, InstantiateTemplateInput {
+            base_date: Some("2024-07-10".into()),
+            values: Some(values),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(created.title, "Release 2.1");
+        assert_eq!(created.description.as_deref(), Some("Cut on 2024-07-10"));
+        assert_eq!((created.start_date.as_deref(), created.due_date.as_deref()), (Some("2024-07-08"), Some("2024-07-10")));
+        assert_eq!((created.priority, created.tags.unwrap()[0].id.clone()), (Priority::High, t.id));
+        let subs = created.sub_tasks.unwrap();
+        assert_eq!(subs.iter().map(|st| st.title.as_str()).collect::<Vec<_>>(), vec!["Freeze main", "Publish"]);
+        assert_eq!(subs[0].due_date.as_deref(), Some("2024-07-08"));
+        assert_eq!(subs[0].children.as_ref().unwrap()[0].title, "Tell QA");
+
+        let err = instantiate_template(&conn, &template.id, InstantiateTemplateInput::default()).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "values");
+    }
+
+    #[test]
+    fn test_save_task_as_template_and_edit_it() {
+        let conn = open_test_connection();
+        let source = task::create_task(&conn, CreateTaskInput {
+            title: "Onboard".into(),
+            priority: Some("medium".into()),
+            due_date: Some("2024-03-08".into()),
+            start_date: Some("2024-03-04".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        task::create_sub_task(&conn, &source.id, CreateSubTaskInput {
+            title: "Laptop".into(),
+            due_date: Some("2024-03-05".into()),
+            ..Default::default()
+        }, None)
+        .unwrap();
+
+        let template = create_template_from_task(&conn, &source.id, "Onboarding", None).unwrap();
+        assert_eq!((template.start_offset_days, template.due_offset_days), (Some(-4), Some(0)));
+        assert_eq!((template.sub_tasks[0].title.as_str(), template.sub_tasks[0].due_offset_days), ("Laptop", Some(-3)));
+
+        let err = create_template_from_task(&conn, &source.id, " Onboarding ", None).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "name");
+        let err = update_template(&conn, &template.id, UpdateTemplateInput {
+            sub_tasks: Some(vec![item("Desk", None, vec![item(" ", None, vec![])])]),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "subTasks[0].children[0].title");
+
+        let updated = update_template(&conn, &template.id, UpdateTemplateInput {
+            due_offset_days: Some(None),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(updated.due_offset_days, None);
+        delete_template(&conn, &template.id).unwrap();
+        assert!(get_templates(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_template_json_fails_the_read() {
+        let conn = open_test_connection();
+        conn.execute(
+            "INSERT INTO task_templates (id, name, title, sub_tasks, created_at, updated_at)
+             VALUES ('bad', 'Bad', 'Bad', '[{\"title\":', '', '')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(get_templates(&conn).unwrap_err().code, ErrorCode::DbError);
+    }
+}
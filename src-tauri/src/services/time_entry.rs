@@ -0,0 +1,270 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use rusqlite::{Connection, params, OptionalExtension};
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::models::*;
+use crate::services::change_log::{self, TIME_ENTRY};
+use crate::utils::validate;
+
+// A running timer is just an entry without `ended_at`; a partial unique index keeps
+// it to one at a time. Since it lives in the database, it keeps running across
+// restarts until `stop_timer`.
+
+pub(crate) fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<TimeEntry> {
+    let started_at: String = row.get("started_at")?;
+    let ended_at: Option<String> = row.get("ended_at")?;
+    Ok(TimeEntry {
+        id: row.get("id")?,
+        task_id: row.get("task_id")?,
+        duration_seconds: seconds_between(&started_at, ended_at.as_deref()),
+        started_at,
+        ended_at,
+        note: row.get("note")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+fn parse(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value).ok()
+}
+
+/// Seconds from `start` to `end`, or to now while running. Unparsable timestamps
+/// (only possible in hand-edited data) count as nothing.
+pub(crate) fn seconds_between(start: &str, end: Option<&str>) -> i64 {
+    let Some(start) = parse(start) else { return 0 };
+    let end = match end {
+        Some(end) => parse(end),
+        None => Some(Utc::now().fixed_offset()),
+    };
+    end.map_or(0, |end| (end - start).num_seconds().max(0))
+}
+
+fn ensure_task(conn: &Connection, task_id: &str) -> Result<(), AppError> {
+    let exists: bool = conn
+        .query_row("SELECT COUNT(*) > 0 FROM tasks WHERE id = ?1 AND deleted_at IS NULL", params![task_id], |row| row.get(0))?;
+    if !exists {
+        return Err(AppError::not_found("Task not found").with_detail("id", task_id));
+    }
+    Ok(())
+}
+
+fn validate_span(started_at: &str, ended_at: &str) -> Result<(), AppError> {
+    validate::date_time("startedAt", started_at)?;
+    validate::date_time("endedAt", ended_at)?;
+    if parse(ended_at) <= parse(started_at) {
+        return Err(AppError::validation("endedAt must be after startedAt")
+            .with_detail("field", "endedAt")
+            .with_detail("value", ended_at));
+    }
+    Ok(())
+}
+
+fn get_entry(conn: &Connection, id: &str) -> Result<TimeEntry, AppError> {
+    conn.query_row("SELECT * FROM time_entries WHERE id = ?1", params![id], row_to_entry)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("Time entry not found"))
+}
+
+// ============================================================
+// Timer
+// ============================================================
+
+pub fn get_running_timer(conn: &Connection) -> Result<Option<TimeEntry>, AppError> {
+    conn.query_row("SELECT * FROM time_entries WHERE ended_at IS NULL", [], row_to_entry)
+        .optional()
+        .map_err(AppError::from)
+}
+
+/// Start timing `task_id`. Fails with `Conflict` while another timer is running.
+pub fn start_timer(conn: &Connection, task_id: &str, note: Option<String>) -> Result<TimeEntry, AppError> {
+    ensure_task(conn, task_id)?;
+    if let Some(running) = get_running_timer(conn)? {
+        return Err(AppError::new(ErrorCode::Conflict, "A timer is already running")
+            .with_detail("timeEntryId", running.id)
+            .with_detail("taskId", running.task_id));
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    change_log::tracked(conn, TIME_ENTRY, &id, "create", || {
+        conn.execute(
+            "INSERT INTO time_entries (id, task_id, started_at, ended_at, note, created_at, updated_at)
+             VALUES (?1, ?2, ?3, NULL, ?4, ?3, ?3)",
+            params![id, task_id, now, note],
+        )
+        .map_err(AppError::from)
+    })?;
+    get_entry(conn, &id)
+}
+
+/// Stop the running timer, if any, and return the finished entry.
+pub fn stop_timer(conn: &Connection) -> Result<Option<TimeEntry>, AppError> {
+    let Some(running) = get_running_timer(conn)? else { return Ok(None) };
+    let now = Utc::now().to_rfc3339();
+    change_log::tracked(conn, TIME_ENTRY, &running.id, "update", || {
+        conn.execute(
+            "UPDATE time_entries SET ended_at = ?1, updated_at = ?1 WHERE id = ?2",
+            params![now, running.id],
+        )
+        .map_err(AppError::from)
+    })?;
+    get_entry(conn, &running.id).map(Some)
+}
+
+// ============================================================
+// Entries
+// ============================================================
+
+/// A task's entries, most recent first.
+pub fn get_time_entries(conn: &Connection, task_id: &str) -> Result<Vec<TimeEntry>, AppError> {
+    let mut stmt = conn.prepare("SELECT * FROM time_entries WHERE task_id = ?1 ORDER BY started_at DESC")?;
+    let entries: Vec<TimeEntry> = stmt
+        .query_map(params![task_id], row_to_entry)?
+        .collect::<Result<_, _>>()?;
+    Ok(entries)
+}
+
+pub fn create_time_entry(conn: &Connection, input: CreateTimeEntryInput) -> Result<TimeEntry, AppError> {
+    ensure_task(conn, &input.task_id)?;
+    validate_span(&input.started_at, &input.ended_at)?;
+
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    change_log::tracked(conn, TIME_ENTRY, &id, "create", || {
+        conn.execute(
+            "INSERT INTO time_entries (id, task_id, started_at, ended_at, note, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            params![id, input.task_id, input.started_at, input.ended_at, input.note, now],
+        )
+        .map_err(AppError::from)
+    })?;
+    get_entry(conn, &id)
+}
+
+/// Edit an entry's times or note. A running entry can have its start moved, but
+/// only `stop_timer` or an explicit `ended_at` finishes it.
+pub fn update_time_entry(conn: &Connection, id: &str, input: UpdateTimeEntryInput) -> Result<TimeEntry, AppError> {
+    let existing = get_entry(conn, id)?;
+    let started_at = input.started_at.unwrap_or(existing.started_at);
+    match input.ended_at.as_deref().or(existing.ended_at.as_deref()) {
+        Some(ended_at) => validate_span(&started_at, ended_at)?,
+        None => validate::date_time("startedAt", &started_at)?,
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let note = input.note.unwrap_or(existing.note);
+    change_log::tracked(conn, TIME_ENTRY, id, "update", || {
+        conn.execute(
+            "UPDATE time_entries SET started_at = ?1, ended_at = COALESCE(?2, ended_at), note = ?3, updated_at = ?4
+             WHERE id = ?5",
+            params![started_at, input.ended_at, note, now, id],
+        )
+        .map_err(AppError::from)
+    })?;
+    get_entry(conn, id)
+}
+
+pub fn delete_time_entry(conn: &Connection, id: &str) -> Result<(), AppError> {
+    let changes = change_log::tracked(conn, TIME_ENTRY, id, "delete", || {
+        conn.execute("DELETE FROM time_entries WHERE id = ?1", params![id])
+            .map_err(AppError::from)
+    })?;
+    if changes == 0 {
+        return Err(AppError::not_found("Time entry not found"));
+    }
+    Ok(())
+}
+
+/// Fill in `time_spent_seconds` for the given tasks.
+pub fn attach_time_spent(conn: &Connection, tasks: &mut [Task]) -> Result<(), AppError> {
+    if tasks.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    let placeholders = (1..=ids.len()).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(",");
+    let sql = format!("SELECT task_id, started_at, ended_at FROM time_entries WHERE task_id IN ({})", placeholders);
+    let mut stmt = conn.prepare(&sql)?;
+    let mut totals: HashMap<String, i64> = HashMap::new();
+    let rows = stmt.query_map(rusqlite::params_from_iter(&ids), |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+    })?;
+    for row in rows {
+        let (task_id, started_at, ended_at) = row?;
+        *totals.entry(task_id).or_default() += seconds_between(&started_at, ended_at.as_deref());
+    }
+
+    for task in tasks.iter_mut() {
+        task.time_spent_seconds = Some(totals.get(&task.id).copied().unwrap_or(0));
+    }
+    Ok(())
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_test_task, open_test_connection};
+    use crate::services::task;
+
+    fn entry(task_id: &str, started_at: &str, ended_at: &str) -> CreateTimeEntryInput {
+        CreateTimeEntryInput {
+            task_id: task_id.to_string(),
+            started_at: started_at.to_string(),
+            ended_at: ended_at.to_string(),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_only_one_timer_runs_at_a_time() {
+        let conn = open_test_connection();
+        let (a, b) = (create_test_task(&conn, "A"), create_test_task(&conn, "B"));
+
+        let running = start_timer(&conn, &a.id, None).unwrap();
+        assert!(running.ended_at.is_none());
+        let err = start_timer(&conn, &b.id, None).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        assert_eq!(err.details.unwrap()["taskId"], a.id.as_str());
+        assert_eq!(get_running_timer(&conn).unwrap().unwrap().id, running.id);
+
+        let stopped = stop_timer(&conn).unwrap().unwrap();
+        assert_eq!(stopped.id, running.id);
+        assert!(stopped.ended_at.is_some());
+        assert!(stop_timer(&conn).unwrap().is_none());
+        start_timer(&conn, &b.id, None).unwrap();
+    }
+
+    #[test]
+    fn test_manual_entries_and_task_totals() {
+        let conn = open_test_connection();
+        let t = create_test_task(&conn, "Invoice");
+        create_time_entry(&conn, entry(&t.id, "2024-03-01T09:00:00Z", "2024-03-01T10:30:00Z")).unwrap();
+        let e = create_time_entry(&conn, entry(&t.id, "2024-03-02T09:00:00+01:00", "2024-03-02T09:15:00+01:00")).unwrap();
+        assert_eq!(e.duration_seconds, 15 * 60);
+
+        let err = create_time_entry(&conn, entry(&t.id, "2024-03-01T10:00:00Z", "2024-03-01T09:00:00Z")).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "endedAt");
+        let err = create_time_entry(&conn, entry(&t.id, "yesterday", "2024-03-01T09:00:00Z")).unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "startedAt");
+
+        let e = update_time_entry(&conn, &e.id, UpdateTimeEntryInput {
+            ended_at: Some("2024-03-02T09:30:00+01:00".into()),
+            note: Some(Some("call".into())),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!((e.duration_seconds, e.note.as_deref()), (30 * 60, Some("call")));
+
+        let loaded = task::get_task_by_id(&conn, &t.id).unwrap().unwrap();
+        assert_eq!(loaded.time_spent_seconds, Some(2 * 3600));
+        assert_eq!(task::get_all_tasks(&conn, None).unwrap()[0].time_spent_seconds, Some(2 * 3600));
+
+        delete_time_entry(&conn, &e.id).unwrap();
+        assert_eq!(get_time_entries(&conn, &t.id).unwrap().len(), 1);
+        assert_eq!(delete_time_entry(&conn, &e.id).unwrap_err().code, ErrorCode::NotFound);
+    }
+}
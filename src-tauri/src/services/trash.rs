@@ -0,0 +1,207 @@
+use rusqlite::{Connection, params};
+use crate::models::*;
+use crate::services::{project, settings, task};
+use crate::services::change_log::{self, CATEGORY, NOTE, PROJECT, SUB_TASK, TAG, TASK};
+
+/// Trashable tables with their `change_log` entity type, purged in this order.
+const TRASH_TABLES: [(&str, &str); 6] = [
+    ("tasks", TASK),
+    ("sub_tasks", SUB_TASK),
+    ("notes", NOTE),
+    ("categories", CATEGORY),
+    ("tags", TAG),
+    ("projects", PROJECT),
+];
+
+/// Everything currently in the trash, most recently deleted first.
+///
+/// Sub-tasks are listed only at the root of each trashed subtree, and not at all
+/// when their task is trashed — they come back with the task. Tasks trashed along
+/// with their project are likewise left to the project.
+pub fn get_trash(conn: &Connection) -> Result<Vec<TrashItem>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT 'task', t.id, t.title, NULL, t.deleted_at FROM tasks t
+                LEFT JOIN projects p ON p.id = t.project_id
+                WHERE t.deleted_at IS NOT NULL AND (p.id IS NULL OR p.deleted_at IS NULL OR p.deleted_at != t.deleted_at)
+             UNION ALL
+             SELECT 'subTask', s.id, s.title, s.task_id, s.deleted_at FROM sub_tasks s
+                INNER JOIN tasks t ON t.id = s.task_id
+                LEFT JOIN sub_tasks p ON p.id = s.parent_id
+                WHERE s.deleted_at IS NOT NULL AND t.deleted_at IS NULL
+                  AND (p.id IS NULL OR p.deleted_at IS NULL OR p.deleted_at != s.deleted_at)
+             UNION ALL
+             SELECT 'note', id, title, NULL, deleted_at FROM notes WHERE deleted_at IS NOT NULL
+             UNION ALL
+             SELECT 'category', id, name, NULL, deleted_at FROM categories WHERE deleted_at IS NOT NULL
+             UNION ALL
+             SELECT 'tag', id, name, NULL, deleted_at FROM tags WHERE deleted_at IS NOT NULL
+             UNION ALL
+             SELECT 'project', id, title, NULL, deleted_at FROM projects WHERE deleted_at IS NOT NULL
+             ORDER BY 5 DESC",
+        )?;
+
+    let items: Vec<TrashItem> = stmt
+        .query_map([], |row| {
+            Ok(TrashItem {
+                entity_type: row.get(0)?,
+                id: row.get(1)?,
+                title: row.get(2)?,
+                task_id: row.get(3)?,
+                deleted_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(items)
+}
+
+pub fn restore_from_trash(conn: &Connection, entity_type: &str, id: &str) -> Result<(), AppError> {
+    let table = match entity_type {
+        SUB_TASK => return task::restore_sub_task(conn, id),
+        PROJECT => return project::restore_project(conn, id),
+        TASK => "tasks",
+        NOTE => "notes",
+        CATEGORY => "categories",
+        TAG => "tags",
+        _ => {
+            return Err(AppError::validation(format!("Unknown trash entity type: {}", entity_type)))
+        }
+    };
+
+    let changes = change_log::tracked(conn, entity_type, id, "restore", || {
+        conn.execute(
+            &format!("UPDATE {} SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL", table),
+            params![id],
+        )
+        .map_err(AppError::from)
+    })?;
+    if changes == 0 {
+        return Err(AppError::not_found("Item not found in trash"));
+    }
+    Ok(())
+}
+
+/// Permanently delete trashed rows deleted before `cutoff` (RFC 3339), or everything
+/// in the trash when `cutoff` is `None`. Returns the number of rows removed.
+///
+/// Purging a task cascades to its sub-tasks and tag links; purging a project takes
+/// its headings with it; purging a category un-assigns its tasks.
+pub fn purge_trash(conn: &Connection, cutoff: Option<&str>) -> Result<usize, AppError> {
+    change_log::in_transaction(conn, || {
+        let mut removed = 0;
+        for (table, entity_type) in TRASH_TABLES {
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT id FROM {} WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR deleted_at < ?1)",
+                    table
+                ))?;
+            let ids: Vec<String> = stmt
+                .query_map(params![cutoff], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+
+            for id in ids {
+                removed += change_log::tracked(conn, entity_type, &id, "purge", || {
+                    conn.execute(&format!("DELETE FROM {} WHERE id = ?1", table), params![id])
+                        .map_err(AppError::from)
+                })?;
+            }
+        }
+        Ok(removed)
+    })
+}
+
+/// Purge items that have been in the trash longer than the configured retention.
+pub fn purge_expired(conn: &Connection) -> Result<usize, AppError> {
+    let days = settings::get_settings(conn)?.trash_retention_days;
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+    purge_trash(conn, Some(&cutoff))
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_test_task, open_test_connection};
+    use crate::services::{note, tag};
+
+    fn new_sub_task(conn: &Connection, task_id: &str, parent_id: Option<&str>, title: &str) -> SubTask {
+        task::create_sub_task(conn, task_id, CreateSubTaskInput {
+            title: title.to_string(),
+            parent_id: parent_id.map(|p| p.to_string()),
+            ..Default::default()
+        }, None)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_deleted_task_is_hidden_and_restored_with_relations() {
+        let conn = open_test_connection();
+        let t = create_test_task(&conn, "Write report");
+        let root = new_sub_task(&conn, &t.id, None, "Outline");
+        new_sub_task(&conn, &t.id, Some(&root.id), "Intro");
+        let work = tag::create_tag(&conn, "work", None).unwrap();
+        tag::add_tag_to_task(&conn, &t.id, &work.id).unwrap();
+
+        task::delete_task(&conn, &t.id).unwrap();
+        assert!(task::get_all_tasks(&conn, None).unwrap().is_empty());
+        assert!(task::get_task_by_id(&conn, &t.id).unwrap().is_none());
+        let trash = get_trash(&conn).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].entity_type, TASK);
+
+        restore_from_trash(&conn, TASK, &t.id).unwrap();
+        let restored = task::get_task_by_id(&conn, &t.id).unwrap().unwrap();
+        let subs = restored.sub_tasks.unwrap();
+        assert_eq!(subs.len(), 1);
+        assert_eq!(subs[0].children.as_ref().unwrap().len(), 1);
+        assert_eq!(restored.tags.unwrap().len(), 1);
+        assert!(get_trash(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sub_task_subtree_round_trip() {
+        let conn = open_test_connection();
+        let t = create_test_task(&conn, "Plan trip");
+        let root = new_sub_task(&conn, &t.id, None, "Book");
+        let child = new_sub_task(&conn, &t.id, Some(&root.id), "Flights");
+        new_sub_task(&conn, &t.id, Some(&child.id), "Seats");
+
+        task::delete_sub_task(&conn, &root.id).unwrap();
+        let loaded = task::get_task_by_id(&conn, &t.id).unwrap().unwrap();
+        assert!(loaded.sub_tasks.unwrap().is_empty());
+        // Only the root of the trashed subtree is listed
+        let trash = get_trash(&conn).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, root.id);
+        assert_eq!(trash[0].task_id.as_deref(), Some(t.id.as_str()));
+
+        restore_from_trash(&conn, SUB_TASK, &root.id).unwrap();
+        let loaded = task::get_task_by_id(&conn, &t.id).unwrap().unwrap();
+        let subs = loaded.sub_tasks.unwrap();
+        assert_eq!(subs.len(), 1);
+        assert_eq!(subs[0].children.as_ref().unwrap()[0].children.as_ref().unwrap().len(), 1);
+        assert!(get_trash(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_purge_respects_cutoff() {
+        let conn = open_test_connection();
+        let t = create_test_task(&conn, "Old");
+        new_sub_task(&conn, &t.id, None, "Step");
+        let n = note::create_note(&conn, CreateNoteInput { title: Some("Recent".into()), content: None }).unwrap();
+        task::delete_task(&conn, &t.id).unwrap();
+        note::delete_note(&conn, &n.id).unwrap();
+        conn.execute("UPDATE tasks SET deleted_at = '2000-01-01T00:00:00+00:00'", []).unwrap();
+
+        assert_eq!(purge_expired(&conn).unwrap(), 1);
+        let sub_tasks: i64 = conn.query_row("SELECT COUNT(*) FROM sub_tasks", [], |row| row.get(0)).unwrap();
+        assert_eq!(sub_tasks, 0);
+        assert_eq!(get_trash(&conn).unwrap().len(), 1);
+
+        assert_eq!(purge_trash(&conn, None).unwrap(), 1);
+        assert!(get_trash(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,579 @@
+use rusqlite::{Connection, params};
+use serde_json::Value;
+use std::sync::Mutex;
+use crate::models::*;
+use crate::services::change_log;
+
+/// How many actions the undo stack keeps before dropping the oldest.
+const MAX_UNDO_STEPS: usize = 100;
+
+/// Undo/redo stacks — kept in-process, so history starts fresh with each launch.
+#[derive(Default)]
+pub struct UndoState {
+    stacks: Mutex<UndoStacks>,
+}
+
+#[derive(Default)]
+struct UndoStacks {
+    undo: Vec<UndoAction>,
+    redo: Vec<UndoAction>,
+}
+
+/// One user-level action: the net change it made to every row it touched, in the
+/// order the rows were first touched.
+#[derive(Debug, Clone)]
+struct UndoAction {
+    name: String,
+    rows: Vec<RowChange>,
+}
+
+#[derive(Debug, Clone)]
+struct RowChange {
+    entity_type: String,
+    entity_id: String,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+impl UndoState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+// ============================================================
+// Recording
+// ============================================================
+
+/// Run the write `f` as one undoable action named `name` (e.g. "updateTask").
+///
+/// Everything `f` records in `change_log` becomes part of the action, which is why
+/// this must run on the writer connection. A new action clears the redo stack.
+pub fn capture<T>(
+    state: &UndoState,
+    conn: &Connection,
+    name: &str,
+    f: impl FnOnce() -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let since: i64 = conn
+        .query_row("SELECT COALESCE(MAX(id), 0) FROM change_log", [], |row| row.get(0))?;
+    let value = f()?;
+
+    let rows = load_row_changes(conn, since)?;
+    if !rows.is_empty() {
+        let mut stacks = state.stacks.lock().unwrap();
+        stacks.undo.push(UndoAction { name: name.to_string(), rows });
+        if stacks.undo.len() > MAX_UNDO_STEPS {
+            stacks.undo.remove(0);
+        }
+        stacks.redo.clear();
+    }
+    Ok(value)
+}
+
+/// Forget all history, e.g. after the whole database was replaced.
+pub fn clear(state: &UndoState) {
+    let mut stacks = state.stacks.lock().unwrap();
+    stacks.undo.clear();
+    stacks.redo.clear();
+}
+
+/// Collapse the change_log entries written after `since` into one net change per row.
+fn load_row_changes(conn: &Connection, since: i64) -> Result<Vec<RowChange>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT entity_type, entity_id, before_json, after_json
+             FROM change_log WHERE id > ?1 ORDER BY id",
+        )?;
+    let entries = stmt
+        .query_map(params![since], |row| {
+            Ok(RowChange {
+                entity_type: row.get(0)?,
+                entity_id: row.get(1)?,
+                before: change_log::snapshot_column(row, 2)?,
+                after: change_log::snapshot_column(row, 3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut rows: Vec<RowChange> = Vec::new();
+    for entry in entries {
+        match rows.iter_mut().find(|r| same_row(r, &entry)) {
+            Some(row) => row.after = entry.after,
+            None => rows.push(entry),
+        }
+    }
+    rows.retain(|r| r.before != r.after);
+    Ok(rows)
+}
+
+fn same_row(a: &RowChange, b: &RowChange) -> bool {
+    if a.entity_type != b.entity_type || a.entity_id != b.entity_id {
+        return false;
+    }
+    // Links of one task share an entity id; the other end tells them apart
+    match change_log::link_for(&a.entity_type) {
+        Some(link) => link_target(a, &link) == link_target(b, &link),
+        None => true,
+    }
+}
+
+fn link_row(row: &RowChange) -> Value {
+    row.before.as_ref().or(row.after.as_ref()).cloned().unwrap_or(Value::Null)
+}
+
+fn link_target<'a>(row: &'a RowChange, link: &change_log::Link) -> Option<&'a Value> {
+    row.before.as_ref().or(row.after.as_ref()).and_then(|v| v.get(link.other_column))
+}
+
+// ============================================================
+// Undo / Redo
+// ============================================================
+
+/// Reverse the most recent action. Returns `None` when there is nothing to undo.
+pub fn undo(state: &UndoState, conn: &Connection) -> Result<Option<UndoResult>, AppError> {
+    step(state, conn, true)
+}
+
+/// Re-apply the most recently undone action. Returns `None` when there is nothing to redo.
+pub fn redo(state: &UndoState, conn: &Connection) -> Result<Option<UndoResult>, AppError> {
+    step(state, conn, false)
+}
+
+fn step(state: &UndoState, conn: &Connection, undoing: bool) -> Result<Option<UndoResult>, AppError> {
+    let mut stacks = state.stacks.lock().unwrap();
+    let popped = if undoing { stacks.undo.pop() } else { stacks.redo.pop() };
+    let Some(action) = popped else { return Ok(None) };
+
+    // Undo walks rows backwards so children go before the parents they reference
+    let ordered: Vec<&RowChange> = if undoing {
+        action.rows.iter().rev().collect()
+    } else {
+        action.rows.iter().collect()
+    };
+
+    // Something outside the undo history (an import, a category edit that cleared a
+    // reference, ...) may have touched these rows since. Refuse rather than clobber it;
+    // the action is dropped because it can never apply cleanly again.
+    for row in &ordered {
+        let expected = if undoing { &row.after } else { &row.before };
+        if current_state(conn, row)? != *expected {
+            return Err(AppError::new(ErrorCode::Conflict, format!(
+                    "Can't {} \"{}\": the {} was changed since",
+                    if undoing { "undo" } else { "redo" },
+                    action.name,
+                    row.entity_type
+                )));
+        }
+    }
+
+    let operation = if undoing { "undo" } else { "redo" };
+    let applied = change_log::in_transaction(conn, || {
+        let mut changes = Vec::new();
+        let mut deferred = Vec::new();
+        for row in &ordered {
+            let target = if undoing { &row.before } else { &row.after };
+            match without_parent(row, target.as_ref()) {
+                Some(detached) => {
+                    apply_state(conn, row, Some(&detached), operation)?;
+                    deferred.push((row, target));
+                }
+                None => apply_state(conn, row, target.as_ref(), operation)?,
+            }
+            changes.push(EntityChange {
+                entity_type: row.entity_type.clone(),
+                entity_id: row.entity_id.clone(),
+                change: describe(if undoing { &row.after } else { &row.before }, target).into(),
+            });
+        }
+        for (row, target) in deferred {
+            apply_state(conn, row, target.as_ref(), operation)?;
+        }
+        Ok(changes)
+    });
+    let changes = match applied {
+        Ok(changes) => changes,
+        Err(e) => {
+            // Rolled back, so the action can be retried
+            if undoing { stacks.undo.push(action) } else { stacks.redo.push(action) }
+            return Err(e);
+        }
+    };
+
+    let name = action.name.clone();
+    if undoing {
+        stacks.redo.push(action);
+    } else {
+        stacks.undo.push(action);
+    }
+
+    Ok(Some(UndoResult {
+        action: name,
+        changes,
+        can_undo: !stacks.undo.is_empty(),
+        can_redo: !stacks.redo.is_empty(),
+    }))
+}
+
+/// `target` with its sub-task parent cleared, when it has one.
+///
+/// A sub-task's parent must belong to the same task, so a subtree moved to another
+/// task can't be put back row by row in any single order. Sub-tasks are written
+/// detached first and hung back under their parents once every row is in place.
+fn without_parent(row: &RowChange, target: Option<&Value>) -> Option<Value> {
+    if row.entity_type != change_log::SUB_TASK {
+        return None;
+    }
+    let mut detached = target?.clone();
+    let parent = detached.get_mut("parent_id")?;
+    if parent.is_null() {
+        return None;
+    }
+    *parent = Value::Null;
+    Some(detached)
+}
+
+/// How the UI sees the row going from `from` to `to`: trashed rows count as gone.
+fn describe(from: &Option<Value>, to: &Option<Value>) -> &'static str {
+    let visible = |state: &Option<Value>| {
+        state.as_ref().is_some_and(|v| v.get("deleted_at").is_none_or(|d| d.is_null()))
+    };
+    match (visible(from), visible(to)) {
+        (false, true) => "created",
+        (true, false) => "deleted",
+        _ => "updated",
+    }
+}
+
+fn current_state(conn: &Connection, row: &RowChange) -> Result<Option<Value>, AppError> {
+    let Some(link) = change_log::link_for(&row.entity_type) else {
+        return change_log::snapshot(conn, &row.entity_type, &row.entity_id);
+    };
+    let logged = link_row(row);
+    let sql = format!("SELECT COUNT(*) > 0 FROM {} WHERE {} = ?1 AND {} = ?2", link.table, link.owner_column, link.other_column);
+    let exists: bool = conn
+        .query_row(&sql, params![logged[link.owner_column].as_str(), logged[link.other_column].as_str()], |r| r.get(0))?;
+    Ok(exists.then_some(logged))
+}
+
+/// Put a row back into `target` (absent when `None`), logging it like any other write.
+fn apply_state(conn: &Connection, row: &RowChange, target: Option<&Value>, operation: &str) -> Result<(), AppError> {
+    if let Some(link) = change_log::link_for(&row.entity_type) {
+        let logged = link_row(row);
+        match target {
+            Some(Value::Object(columns)) => {
+                let names: Vec<&str> = columns.keys().map(|c| c.as_str()).collect();
+                let sql = format!(
+                    "INSERT OR IGNORE INTO {} ({}) VALUES ({})",
+                    link.table,
+                    names.join(", "),
+                    vec!["?"; names.len()].join(", ")
+                );
+                conn.execute(&sql, rusqlite::params_from_iter(columns.values().map(json_to_sql)))?;
+            }
+            _ => {
+                let sql = format!("DELETE FROM {} WHERE {} = ?1 AND {} = ?2", link.table, link.owner_column, link.other_column);
+                conn.execute(&sql, params![logged[link.owner_column].as_str(), logged[link.other_column].as_str()])?;
+            }
+        }
+        let before = if target.is_some() { None } else { Some(&logged) };
+        return change_log::record(conn, &row.entity_type, &row.entity_id, operation, before, target);
+    }
+
+    let table = change_log::table_for(&row.entity_type)?;
+    change_log::tracked(conn, &row.entity_type, &row.entity_id, operation, || {
+        let Some(Value::Object(columns)) = target else {
+            ensure_no_dependents(conn, table, row)?;
+            conn.execute(&format!("DELETE FROM {} WHERE id = ?1", table), params![row.entity_id])?;
+            return Ok(());
+        };
+
+        // Snapshots may predate a migration; only write columns the table still has
+        let existing: Vec<String> = conn
+            .prepare(&format!("SELECT * FROM {} LIMIT 0", table))?
+            .column_names()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let columns: Vec<(&String, &Value)> = columns.iter().filter(|(c, _)| existing.contains(c)).collect();
+
+        let names: Vec<&str> = columns.iter().map(|(c, _)| c.as_str()).collect();
+        let placeholders = vec!["?"; names.len()].join(", ");
+        let updates = names.iter().map(|c| format!("{c} = excluded.{c}")).collect::<Vec<_>>().join(", ");
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+            table,
+            names.join(", "),
+            placeholders,
+            updates
+        );
+        let values: Vec<rusqlite::types::Value> = columns.iter().map(|(_, v)| json_to_sql(v)).collect();
+        conn.execute(&sql, rusqlite::params_from_iter(values))?;
+        Ok(())
+    })
+}
+
+/// Rows that go with a deleted row through `ON DELETE CASCADE` or `SET NULL`, as
+/// `(table, dependent table, column)`.
+const DEPENDENTS: &[(&str, &str, &str)] = &[
+    ("tasks", "sub_tasks", "task_id"),
+    ("tasks", "task_tags", "task_id"),
+    ("tasks", "task_dependencies", "task_id"),
+    ("tasks", "task_dependencies", "depends_on_id"),
+    ("tasks", "time_entries", "task_id"),
+    ("tasks", "focus_sessions", "task_id"),
+    ("tasks", "attachments", "task_id"),
+    ("sub_tasks", "sub_tasks", "parent_id"),
+    ("notes", "attachments", "note_id"),
+    ("tags", "task_tags", "tag_id"),
+    ("projects", "headings", "project_id"),
+    ("projects", "tasks", "project_id"),
+    ("headings", "tasks", "heading_id"),
+];
+
+/// Refuse to delete a row that other rows still depend on.
+///
+/// Undo walks an action's rows children first, so anything left at this point was
+/// written outside the action (a focus session, an attachment, a later write whose
+/// action has since been dropped). The cascade would change it without a trace and
+/// redo couldn't bring it back; the action stays on its stack and applies once the
+/// dependents are gone.
+fn ensure_no_dependents(conn: &Connection, table: &str, row: &RowChange) -> Result<(), AppError> {
+    for (_, dependent, column) in DEPENDENTS.iter().filter(|(parent, _, _)| *parent == table) {
+        let referenced: bool = conn.query_row(
+            &format!("SELECT COUNT(*) > 0 FROM {} WHERE {} = ?1", dependent, column),
+            params![row.entity_id],
+            |r| r.get(0),
+        )?;
+        if referenced {
+            return Err(AppError::new(
+                ErrorCode::Conflict,
+                format!("The {} is still referenced from {}; remove those first", row.entity_type, dependent),
+            )
+            .with_detail("id", row.entity_id.as_str())
+            .with_detail("table", *dependent));
+        }
+    }
+    Ok(())
+}
+
+fn json_to_sql(value: &Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value as Sql;
+    match value {
+        Value::Null => Sql::Null,
+        Value::Bool(b) => Sql::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Sql::Integer(i),
+            None => Sql::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => Sql::Text(s.clone()),
+        other => Sql::Text(other.to_string()),
+    }
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+    use crate::services::{dependency, note, tag, task};
+
+    fn create_task(state: &UndoState, conn: &Connection, title: &str, recurrence_rule: Option<RecurrenceRule>) -> Task {
+        capture(state, conn, "createTask", || {
+            task::create_task(conn, CreateTaskInput {
+                title: title.to_string(),
+                due_date: Some("2026-01-05".into()),
+                recurrence_rule,
+                ..Default::default()
+            })
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_undo_and_redo_create_and_update() {
+        let conn = open_test_connection();
+        let state = UndoState::new();
+        let t = create_task(&state, &conn, "Draft", None);
+        capture(&state, &conn, "updateTask", || {
+            task::update_task(&conn, &t.id, UpdateTaskInput { title: Some("Final".into()), ..Default::default() })
+        })
+        .unwrap();
+
+        let result = undo(&state, &conn).unwrap().unwrap();
+        assert_eq!(result.action, "updateTask");
+        assert_eq!(result.changes[0].change, "updated");
+        assert_eq!(task::get_task_by_id(&conn, &t.id).unwrap().unwrap().title, "Draft");
+
+        let result = undo(&state, &conn).unwrap().unwrap();
+        assert_eq!(result.changes[0].change, "deleted");
+        assert!(!result.can_undo);
+        assert!(task::get_task_by_id(&conn, &t.id).unwrap().is_none());
+        assert!(undo(&state, &conn).unwrap().is_none());
+
+        redo(&state, &conn).unwrap().unwrap();
+        let result = redo(&state, &conn).unwrap().unwrap();
+        assert!(!result.can_redo);
+        assert_eq!(task::get_task_by_id(&conn, &t.id).unwrap().unwrap().title, "Final");
+    }
+
+    #[test]
+    fn test_undo_complete_removes_next_recurrence() {
+        let conn = open_test_connection();
+        let state = UndoState::new();
+        let rule = RecurrenceRule {
+            rule_type: "daily".into(),
+            interval: 1,
+            days_of_week: None,
+            day_of_month: None,
+            end_date: None,
+        };
+        let t = create_task(&state, &conn, "Water plants", Some(rule));
+        let completed = capture(&state, &conn, "completeTask", || task::complete_task(&conn, &t.id)).unwrap();
+        let next_id = completed.next_task.unwrap().id;
+
+        let result = undo(&state, &conn).unwrap().unwrap();
+        assert_eq!(result.changes.len(), 2);
+        assert!(task::get_task_by_id(&conn, &next_id).unwrap().is_none());
+        assert_eq!(task::get_task_by_id(&conn, &t.id).unwrap().unwrap().status, TaskStatus::Todo);
+
+        redo(&state, &conn).unwrap().unwrap();
+        assert!(task::get_task_by_id(&conn, &next_id).unwrap().is_some());
+        assert_eq!(task::get_task_by_id(&conn, &t.id).unwrap().unwrap().status, TaskStatus::Completed);
+    }
+
+    #[test]
+    fn test_undo_sub_task_and_note_deletes() {
+        let conn = open_test_connection();
+        let state = UndoState::new();
+        let t = create_task(&state, &conn, "Trip", None);
+        let sub = task::create_sub_task(&conn, &t.id, CreateSubTaskInput { title: "Pack".into(), ..Default::default() }, None)
+            .unwrap();
+        let n = note::create_note(&conn, CreateNoteInput { title: Some("Packing list".into()), content: None }).unwrap();
+
+        capture(&state, &conn, "deleteSubTask", || task::delete_sub_task(&conn, &sub.id)).unwrap();
+        capture(&state, &conn, "deleteNote", || note::delete_note(&conn, &n.id)).unwrap();
+
+        let result = undo(&state, &conn).unwrap().unwrap();
+        assert_eq!(result.changes[0].change, "created");
+        assert!(note::get_note_by_id(&conn, &n.id).unwrap().is_some());
+        undo(&state, &conn).unwrap().unwrap();
+        let subs = task::get_task_by_id(&conn, &t.id).unwrap().unwrap().sub_tasks.unwrap();
+        assert_eq!(subs.len(), 1);
+    }
+
+    #[test]
+    fn test_undo_and_redo_subtree_moved_between_tasks() {
+        let conn = open_test_connection();
+        let state = UndoState::new();
+        let (a, b) = (create_task(&state, &conn, "A", None), create_task(&state, &conn, "B", None));
+        let mut parent_id = None;
+        for title in ["One", "Two", "Three"] {
+            let st = task::create_sub_task(&conn, &a.id, CreateSubTaskInput {
+                title: title.into(),
+                parent_id: parent_id.take(),
+                ..Default::default()
+            }, None)
+            .unwrap();
+            parent_id = Some(st.id);
+        }
+        let depth = |task_id: &str| {
+            let mut level = task::get_task_by_id(&conn, task_id).unwrap().unwrap().sub_tasks.unwrap();
+            let mut depth = 0;
+            while let Some(st) = level.pop() {
+                depth += 1;
+                level = st.children.unwrap_or_default();
+            }
+            depth
+        };
+
+        capture(&state, &conn, "demoteTask", || task::demote_task(&conn, &a.id, &b.id, None)).unwrap();
+        assert_eq!(depth(&b.id), 4);
+
+        // Three levels deep: putting rows back one at a time would trip the same-task trigger
+        undo(&state, &conn).unwrap().unwrap();
+        assert_eq!(depth(&a.id), 3);
+        assert_eq!(depth(&b.id), 0);
+        redo(&state, &conn).unwrap().unwrap();
+        assert_eq!(depth(&b.id), 4);
+    }
+
+    #[test]
+    fn test_undo_and_redo_dependency_links() {
+        let conn = open_test_connection();
+        let state = UndoState::new();
+        let a = create_task(&state, &conn, "Design", None);
+        let b = create_task(&state, &conn, "Build", None);
+        let c = create_task(&state, &conn, "Ship", None);
+        capture(&state, &conn, "addTaskDependency", || dependency::add_dependency(&conn, &c.id, &a.id)).unwrap();
+        capture(&state, &conn, "addTaskDependency", || dependency::add_dependency(&conn, &c.id, &b.id)).unwrap();
+        capture(&state, &conn, "removeTaskDependency", || dependency::remove_dependency(&conn, &c.id, &a.id)).unwrap();
+
+        let blocked_by = |conn: &Connection| task::get_task_by_id(conn, &c.id).unwrap().unwrap().blocked_by.unwrap();
+        undo(&state, &conn).unwrap().unwrap();
+        assert_eq!(blocked_by(&conn), vec![a.id.clone(), b.id.clone()]);
+        undo(&state, &conn).unwrap().unwrap();
+        assert_eq!(blocked_by(&conn), vec![a.id.clone()]);
+        redo(&state, &conn).unwrap().unwrap();
+        redo(&state, &conn).unwrap().unwrap();
+        assert_eq!(blocked_by(&conn), vec![b.id.clone()]);
+    }
+
+    #[test]
+    fn test_undo_refuses_when_row_changed_outside_history() {
+        let conn = open_test_connection();
+        let state = UndoState::new();
+        let t = create_task(&state, &conn, "Draft", None);
+        conn.execute("UPDATE tasks SET title = 'Edited elsewhere' WHERE id = ?1", params![t.id]).unwrap();
+
+        let err = undo(&state, &conn).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        assert!(task::get_task_by_id(&conn, &t.id).unwrap().is_some());
+        assert!(undo(&state, &conn).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_keeps_tasks_with_uncaptured_dependents() {
+        let conn = open_test_connection();
+        let state = UndoState::new();
+        let t = create_task(&state, &conn, "Spec", None);
+        let work = tag::create_tag(&conn, "work", None).unwrap();
+        tag::add_tag_to_task(&conn, &t.id, &work.id).unwrap();
+
+        let err = undo(&state, &conn).unwrap_err();
+        assert_eq!((err.code, err.details.unwrap()["table"].as_str()), (ErrorCode::Conflict, "task_tags"));
+        assert!(task::get_task_by_id(&conn, &t.id).unwrap().is_some());
+
+        tag::remove_tag_from_task(&conn, &t.id, &work.id).unwrap();
+        conn.execute(
+            "INSERT INTO attachments (id, task_id, file_name, size_bytes, sha256, created_at)
+             VALUES ('a1', ?1, 'spec.pdf', 4, 'abc', '')",
+            params![t.id],
+        )
+        .unwrap();
+        assert_eq!(undo(&state, &conn).unwrap_err().details.unwrap()["table"], "attachments");
+
+        // Still on the stack, and applies once nothing depends on the task
+        conn.execute("DELETE FROM attachments", []).unwrap();
+        assert_eq!(undo(&state, &conn).unwrap().unwrap().action, "createTask");
+    }
+
+    #[test]
+    fn test_captured_tag_links_undo_before_their_task() {
+        let conn = open_test_connection();
+        let state = UndoState::new();
+        let t = create_task(&state, &conn, "Spec", None);
+        let work = tag::create_tag(&conn, "work", None).unwrap();
+        capture(&state, &conn, "addTagToTask", || tag::add_tag_to_task(&conn, &t.id, &work.id)).unwrap();
+
+        assert_eq!(undo(&state, &conn).unwrap().unwrap().action, "addTagToTask");
+        assert_eq!(undo(&state, &conn).unwrap().unwrap().action, "createTask");
+        redo(&state, &conn).unwrap();
+        redo(&state, &conn).unwrap();
+        let tags = task::get_task_by_id(&conn, &t.id).unwrap().unwrap().tags.unwrap();
+        assert_eq!(tags.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(), vec!["work"]);
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use crate::models::*;
+use crate::services::task::{row_to_task, with_relations};
+
+// Things-style lists. "Open" means status 'todo'; dates compare as `%Y-%m-%d` text.
+//
+// - Today:    open, not in Someday, starting or due today or earlier
+// - Upcoming: open, not in Someday, not in Today, starting or due later
+// - Anytime:  open, not in Someday, already started (or no start date)
+// - Someday:  open and parked in Someday
+// - Logbook:  recently closed, i.e. not archived yet, most recent first
+//
+// Archived tasks are in none of them. `logbook::get_logbook` pages through the
+// whole history of closed tasks, archived ones included.
+
+const OPEN: &str = "deleted_at IS NULL AND archived_at IS NULL AND status = 'todo'";
+
+pub fn get_view(conn: &Connection, kind: &str) -> Result<Vec<Task>, AppError> {
+    let kind = ViewKind::parse("kind", kind)?;
+    get_view_on(conn, kind, chrono::Local::now().date_naive())
+}
+
+/// `get_view` as seen on `today`.
+pub fn get_view_on(conn: &Connection, kind: ViewKind, today: NaiveDate) -> Result<Vec<Task>, AppError> {
+    let sql = match kind {
+        ViewKind::Today => format!(
+            "SELECT * FROM tasks WHERE {OPEN} AND someday = 0
+             AND (start_date <= ?1 OR due_date <= ?1)
+             ORDER BY sort_order"
+        ),
+        ViewKind::Upcoming => format!(
+            "SELECT * FROM tasks WHERE {OPEN} AND someday = 0
+             AND (start_date IS NOT NULL OR due_date IS NOT NULL)
+             AND (start_date IS NULL OR start_date > ?1)
+             AND (due_date IS NULL OR due_date > ?1)
+             ORDER BY COALESCE(start_date, due_date), sort_order"
+        ),
+        ViewKind::Anytime => format!(
+            "SELECT * FROM tasks WHERE {OPEN} AND someday = 0
+             AND (start_date IS NULL OR start_date <= ?1)
+             ORDER BY sort_order"
+        ),
+        ViewKind::Someday => format!(
+            "SELECT * FROM tasks WHERE {OPEN} AND someday = 1
+             ORDER BY sort_order"
+        ),
+        ViewKind::Logbook => "SELECT * FROM tasks WHERE deleted_at IS NULL AND archived_at IS NULL AND status != 'todo'
+             ORDER BY completed_at DESC"
+            .to_string(),
+    };
+
+    let mut stmt = conn.prepare(&sql)?;
+    let today = today.format("%Y-%m-%d").to_string();
+    let params: &[&dyn rusqlite::types::ToSql] = if stmt.parameter_count() > 0 { &[&today] } else { &[] };
+    let rows: Vec<Task> = stmt
+        .query_map(params, row_to_task)?
+        .collect::<Result<_, _>>()?;
+    with_relations(conn, rows)
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_test_connection;
+    use crate::services::task;
+
+    fn new_task(conn: &Connection, title: &str, start_date: Option<&str>, due_date: Option<&str>, someday: bool) -> Task {
+        task::create_task(conn, CreateTaskInput {
+            title: title.to_string(),
+            start_date: start_date.map(String::from),
+            due_date: due_date.map(String::from),
+            someday: Some(someday),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_views_follow_start_due_and_someday() {
+        let conn = open_test_connection();
+        new_task(&conn, "Inbox", None, None, false);
+        new_task(&conn, "Started", Some("2026-03-01"), None, false);
+        new_task(&conn, "Overdue", None, Some("2026-03-09"), false);
+        new_task(&conn, "Deferred", Some("2026-03-20"), Some("2026-03-25"), false);
+        new_task(&conn, "Due later", None, Some("2026-03-12"), false);
+        new_task(&conn, "Maybe", None, Some("2026-03-01"), true);
+        let done = new_task(&conn, "Done", None, Some("2026-03-10"), false);
+        task::complete_task(&conn, &done.id).unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        let titles = |kind| {
+            let mut titles: Vec<String> = get_view_on(&conn, kind, today).unwrap().into_iter().map(|t| t.title).collect();
+            if kind != ViewKind::Upcoming {
+                titles.sort();
+            }
+            titles
+        };
+
+        assert_eq!(titles(ViewKind::Today), vec!["Overdue", "Started"]);
+        assert_eq!(titles(ViewKind::Upcoming), vec!["Due later", "Deferred"]);
+        assert_eq!(titles(ViewKind::Anytime), vec!["Due later", "Inbox", "Overdue", "Started"]);
+        assert_eq!(titles(ViewKind::Someday), vec!["Maybe"]);
+        assert_eq!(titles(ViewKind::Logbook), vec!["Done"]);
+    }
+
+    #[test]
+    fn test_views_load_relations_and_reject_unknown_kinds() {
+        let conn = open_test_connection();
+        new_task(&conn, "Inbox", None, None, false);
+        let anytime = get_view(&conn, "anytime").unwrap();
+        assert_eq!(anytime[0].tags.as_ref().map(Vec::len), Some(0));
+        assert_eq!(anytime[0].is_blocked, Some(false));
+
+        let err = get_view(&conn, "inbox").unwrap_err();
+        assert_eq!(err.details.unwrap()["field"], "kind");
+    }
+}
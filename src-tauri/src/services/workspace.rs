@@ -0,0 +1,312 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+use crate::db::Database;
+use crate::models::*;
+use crate::services::attachment;
+
+/// Registry of workspaces, stored next to the database files.
+const REGISTRY_FILE: &str = "workspaces.json";
+/// The workspace every install starts with; it keeps the original database file.
+const DEFAULT_WORKSPACE_ID: &str = "default";
+const DEFAULT_DATABASE_FILE: &str = "watermelon.db";
+
+/// Workspace registry state managed by Tauri.
+pub struct WorkspaceState {
+    data_dir: PathBuf,
+    registry: Mutex<Registry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Registry {
+    active_id: String,
+    workspaces: Vec<WorkspaceEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceEntry {
+    id: String,
+    name: String,
+    file_name: String,
+    created_at: String,
+}
+
+fn not_found() -> AppError {
+    AppError::not_found("Workspace not found")
+}
+
+impl WorkspaceState {
+    /// Load the registry from `data_dir`, starting with a single default workspace
+    /// backed by the original `watermelon.db` when there is none yet.
+    pub fn load(data_dir: &Path) -> Result<Self, AppError> {
+        let path = data_dir.join(REGISTRY_FILE);
+        let registry = if path.exists() {
+            let json = fs::read_to_string(&path)?;
+            serde_json::from_str(&json).map_err(|e| {
+                AppError::new(ErrorCode::SerializeError, format!("Invalid {}: {}", REGISTRY_FILE, e))
+            })?
+        } else {
+            Registry {
+                active_id: DEFAULT_WORKSPACE_ID.into(),
+                workspaces: vec![WorkspaceEntry {
+                    id: DEFAULT_WORKSPACE_ID.into(),
+                    name: "Default".into(),
+                    file_name: DEFAULT_DATABASE_FILE.into(),
+                    created_at: chrono::Utc::now().to_rfc3339(),
+                }],
+            }
+        };
+        Ok(WorkspaceState { data_dir: data_dir.to_path_buf(), registry: Mutex::new(registry) })
+    }
+
+    /// Database file of the active workspace.
+    pub fn active_database_path(&self) -> PathBuf {
+        let registry = self.registry.lock().unwrap();
+        let entry = registry
+            .workspaces
+            .iter()
+            .find(|w| w.id == registry.active_id)
+            .or(registry.workspaces.first());
+        self.data_dir.join(entry.map_or(DEFAULT_DATABASE_FILE, |w| w.file_name.as_str()))
+    }
+
+    /// Attachment store of the database at `db_path`.
+    pub fn attachment_dir(&self, db_path: &Path) -> PathBuf {
+        attachment::store_dir(&self.data_dir, db_path)
+    }
+
+    fn save(&self, registry: &Registry) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(registry)?;
+        // Write-then-rename so a crash never leaves a half-written registry
+        let path = self.data_dir.join(REGISTRY_FILE);
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, json)?;
+        fs::rename(&partial, &path).map_err(AppError::from)
+    }
+}
+
+// ============================================================
+// Workspace Service
+// ============================================================
+
+pub fn list_workspaces(state: &WorkspaceState) -> Vec<Workspace> {
+    let registry = state.registry.lock().unwrap();
+    registry
+        .workspaces
+        .iter()
+        .map(|w| Workspace {
+            id: w.id.clone(),
+            name: w.name.clone(),
+            created_at: w.created_at.clone(),
+            active: w.id == registry.active_id,
+        })
+        .collect()
+}
+
+/// Register a new workspace. Its database file is created on first switch.
+pub fn create_workspace(state: &WorkspaceState, name: &str) -> Result<Workspace, AppError> {
+    let mut registry = state.registry.lock().unwrap();
+    validate_name(&registry, name, None)?;
+
+    let id = Uuid::new_v4().to_string();
+    let entry = WorkspaceEntry {
+        id: id.clone(),
+        name: name.trim().to_string(),
+        file_name: format!("workspace-{}.db", id),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let mut updated = registry.clone();
+    updated.workspaces.push(entry.clone());
+    state.save(&updated)?;
+    *registry = updated;
+
+    Ok(Workspace { id, name: entry.name, created_at: entry.created_at, active: false })
+}
+
+pub fn rename_workspace(state: &WorkspaceState, id: &str, name: &str) -> Result<Workspace, AppError> {
+    let mut registry = state.registry.lock().unwrap();
+    validate_name(&registry, name, Some(id))?;
+
+    let mut updated = registry.clone();
+    let entry = updated.workspaces.iter_mut().find(|w| w.id == id).ok_or_else(not_found)?;
+    entry.name = name.trim().to_string();
+    let workspace = Workspace {
+        id: entry.id.clone(),
+        name: entry.name.clone(),
+        created_at: entry.created_at.clone(),
+        active: entry.id == updated.active_id,
+    };
+    state.save(&updated)?;
+    *registry = updated;
+    Ok(workspace)
+}
+
+/// Remove a workspace and its database file. The active workspace can't be deleted;
+/// switch away from it first. Backups of it are left in the backups folder.
+pub fn delete_workspace(state: &WorkspaceState, id: &str) -> Result<(), AppError> {
+    let mut registry = state.registry.lock().unwrap();
+    let entry = registry.workspaces.iter().find(|w| w.id == id).cloned().ok_or_else(not_found)?;
+    if entry.id == registry.active_id {
+        return Err(AppError::validation("The active workspace can't be deleted"));
+    }
+
+    let mut updated = registry.clone();
+    updated.workspaces.retain(|w| w.id != id);
+    state.save(&updated)?;
+    *registry = updated;
+
+    let db_path = state.data_dir.join(&entry.file_name);
+    for suffix in ["", "-wal", "-shm"] {
+        let file = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+        if file.exists() {
+            fs::remove_file(&file)?;
+        }
+    }
+    let attachments = state.attachment_dir(&db_path);
+    if attachments.exists() {
+        fs::remove_dir_all(&attachments)?;
+    }
+    Ok(())
+}
+
+/// Make `id` the active workspace and point `db` at its database file.
+///
+/// `before_switch` runs on the old database once nothing else can stop the switch,
+/// to wind down work that belongs to it. If it fails, nothing is switched.
+pub fn switch_workspace(
+    state: &WorkspaceState,
+    db: &Database,
+    id: &str,
+    before_switch: impl FnOnce(&Connection) -> Result<(), AppError>,
+) -> Result<Workspace, AppError> {
+    let mut registry = state.registry.lock().unwrap();
+    let entry = registry.workspaces.iter().find(|w| w.id == id).cloned().ok_or_else(not_found)?;
+
+    // The registry is saved once the new database is open, and the swap can't fail
+    // after it, so `active_id` always names the database in use
+    let previous = registry.clone();
+    let mut updated = registry.clone();
+    updated.active_id = entry.id.clone();
+    db.switch_to(&state.data_dir.join(&entry.file_name), |conn| {
+        state.save(&updated)?;
+        before_switch(conn).inspect_err(|_| {
+            if let Err(e) = state.save(&previous) {
+                log::error!("Failed to restore the workspace registry: {}", e);
+            }
+        })
+    })?;
+    *registry = updated;
+
+    Ok(Workspace { id: entry.id, name: entry.name, created_at: entry.created_at, active: true })
+}
+
+fn validate_name(registry: &Registry, name: &str, exclude_id: Option<&str>) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::validation("Workspace name must not be empty"));
+    }
+    let taken = registry
+        .workspaces
+        .iter()
+        .any(|w| Some(w.id.as_str()) != exclude_id && w.name.eq_ignore_ascii_case(name.trim()));
+    if taken {
+        return Err(AppError::validation("Workspace name already exists"));
+    }
+    Ok(())
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestDir(PathBuf);
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn setup() -> (TestDir, WorkspaceState, Database) {
+        let dir = std::env::temp_dir().join(format!("watermelon-workspace-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let state = WorkspaceState::load(&dir).unwrap();
+        let db = Database::new(&state.active_database_path(), &dir.join("backups")).unwrap();
+        (TestDir(dir), state, db)
+    }
+
+    fn tag_names(db: &Database) -> Vec<String> {
+        let conn = db.read();
+        let mut stmt = conn.prepare("SELECT name FROM tags ORDER BY name").unwrap();
+        stmt.query_map([], |row| row.get(0)).unwrap().map(|r| r.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_switching_isolates_data_and_persists() {
+        let (dir, state, db) = setup();
+        db.write()
+            .execute("INSERT INTO tags (id, name, created_at) VALUES ('t1', 'personal', '2024-01-01T00:00:00Z')", [])
+            .unwrap();
+
+        let work = create_workspace(&state, "Work").unwrap();
+        switch_workspace(&state, &db, &work.id, |_| Ok(())).unwrap();
+        assert!(tag_names(&db).is_empty());
+        db.write()
+            .execute("INSERT INTO tags (id, name, created_at) VALUES ('t2', 'work', '2024-01-01T00:00:00Z')", [])
+            .unwrap();
+
+        // The registry survives a restart
+        let reloaded = WorkspaceState::load(&dir.0).unwrap();
+        assert_eq!(reloaded.active_database_path(), db.path());
+        assert!(list_workspaces(&reloaded).iter().any(|w| w.id == work.id && w.active));
+
+        switch_workspace(&state, &db, DEFAULT_WORKSPACE_ID, |_| Ok(())).unwrap();
+        assert_eq!(tag_names(&db), vec!["personal".to_string()]);
+    }
+
+    #[test]
+    fn test_rename_and_delete_rules() {
+        let (_dir, state, db) = setup();
+        let work = create_workspace(&state, "Work").unwrap();
+        assert_eq!(create_workspace(&state, " work ").unwrap_err().code, ErrorCode::ValidationError);
+        assert_eq!(rename_workspace(&state, &work.id, "Job").unwrap().name, "Job");
+
+        switch_workspace(&state, &db, &work.id, |_| Ok(())).unwrap();
+        let work_file = db.path();
+        assert_eq!(delete_workspace(&state, &work.id).unwrap_err().code, ErrorCode::ValidationError);
+
+        switch_workspace(&state, &db, DEFAULT_WORKSPACE_ID, |_| Ok(())).unwrap();
+        delete_workspace(&state, &work.id).unwrap();
+        assert!(!work_file.exists());
+        assert_eq!(list_workspaces(&state).len(), 1);
+    }
+
+    #[test]
+    fn test_failed_switch_leaves_everything_in_place() {
+        let (dir, state, db) = setup();
+        let home = db.path();
+        let work = create_workspace(&state, "Work").unwrap();
+
+        let mut called = false;
+        let err = switch_workspace(&state, &db, "missing", |_| {
+            called = true;
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+        assert!(!called);
+
+        let err = switch_workspace(&state, &db, &work.id, |_| Err(AppError::validation("busy"))).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ValidationError);
+        assert_eq!(db.path(), home);
+        assert_eq!(WorkspaceState::load(&dir.0).unwrap().active_database_path(), home);
+    }
+}
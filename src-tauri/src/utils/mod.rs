@@ -1 +1,2 @@
 pub mod recurrence;
+pub mod validate;
@@ -0,0 +1,123 @@
+use chrono::NaiveDate;
+use crate::models::{AppError, RecurrenceRule};
+
+/// Format of due dates and recurrence end dates, as expected by `complete_task`.
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+const RECURRENCE_TYPES: &[&str] = &["daily", "weekly", "monthly", "custom"];
+
+// Every validator takes the name of the field being checked (as the frontend
+// spells it, e.g. `dueDate` or `tasks[2].dueDate`) and reports it in the
+// error's `details`, together with the rejected value.
+
+fn invalid(field: &str, value: impl ToString, message: String) -> AppError {
+    AppError::validation(message)
+        .with_detail("field", field)
+        .with_detail("value", value.to_string())
+}
+
+pub fn is_date(value: &str) -> bool {
+    NaiveDate::parse_from_str(value, DATE_FORMAT).is_ok()
+}
+
+/// RFC 3339, as expected by the notification scheduler.
+pub fn is_date_time(value: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(value).is_ok()
+}
+
+pub fn date(field: &str, value: &str) -> Result<(), AppError> {
+    if !is_date(value) {
+        return Err(invalid(field, value, format!("{} must be a date like 2024-01-31", field)));
+    }
+    Ok(())
+}
+
+pub fn date_time(field: &str, value: &str) -> Result<(), AppError> {
+    if !is_date_time(value) {
+        return Err(invalid(field, value, format!("{} must be an RFC 3339 date-time like 2024-01-31T09:00:00Z", field)));
+    }
+    Ok(())
+}
+
+/// `#rgb` or `#rrggbb`.
+pub fn color(field: &str, value: &str) -> Result<(), AppError> {
+    let valid = value
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()));
+    if !valid {
+        return Err(invalid(field, value, format!("{} must be a hex color like #3b82f6", field)));
+    }
+    Ok(())
+}
+
+pub fn recurrence_rule(field: &str, rule: &RecurrenceRule) -> Result<(), AppError> {
+    if !RECURRENCE_TYPES.contains(&rule.rule_type.as_str()) {
+        let field = format!("{}.type", field);
+        let message = format!("{} must be one of {}", field, RECURRENCE_TYPES.join(", "));
+        return Err(invalid(&field, &rule.rule_type, message));
+    }
+    if rule.interval < 1 {
+        let field = format!("{}.interval", field);
+        return Err(invalid(&field, rule.interval, format!("{} must be at least 1", field)));
+    }
+    if let Some(ref days) = rule.days_of_week {
+        let field = format!("{}.daysOfWeek", field);
+        if days.is_empty() {
+            return Err(invalid(&field, "[]", format!("{} must not be empty", field)));
+        }
+        if let Some(day) = days.iter().find(|d| !(0..=6).contains(*d)) {
+            return Err(invalid(&field, day, format!("{} values must be between 0 (Sunday) and 6 (Saturday)", field)));
+        }
+    }
+    if let Some(day) = rule.day_of_month {
+        if !(1..=31).contains(&day) {
+            let field = format!("{}.dayOfMonth", field);
+            return Err(invalid(&field, day, format!("{} must be between 1 and 31", field)));
+        }
+    }
+    if let Some(ref end_date) = rule.end_date {
+        date(&format!("{}.endDate", field), end_date)?;
+    }
+    Ok(())
+}
+
+// ============================================================
+// Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(rule_type: &str, interval: i64, days_of_week: Option<Vec<i64>>) -> RecurrenceRule {
+        RecurrenceRule { rule_type: rule_type.into(), interval, days_of_week, day_of_month: None, end_date: None }
+    }
+
+    #[test]
+    fn test_dates_times_and_colors() {
+        assert!(date("dueDate", "2024-02-29").is_ok());
+        let err = date("dueDate", "2024-02-30").unwrap_err();
+        let details = err.details.unwrap();
+        assert_eq!(details["field"], "dueDate");
+        assert_eq!(details["value"], "2024-02-30");
+
+        assert!(date_time("reminderTime", "2024-01-31T09:00:00+08:00").is_ok());
+        assert!(date_time("reminderTime", "2024-01-31 09:00").is_err());
+
+        assert!(color("color", "#3b82f6").is_ok());
+        assert!(color("color", "#FFF").is_ok());
+        assert!(color("color", "blue").is_err());
+        assert!(color("color", "#12345g").is_err());
+    }
+
+    #[test]
+    fn test_recurrence_rules() {
+        assert!(recurrence_rule("recurrenceRule", &rule("weekly", 1, Some(vec![1, 3]))).is_ok());
+
+        let field = |r: RecurrenceRule| recurrence_rule("recurrenceRule", &r).unwrap_err().details.unwrap()["field"].clone();
+        assert_eq!(field(rule("daily", 0, None)), "recurrenceRule.interval");
+        assert_eq!(field(rule("weekly", 1, Some(vec![]))), "recurrenceRule.daysOfWeek");
+        assert_eq!(field(rule("weekly", 1, Some(vec![7]))), "recurrenceRule.daysOfWeek");
+        assert_eq!(field(rule("yearly", 1, None)), "recurrenceRule.type");
+    }
+}
@@ -0,0 +1,897 @@
+// Generated from src-tauri/src/models and src-tauri/src/commands.
+// Do not edit by hand: run `bun run bindings` to regenerate.
+
+import { invoke } from '@tauri-apps/api/core'
+
+// ============================================================
+// Types
+// ============================================================
+
+export interface Task {
+  id: string
+  title: string
+  description?: string | null
+  status: TaskStatus
+  priority: Priority
+  categoryId?: string | null
+  projectId?: string | null
+  /** Always a heading of `project_id` */
+  headingId?: string | null
+  /** Hidden from Today and Anytime until this date */
+  startDate?: string | null
+  dueDate?: string | null
+  reminderTime?: string | null
+  recurrenceRule?: RecurrenceRule | null
+  /** Parked in Someday, out of every other view */
+  someday: boolean
+  completedAt?: string | null
+  /** Moved to the logbook, out of the task list and every view */
+  archivedAt?: string | null
+  sortOrder: number
+  createdAt: string
+  updatedAt: string
+  subTasks?: SubTask[] | null
+  tags?: Tag[] | null
+  category?: Category | null
+  /** Ids of the tasks this one waits on */
+  blockedBy?: string[] | null
+  /** Ids of the tasks waiting on this one */
+  blocking?: string[] | null
+  /** Whether any task this one waits on is still open */
+  isBlocked?: boolean | null
+  /** Seconds logged against this task, a running timer included */
+  timeSpentSeconds?: number | null
+}
+
+export interface SubTask {
+  id: string
+  taskId: string
+  parentId?: string | null
+  title: string
+  description?: string | null
+  priority: Priority
+  dueDate?: string | null
+  completed: boolean
+  sortOrder: number
+  createdAt: string
+  children?: SubTask[] | null
+}
+
+export interface Category {
+  id: string
+  name: string
+  color?: string | null
+  sortOrder: number
+  createdAt: string
+}
+
+export interface Project {
+  id: string
+  categoryId?: string | null
+  title: string
+  notes?: string | null
+  deadline?: string | null
+  status: ProjectStatus
+  completedAt?: string | null
+  sortOrder: number
+  createdAt: string
+  updatedAt: string
+  headings: Heading[]
+  progress: ProjectProgress
+}
+
+export interface Heading {
+  id: string
+  projectId: string
+  title: string
+  sortOrder: number
+  createdAt: string
+}
+
+/** Task counts of a project; canceled tasks count toward neither. */
+export interface ProjectProgress {
+  totalTasks: number
+  completedTasks: number
+  /** `completed_tasks / total_tasks`, 0 for an empty project */
+  ratio: number
+}
+
+export interface Tag {
+  id: string
+  name: string
+  color?: string | null
+  createdAt: string
+}
+
+export interface TimeEntry {
+  id: string
+  taskId: string
+  startedAt: string
+  /** `None` while the timer is running */
+  endedAt?: string | null
+  note?: string | null
+  /** Up to now for a running timer */
+  durationSeconds?: number
+  createdAt: string
+  updatedAt: string
+}
+
+/**
+ * A saved task to create again and again. Dates are stored as offsets in days
+ * from the base date given when the template is used.
+ */
+export interface TaskTemplate {
+  id: string
+  name: string
+  title: string
+  description?: string | null
+  priority: Priority
+  categoryId?: string | null
+  startOffsetDays?: number | null
+  dueOffsetDays?: number | null
+  tagIds: string[]
+  subTasks: TemplateSubTask[]
+  /** `{{placeholder}}` names used in titles and descriptions, in order of appearance */
+  placeholders?: string[]
+  createdAt: string
+  updatedAt: string
+}
+
+export interface TemplateSubTask {
+  title: string
+  description?: string | null
+  priority?: Priority
+  dueOffsetDays?: number | null
+  children?: TemplateSubTask[]
+}
+
+/**
+ * A file attached to a task or a note. The file is stored once per workspace
+ * under its SHA-256, however many attachments share it.
+ */
+export interface Attachment {
+  id: string
+  /** Exactly one of `task_id` and `note_id` is set */
+  taskId?: string | null
+  noteId?: string | null
+  fileName: string
+  sizeBytes: number
+  sha256: string
+  createdAt: string
+}
+
+export interface RecurrenceRule {
+  type: string
+  interval: number
+  daysOfWeek?: number[] | null
+  dayOfMonth?: number | null
+  endDate?: string | null
+}
+
+export type TaskStatus = 'todo' | 'completed' | 'canceled'
+
+export type ProjectStatus = 'active' | 'completed'
+
+export type RemainingTasks = 'complete' | 'cancel'
+
+export type BulkTaskAction = 'complete' | 'uncomplete' | 'delete'
+
+export type Priority = 'none' | 'low' | 'medium' | 'high'
+
+export interface CreateTaskInput {
+  title: string
+  description?: string | null
+  priority?: string | null
+  categoryId?: string | null
+  projectId?: string | null
+  headingId?: string | null
+  startDate?: string | null
+  dueDate?: string | null
+  reminderTime?: string | null
+  recurrenceRule?: RecurrenceRule | null
+  someday?: boolean | null
+}
+
+export interface UpdateTaskInput {
+  title?: string | null
+  description?: string | null
+  priority?: string | null
+  categoryId?: string | null
+  projectId?: string | null
+  headingId?: string | null
+  startDate?: string | null
+  dueDate?: string | null
+  reminderTime?: string | null
+  recurrenceRule?: RecurrenceRule | null
+  someday?: boolean | null
+}
+
+export interface CreateSubTaskInput {
+  title: string
+  description?: string | null
+  priority?: string | null
+  dueDate?: string | null
+  sortOrder?: number | null
+  parentId?: string | null
+}
+
+export interface UpdateSubTaskInput {
+  title?: string | null
+  description?: string | null
+  priority?: string | null
+  dueDate?: string | null
+  completed?: boolean | null
+  sortOrder?: number | null
+}
+
+export interface CreateCategoryInput {
+  name: string
+  color?: string | null
+}
+
+export interface UpdateCategoryInput {
+  name?: string | null
+  color?: string | null
+  sortOrder?: number | null
+}
+
+export interface CreateProjectInput {
+  title: string
+  notes?: string | null
+  categoryId?: string | null
+  deadline?: string | null
+}
+
+export interface UpdateProjectInput {
+  title?: string | null
+  notes?: string | null
+  categoryId?: string | null
+  deadline?: string | null
+  sortOrder?: number | null
+}
+
+export interface UpdateHeadingInput {
+  title?: string | null
+  sortOrder?: number | null
+}
+
+export interface CreateTimeEntryInput {
+  taskId: string
+  startedAt: string
+  endedAt: string
+  note?: string | null
+}
+
+export interface UpdateTimeEntryInput {
+  startedAt?: string | null
+  /** Can't be cleared; use `start_timer` to run a timer */
+  endedAt?: string | null
+  note?: string | null
+}
+
+export interface DuplicateTaskOptions {
+  /** Start the copy over as open; defaults to true */
+  resetCompletion?: boolean | null
+  /** Days to move every date of the copy by, e.g. 7 for the week after */
+  dateOffsetDays?: number | null
+}
+
+export interface BulkUpdateTasksInput {
+  ids: string[]
+  /** Field changes applied to every task, as in `update_task` */
+  changes?: UpdateTaskInput | null
+  addTagIds?: string[] | null
+  removeTagIds?: string[] | null
+  action?: string | null
+}
+
+export interface CreateTemplateInput {
+  name: string
+  title: string
+  description?: string | null
+  priority?: string | null
+  categoryId?: string | null
+  startOffsetDays?: number | null
+  dueOffsetDays?: number | null
+  tagIds?: string[] | null
+  subTasks?: TemplateSubTaskInput[] | null
+}
+
+export interface UpdateTemplateInput {
+  name?: string | null
+  title?: string | null
+  description?: string | null
+  priority?: string | null
+  categoryId?: string | null
+  startOffsetDays?: number | null
+  dueOffsetDays?: number | null
+  tagIds?: string[] | null
+  /** Replaces the whole tree */
+  subTasks?: TemplateSubTaskInput[] | null
+}
+
+export interface TemplateSubTaskInput {
+  title: string
+  description?: string | null
+  priority?: string | null
+  dueOffsetDays?: number | null
+  children?: TemplateSubTaskInput[] | null
+}
+
+export interface InstantiateTemplateInput {
+  /** Date the offsets count from; defaults to today */
+  baseDate?: string | null
+  /** Values for the template's placeholders; `date` defaults to the base date */
+  values?: Record<string, string> | null
+  projectId?: string | null
+  headingId?: string | null
+}
+
+export interface AddAttachmentInput {
+  /** Exactly one of `task_id` and `note_id` */
+  taskId?: string | null
+  noteId?: string | null
+  /** File to copy into the attachment store */
+  sourcePath: string
+}
+
+export type PomodoroPhase = 'work' | 'shortBreak' | 'longBreak'
+
+/** The Pomodoro phase in progress. */
+export interface PomodoroStatus {
+  phase: PomodoroPhase
+  taskId?: string | null
+  startedAt: string
+  endsAt: string
+  /** Work sessions finished since the last long break */
+  completedInCycle: number
+}
+
+/** A Pomodoro work session, finished or stopped early. */
+export interface FocusSession {
+  id: string
+  taskId?: string | null
+  startedAt: string
+  endedAt: string
+  durationSeconds: number
+  /** Whether it ran its full length */
+  completed: boolean
+  createdAt: string
+}
+
+export interface TaskFilter {
+  categoryId?: string | null
+  tagIds?: string[] | null
+  priority?: string | null
+  status?: string | null
+  dueDateFrom?: string | null
+  dueDateTo?: string | null
+  sortBy?: string | null
+  sortOrder?: string | null
+}
+
+export type ViewKind = 'today' | 'upcoming' | 'anytime' | 'someday' | 'logbook'
+
+export interface StatsSummary {
+  totalTasks: number
+  completedTasks: number
+  completionRate: number
+  periodStart: string
+  periodEnd: string
+}
+
+export interface DailyTrend {
+  date: string
+  completed: number
+  created: number
+}
+
+/** Time logged in entries started between `from` and `to` (inclusive dates). */
+export interface TimeStats {
+  from: string
+  to: string
+  totalSeconds: number
+  /** Every date in the range, oldest first */
+  byDay: TimeByDay[]
+  byCategory: TimeByGroup[]
+  /**
+   * A task's time counts toward each of its tags, so these can add up to more
+   * than `total_seconds`
+   */
+  byTag: TimeByGroup[]
+}
+
+export interface TimeByDay {
+  date: string
+  seconds: number
+}
+
+/** Finished Pomodoro work sessions per day. */
+export interface FocusTrend {
+  date: string
+  pomodoros: number
+  focusSeconds: number
+}
+
+/**
+ * Finished Pomodoro work sessions per category; `id` and `name` are `None` for
+ * sessions without a categorized task.
+ */
+export interface FocusByCategory {
+  id?: string | null
+  name?: string | null
+  pomodoros: number
+  focusSeconds: number
+}
+
+/** Time for one category or tag; `id` and `name` are `None` for tasks without one. */
+export interface TimeByGroup {
+  id?: string | null
+  name?: string | null
+  seconds: number
+}
+
+export interface ReorderTaskItem {
+  id: string
+  sortOrder: number
+}
+
+/** Machine-readable error category, serialized as e.g. `"NOT_FOUND"`. */
+export type ErrorCode = 'DB_ERROR' | 'NOT_FOUND' | 'VALIDATION_ERROR' | 'SERIALIZE_ERROR' | 'IO_ERROR' | 'CONFLICT'
+
+/**
+ * Error returned by services and commands. Tauri serializes it as the rejection
+ * value of `invoke`, so the frontend receives `{ code, message, details? }`.
+ */
+export interface AppError {
+  code: ErrorCode
+  message: string
+  details?: Record<string, string> | null
+}
+
+export interface CompleteTaskResult {
+  completedTask: Task
+  nextTask?: Task | null
+  /** Tasks that were waiting only on the completed task */
+  unblockedTasks: Task[]
+}
+
+export interface BulkUpdateResult {
+  /** False when any task failed, in which case nothing was written */
+  applied: boolean
+  /** One per distinct id, in the order given */
+  results: BulkTaskResult[]
+}
+
+export interface BulkTaskResult {
+  id: string
+  /** The task as it now is; absent when it was deleted or nothing was applied */
+  task?: Task | null
+  /** The next occurrence spawned by completing a recurring task */
+  nextTask?: Task | null
+  error?: AppError | null
+}
+
+export interface Note {
+  id: string
+  title: string
+  content: string
+  isPinned: boolean
+  sortOrder: number
+  createdAt: string
+  updatedAt: string
+}
+
+export interface CreateNoteInput {
+  title?: string | null
+  content?: string | null
+}
+
+export interface UpdateNoteInput {
+  title?: string | null
+  content?: string | null
+  isPinned?: boolean | null
+}
+
+export interface ExportData {
+  version: number
+  exportedAt: string
+  tasks: ExportTaskRow[]
+  subTasks: ExportSubTaskRow[]
+  categories: Category[]
+  tags: Tag[]
+  taskTags: TaskTagRow[]
+  notes?: Note[]
+  taskDependencies?: TaskDependencyRow[]
+  projects?: ExportProjectRow[]
+  headings?: Heading[]
+  timeEntries?: TimeEntry[]
+  focusSessions?: FocusSession[]
+  templates?: TaskTemplate[]
+  attachments?: Attachment[]
+  /** File contents, present only in a bundle exported with attachments */
+  attachmentFiles?: AttachmentFile[]
+}
+
+export interface AttachmentFile {
+  sha256: string
+  data: string
+}
+
+export interface ExportTaskRow {
+  id: string
+  title: string
+  description?: string | null
+  status: TaskStatus
+  priority: Priority
+  categoryId?: string | null
+  projectId?: string | null
+  headingId?: string | null
+  startDate?: string | null
+  dueDate?: string | null
+  reminderTime?: string | null
+  recurrenceRule?: string | null
+  someday?: boolean
+  completedAt?: string | null
+  archivedAt?: string | null
+  sortOrder: number
+  createdAt: string
+  updatedAt: string
+}
+
+export interface ExportSubTaskRow {
+  id: string
+  taskId: string
+  parentId?: string | null
+  title: string
+  description?: string | null
+  priority: Priority
+  dueDate?: string | null
+  completed: boolean
+  sortOrder: number
+  createdAt: string
+}
+
+export interface TaskTagRow {
+  taskId: string
+  tagId: string
+}
+
+export interface ExportProjectRow {
+  id: string
+  categoryId?: string | null
+  title: string
+  notes?: string | null
+  deadline?: string | null
+  status: ProjectStatus
+  completedAt?: string | null
+  sortOrder: number
+  createdAt: string
+  updatedAt: string
+}
+
+export interface TaskDependencyRow {
+  taskId: string
+  dependsOnId: string
+  createdAt: string
+}
+
+export interface BackupInfo {
+  fileName: string
+  kind: string
+  sizeBytes: number
+  createdAt: string
+}
+
+export interface AppSettings {
+  /** Snapshots kept of each kind (automatic, manual, pre-migration, pre-restore) */
+  backupKeep: number
+  backupIntervalHours: number
+  trashRetentionDays: number
+  /** Closed tasks move to the logbook this many days after closing */
+  logbookArchiveDays: number
+  pomodoroWorkMinutes: number
+  pomodoroShortBreakMinutes: number
+  pomodoroLongBreakMinutes: number
+  /** Every this many finished work sessions, the break is a long one */
+  pomodoroLongBreakEvery: number
+}
+
+export interface UpdateSettingsInput {
+  backupKeep?: number | null
+  backupIntervalHours?: number | null
+  trashRetentionDays?: number | null
+  logbookArchiveDays?: number | null
+  pomodoroWorkMinutes?: number | null
+  pomodoroShortBreakMinutes?: number | null
+  pomodoroLongBreakMinutes?: number | null
+  pomodoroLongBreakEvery?: number | null
+}
+
+export interface DatabaseReport {
+  ok: boolean
+  integrityErrors: string[]
+  foreignKeyViolations: ForeignKeyViolation[]
+  orphanedSubTasks: string[]
+  invalidRecurrenceRules: string[]
+  invalidDates: InvalidDateField[]
+}
+
+export interface ForeignKeyViolation {
+  table: string
+  rowId: number
+  parent: string
+}
+
+export interface InvalidDateField {
+  table: string
+  id: string
+  field: string
+  value: string
+}
+
+export interface RepairReport {
+  removedRows: number
+  clearedReferences: number
+  reattachedSubTasks: number
+  clearedRecurrenceRules: number
+  clearedDates: number
+  remaining: DatabaseReport
+}
+
+export interface TrashItem {
+  entityType: string
+  id: string
+  title: string
+  taskId?: string | null
+  deletedAt: string
+}
+
+/**
+ * One page of closed tasks, archived or not, grouped by the local date they were
+ * closed on.
+ */
+export interface LogbookPage {
+  groups: LogbookGroup[]
+  page: number
+  pageSize: number
+  /** Closed tasks across all pages */
+  total: number
+  hasMore: boolean
+}
+
+export interface LogbookGroup {
+  date: string
+  tasks: Task[]
+}
+
+export interface ChangeLogEntry {
+  id: number
+  entityType: string
+  entityId: string
+  operation: string
+  before?: unknown
+  after?: unknown
+  createdAt: string
+}
+
+export interface EntityChange {
+  entityType: string
+  entityId: string
+  change: string
+}
+
+export interface UndoResult {
+  action: string
+  changes: EntityChange[]
+  canUndo: boolean
+  canRedo: boolean
+}
+
+export interface Workspace {
+  id: string
+  name: string
+  createdAt: string
+  active: boolean
+}
+
+// ============================================================
+// Commands
+// ============================================================
+
+export const commands = {
+  createTask: (data: CreateTaskInput) =>
+    invoke<Task>('create_task', { data }),
+  updateTask: (id: string, data: UpdateTaskInput) =>
+    invoke<Task>('update_task', { id, data }),
+  deleteTask: (id: string) =>
+    invoke<void>('delete_task', { id }),
+  getTasks: (filter?: TaskFilter | null) =>
+    invoke<Task[]>('get_tasks', { filter }),
+  getTaskById: (id: string) =>
+    invoke<Task | null>('get_task_by_id', { id }),
+  getView: (kind: string) =>
+    invoke<Task[]>('get_view', { kind }),
+  completeTask: (id: string) =>
+    invoke<CompleteTaskResult>('complete_task', { id }),
+  uncompleteTask: (id: string) =>
+    invoke<Task>('uncomplete_task', { id }),
+  reorderTasks: (items: ReorderTaskItem[]) =>
+    invoke<void>('reorder_tasks', { items }),
+  duplicateTask: (id: string, options?: DuplicateTaskOptions | null) =>
+    invoke<Task>('duplicate_task', { id, options }),
+  bulkUpdateTasks: (data: BulkUpdateTasksInput) =>
+    invoke<BulkUpdateResult>('bulk_update_tasks', { data }),
+  createSubTask: (taskId: string, data: CreateSubTaskInput, parentId?: string | null) =>
+    invoke<SubTask>('create_sub_task', { taskId, data, parentId }),
+  updateSubTask: (id: string, data: UpdateSubTaskInput) =>
+    invoke<SubTask>('update_sub_task', { id, data }),
+  deleteSubTask: (id: string) =>
+    invoke<void>('delete_sub_task', { id }),
+  moveSubTask: (id: string, newTaskId: string, newParentId?: string | null, position?: number | null) =>
+    invoke<SubTask>('move_sub_task', { id, newTaskId, newParentId, position }),
+  promoteSubTask: (id: string) =>
+    invoke<Task>('promote_sub_task', { id }),
+  demoteTask: (taskId: string, targetTaskId: string, parentId?: string | null) =>
+    invoke<SubTask>('demote_task', { taskId, targetTaskId, parentId }),
+  createCategory: (data: CreateCategoryInput) =>
+    invoke<Category>('create_category', { data }),
+  updateCategory: (id: string, data: UpdateCategoryInput) =>
+    invoke<Category>('update_category', { id, data }),
+  deleteCategory: (id: string) =>
+    invoke<void>('delete_category', { id }),
+  getCategories: () =>
+    invoke<Category[]>('get_categories'),
+  createTag: (name: string, color?: string | null) =>
+    invoke<Tag>('create_tag', { name, color }),
+  updateTag: (id: string, name: string, color?: string | null) =>
+    invoke<Tag>('update_tag', { id, name, color }),
+  deleteTag: (id: string) =>
+    invoke<void>('delete_tag', { id }),
+  getTags: () =>
+    invoke<Tag[]>('get_tags'),
+  addTagToTask: (taskId: string, tagId: string) =>
+    invoke<void>('add_tag_to_task', { taskId, tagId }),
+  removeTagFromTask: (taskId: string, tagId: string) =>
+    invoke<void>('remove_tag_from_task', { taskId, tagId }),
+  addTaskDependency: (taskId: string, dependsOnId: string) =>
+    invoke<void>('add_task_dependency', { taskId, dependsOnId }),
+  removeTaskDependency: (taskId: string, dependsOnId: string) =>
+    invoke<void>('remove_task_dependency', { taskId, dependsOnId }),
+  createProject: (data: CreateProjectInput) =>
+    invoke<Project>('create_project', { data }),
+  updateProject: (id: string, data: UpdateProjectInput) =>
+    invoke<Project>('update_project', { id, data }),
+  deleteProject: (id: string) =>
+    invoke<void>('delete_project', { id }),
+  getProjects: () =>
+    invoke<Project[]>('get_projects'),
+  getProjectById: (id: string) =>
+    invoke<Project | null>('get_project_by_id', { id }),
+  completeProject: (id: string, remainingTasks?: string | null) =>
+    invoke<Project>('complete_project', { id, remainingTasks }),
+  reopenProject: (id: string) =>
+    invoke<Project>('reopen_project', { id }),
+  createHeading: (projectId: string, title: string) =>
+    invoke<Heading>('create_heading', { projectId, title }),
+  updateHeading: (id: string, data: UpdateHeadingInput) =>
+    invoke<Heading>('update_heading', { id, data }),
+  deleteHeading: (id: string) =>
+    invoke<void>('delete_heading', { id }),
+  getTemplates: () =>
+    invoke<TaskTemplate[]>('get_templates'),
+  createTemplate: (data: CreateTemplateInput) =>
+    invoke<TaskTemplate>('create_template', { data }),
+  createTemplateFromTask: (taskId: string, name: string, baseDate?: string | null) =>
+    invoke<TaskTemplate>('create_template_from_task', { taskId, name, baseDate }),
+  updateTemplate: (id: string, data: UpdateTemplateInput) =>
+    invoke<TaskTemplate>('update_template', { id, data }),
+  deleteTemplate: (id: string) =>
+    invoke<void>('delete_template', { id }),
+  instantiateTemplate: (id: string, data?: InstantiateTemplateInput | null) =>
+    invoke<Task>('instantiate_template', { id, data }),
+  startTimer: (taskId: string, note?: string | null) =>
+    invoke<TimeEntry>('start_timer', { taskId, note }),
+  stopTimer: () =>
+    invoke<TimeEntry | null>('stop_timer'),
+  getRunningTimer: () =>
+    invoke<TimeEntry | null>('get_running_timer'),
+  getTimeEntries: (taskId: string) =>
+    invoke<TimeEntry[]>('get_time_entries', { taskId }),
+  createTimeEntry: (data: CreateTimeEntryInput) =>
+    invoke<TimeEntry>('create_time_entry', { data }),
+  updateTimeEntry: (id: string, data: UpdateTimeEntryInput) =>
+    invoke<TimeEntry>('update_time_entry', { id, data }),
+  deleteTimeEntry: (id: string) =>
+    invoke<void>('delete_time_entry', { id }),
+  startPomodoro: (taskId?: string | null) =>
+    invoke<PomodoroStatus>('start_pomodoro', { taskId }),
+  stopPomodoro: () =>
+    invoke<FocusSession | null>('stop_pomodoro'),
+  getPomodoroStatus: () =>
+    invoke<PomodoroStatus | null>('get_pomodoro_status'),
+  getFocusSessions: (taskId?: string | null) =>
+    invoke<FocusSession[]>('get_focus_sessions', { taskId }),
+  searchTasks: (query?: string | null, filters?: TaskFilter | null) =>
+    invoke<Task[]>('search_tasks', { query, filters }),
+  scheduleNotification: (taskId: string, title: string, reminderTime: string) =>
+    invoke<void>('schedule_notification', { taskId, title, reminderTime }),
+  cancelNotification: (taskId: string) =>
+    invoke<void>('cancel_notification', { taskId }),
+  getStats: (period: string) =>
+    invoke<StatsSummary>('get_stats', { period }),
+  getDailyTrend: (days: number) =>
+    invoke<DailyTrend[]>('get_daily_trend', { days }),
+  getFocusTrend: (days: number) =>
+    invoke<FocusTrend[]>('get_focus_trend', { days }),
+  getFocusByCategory: (days: number) =>
+    invoke<FocusByCategory[]>('get_focus_by_category', { days }),
+  getTimeStats: (from: string, to: string) =>
+    invoke<TimeStats>('get_time_stats', { from, to }),
+  exportData: (includeAttachments?: boolean | null) =>
+    invoke<string>('export_data', { includeAttachments }),
+  importData: (jsonStr: string) =>
+    invoke<void>('import_data', { jsonStr }),
+  listBackups: () =>
+    invoke<BackupInfo[]>('list_backups'),
+  createBackup: () =>
+    invoke<BackupInfo>('create_backup'),
+  restoreBackup: (fileName: string) =>
+    invoke<void>('restore_backup', { fileName }),
+  getTrash: () =>
+    invoke<TrashItem[]>('get_trash'),
+  restoreFromTrash: (entityType: string, id: string) =>
+    invoke<void>('restore_from_trash', { entityType, id }),
+  emptyTrash: () =>
+    invoke<number>('empty_trash'),
+  getLogbook: (page?: number | null, pageSize?: number | null) =>
+    invoke<LogbookPage>('get_logbook', { page, pageSize }),
+  unarchiveTask: (id: string) =>
+    invoke<Task>('unarchive_task', { id }),
+  undo: () =>
+    invoke<UndoResult | null>('undo'),
+  redo: () =>
+    invoke<UndoResult | null>('redo'),
+  getEntityHistory: (entity: string, id: string) =>
+    invoke<ChangeLogEntry[]>('get_entity_history', { entity, id }),
+  listWorkspaces: () =>
+    invoke<Workspace[]>('list_workspaces'),
+  createWorkspace: (name: string) =>
+    invoke<Workspace>('create_workspace', { name }),
+  renameWorkspace: (id: string, name: string) =>
+    invoke<Workspace>('rename_workspace', { id, name }),
+  deleteWorkspace: (id: string) =>
+    invoke<void>('delete_workspace', { id }),
+  switchWorkspace: (id: string) =>
+    invoke<Workspace>('switch_workspace', { id }),
+  checkDatabase: () =>
+    invoke<DatabaseReport>('check_database'),
+  repairDatabase: () =>
+    invoke<RepairReport>('repair_database'),
+  getSettings: () =>
+    invoke<AppSettings>('get_settings'),
+  updateSettings: (data: UpdateSettingsInput) =>
+    invoke<AppSettings>('update_settings', { data }),
+  createNote: (data: CreateNoteInput) =>
+    invoke<Note>('create_note', { data }),
+  updateNote: (id: string, data: UpdateNoteInput) =>
+    invoke<Note>('update_note', { id, data }),
+  deleteNote: (id: string) =>
+    invoke<void>('delete_note', { id }),
+  getNotes: () =>
+    invoke<Note[]>('get_notes'),
+  getNoteById: (id: string) =>
+    invoke<Note | null>('get_note_by_id', { id }),
+  searchNotes: (query?: string | null) =>
+    invoke<Note[]>('search_notes', { query }),
+  getAttachments: (taskId?: string | null, noteId?: string | null) =>
+    invoke<Attachment[]>('get_attachments', { taskId, noteId }),
+  addAttachment: (data: AddAttachmentInput) =>
+    invoke<Attachment>('add_attachment', { data }),
+  deleteAttachment: (id: string) =>
+    invoke<void>('delete_attachment', { id }),
+  getAttachmentPath: (id: string) =>
+    invoke<string>('get_attachment_path', { id }),
+  setCompactMode: (compact: boolean) =>
+    invoke<void>('set_compact_mode', { compact })
+}
@@ -1,6 +1,8 @@
 // ============================================================
 // Shared Types for 小西瓜 Todo App
 // Used by both Main Process and Renderer Process
+// The IPC wire types are generated from Rust in ./bindings.ts;
+// the types here narrow some of them for the UI (e.g. RecurrenceType)
 // ============================================================
 
 // --- Enums / Literal Types ---